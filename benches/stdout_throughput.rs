@@ -0,0 +1,92 @@
+//! Measures how fast video data written to `stdout` reaches `JobHandle::take_output_reader()`.
+//!
+//! `/bin/sh` stands in for `HandBrakeCLI`, writing pseudo-random data with a progress line after
+//! every MiB, so the time is mostly spent searching the data for progress lines.
+//!
+//! Run with `cargo bench --bench stdout_throughput`.
+
+#[cfg(unix)]
+#[tokio::main]
+async fn main() {
+    use std::time::{Duration, Instant};
+
+    use futures::StreamExt;
+    use handbrake::{JobBuilder, JobEvent, OutputDestination};
+    use tokio::io::AsyncReadExt;
+
+    const MIB: usize = 1024 * 1024;
+    const SIZE: usize = 256 * MIB;
+    const RUNS: usize = 5;
+
+    let path = std::env::temp_dir().join(format!("handbrake-rs-bench-{}", std::process::id()));
+    std::fs::write(&path, video_data(SIZE, MIB)).expect("Failed to write the bench data");
+
+    for (buffer_capacity, chunk_size) in [(None, None), (Some(4 * MIB), Some(4 * MIB))] {
+        let mut best = Duration::MAX;
+        for _ in 0..RUNS {
+            let mut job = JobBuilder::new("/bin/sh".into(), "in.mkv".into(), OutputDestination::Stdout);
+            if let Some(capacity) = buffer_capacity {
+                job = job.stdout_buffer_capacity(capacity);
+            }
+            if let Some(size) = chunk_size {
+                job = job.stdout_chunk_size(size);
+            }
+            let script = format!("cat '{}'", path.display());
+            let mut handle = job.start_with_args(["-c".to_string(), script]).expect("Failed to spawn /bin/sh");
+            let mut reader = handle.take_output_reader().expect("Output goes to stdout");
+            // The progress events must be drained for the job to keep going.
+            let (_, mut events) = handle.split();
+            let done = tokio::spawn(async move {
+                while let Some(event) = events.next().await {
+                    if let JobEvent::Done(result) = event {
+                        return result.is_ok();
+                    }
+                }
+                false
+            });
+
+            let started = Instant::now();
+            let mut buf = vec![0; MIB];
+            let mut read = 0;
+            loop {
+                match reader.read(&mut buf).await.expect("Failed to read the output") {
+                    0 => break,
+                    n => read += n,
+                }
+            }
+            best = best.min(started.elapsed());
+            assert!(done.await.unwrap());
+            assert!(read > 0 && read < SIZE, "progress lines should be stripped");
+        }
+        let throughput = SIZE as f64 / MIB as f64 / best.as_secs_f64();
+        println!(
+            "buffer capacity {:?}, chunk size {:?}: {:.0} MiB/s (best of {})",
+            buffer_capacity, chunk_size, throughput, RUNS
+        );
+    }
+    let _ = std::fs::remove_file(&path);
+}
+
+/// Pseudo-random bytes with a progress line every `interval` bytes.
+#[cfg(unix)]
+fn video_data(size: usize, interval: usize) -> Vec<u8> {
+    let mut data = Vec::with_capacity(size);
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    let mut next_progress = 0;
+    while data.len() < size {
+        if data.len() >= next_progress {
+            next_progress += interval;
+            let percentage = data.len() as f64 / size as f64 * 100.0;
+            data.extend_from_slice(format!("Encoding: task 1 of 1, {:.2} %\r", percentage.min(99.99)).as_bytes());
+        }
+        // xorshift64
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        data.extend_from_slice(&state.to_le_bytes());
+    }
+    data
+}
+
+#[cfg(not(unix))]
+fn main() {}
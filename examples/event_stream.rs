@@ -3,7 +3,7 @@ use std::time::{Duration, Instant};
 use clap::Parser;
 use futures::StreamExt;
 use handbrake::{HandBrake, InputSource, JobEvent, OutputDestination};
-use tracing::{error, info};
+use tracing::{error, info, warn};
 
 /// A simple example of using the event stream.
 #[derive(Parser, Debug)]
@@ -68,8 +68,15 @@ async fn main() {
     while let Some(event) = event_stream.next().await {
         match event {
             JobEvent::Config(config) => info!(?config, "Job config received"),
+            JobEvent::ScanSummary(summary) => info!(?summary, "Source scanned"),
             JobEvent::Progress(progress) => info!(?progress, "Progress update"),
             JobEvent::Log(log) => info!(?log, "Log message"),
+            JobEvent::Warning(diagnostic) => warn!(?diagnostic, "HandBrake warning"),
+            JobEvent::Error(diagnostic) => error!(?diagnostic, "HandBrake error"),
+            JobEvent::LowDiskSpace { free_bytes, threshold } => {
+                warn!(free_bytes, threshold, "Output volume is running out of space")
+            }
+            JobEvent::OutputSize { bytes, bytes_per_sec } => info!(bytes, bytes_per_sec, "Output size"),
             JobEvent::Done(result) => {
                 info!(?result, "Job finished");
                 break;
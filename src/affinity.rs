@@ -0,0 +1,82 @@
+//! Pinning the `HandBrakeCLI` process to a set of CPU cores.
+
+use std::io;
+
+use tokio::process::{Child, Command};
+
+use crate::error::Error;
+
+fn unsupported(reason: &str) -> Error {
+    Error::ProcessSpawnFailed {
+        source: io::Error::new(io::ErrorKind::Unsupported, reason),
+    }
+}
+
+/// Configures the command so that the spawned process only runs on the given cores.
+///
+/// On Linux, the affinity is set between fork and exec, so it is in place before
+/// `HandBrakeCLI` starts. On Windows, it is applied right after spawning by `apply()`.
+#[cfg(target_os = "linux")]
+pub(crate) fn configure(cmd: &mut Command, cores: &[usize]) -> Result<(), Error> {
+    use nix::sched::{CpuSet, sched_setaffinity};
+    use nix::unistd::Pid;
+
+    let mut cpu_set = CpuSet::new();
+    for &core in cores {
+        cpu_set
+            .set(core)
+            .map_err(|_| unsupported("CPU core index is out of range for this platform"))?;
+    }
+
+    // SAFETY: the closure only performs the `sched_setaffinity` system call, which is
+    // async-signal-safe, on data prepared before forking.
+    unsafe {
+        cmd.pre_exec(move || {
+            sched_setaffinity(Pid::from_raw(0), &cpu_set).map_err(io::Error::from)
+        });
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+pub(crate) fn configure(_cmd: &mut Command, cores: &[usize]) -> Result<(), Error> {
+    mask(cores).map(|_| ())
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+pub(crate) fn configure(_cmd: &mut Command, _cores: &[usize]) -> Result<(), Error> {
+    Err(unsupported("CPU affinity is not supported on this platform"))
+}
+
+/// Applies the affinity to a freshly spawned process, where it can't be set before exec.
+#[cfg(windows)]
+pub(crate) fn apply(child: &Child, cores: &[usize]) -> Result<(), Error> {
+    use windows_sys::Win32::System::Threading::SetProcessAffinityMask;
+
+    let handle = child
+        .raw_handle()
+        .ok_or_else(|| unsupported("Process already exited"))?;
+    let result = unsafe { SetProcessAffinityMask(handle, mask(cores)?) };
+    if result == 0 {
+        // A non-zero value indicates success.
+        return Err(Error::ProcessSpawnFailed {
+            source: io::Error::last_os_error(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(not(windows))]
+pub(crate) fn apply(_child: &Child, _cores: &[usize]) -> Result<(), Error> {
+    Ok(())
+}
+
+#[cfg(windows)]
+fn mask(cores: &[usize]) -> Result<usize, Error> {
+    cores.iter().try_fold(0usize, |mask, &core| {
+        1usize
+            .checked_shl(core as u32)
+            .map(|bit| mask | bit)
+            .ok_or_else(|| unsupported("CPU core index is out of range for this platform"))
+    })
+}
@@ -0,0 +1,223 @@
+//! Applying one job configuration to many input files.
+
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::process::ExitStatus;
+
+use crate::error::Error;
+use crate::handle::JobHandle;
+use crate::job::{InputSource, JobBuilder, OutputDestination};
+
+type Configure = Box<dyn Fn(JobBuilder) -> JobBuilder + Send + Sync>;
+
+/// The output naming and options shared by the jobs of a `BatchBuilder`, `Ladder`, or
+/// `ChapterSplit`.
+pub(crate) struct JobTemplate {
+    handbrake_path: PathBuf,
+    output_dir: Option<PathBuf>,
+    extension: Option<String>,
+    format: Option<String>,
+    configure: Vec<Configure>,
+}
+
+impl JobTemplate {
+    pub(crate) fn new(handbrake_path: PathBuf) -> Self {
+        JobTemplate {
+            handbrake_path,
+            output_dir: None,
+            extension: None,
+            format: None,
+            configure: Vec::new(),
+        }
+    }
+
+    pub(crate) fn output_dir(&mut self, dir: PathBuf) {
+        self.output_dir = Some(dir);
+    }
+
+    pub(crate) fn extension(&mut self, extension: String) {
+        self.extension = Some(extension);
+    }
+
+    /// Sets the container of every job, which the extension of the outputs defaults to.
+    pub(crate) fn format(&mut self, format: String) {
+        self.format = Some(format.clone());
+        self.configure(move |job| job.format(format.clone()));
+    }
+
+    pub(crate) fn configure(&mut self, f: impl Fn(JobBuilder) -> JobBuilder + Send + Sync + 'static) {
+        self.configure.push(Box::new(f));
+    }
+
+    /// The extension set with `extension()`, or the container set with `format()`, or `"mp4"`.
+    fn output_extension(&self) -> &str {
+        self.extension
+            .as_deref()
+            .or_else(|| self.format.as_deref().map(|format| format.trim_start_matches("av_")))
+            .unwrap_or("mp4")
+    }
+
+    /// Returns the path of an output named `name` with the output extension, in the output
+    /// directory or else the directory of the input.
+    pub(crate) fn output_path(&self, input: &Path, name: &OsStr) -> PathBuf {
+        let dir = self
+            .output_dir
+            .as_deref()
+            .or_else(|| input.parent())
+            .unwrap_or_else(|| Path::new(""));
+        let mut name = name.to_os_string();
+        name.push(".");
+        name.push(self.output_extension());
+        dir.join(name)
+    }
+
+    /// Creates a job encoding `input` to `output`, with the options applied in the order they
+    /// were set.
+    pub(crate) fn job(&self, input: &Path, output: PathBuf) -> JobBuilder {
+        let job = JobBuilder::new(
+            self.handbrake_path.clone(),
+            InputSource::File(input.to_path_buf()),
+            OutputDestination::File(output),
+        );
+        self.configure.iter().fold(job, |job, f| f(job))
+    }
+}
+
+/// A builder for a set of jobs that share one configuration, one per input file.
+///
+/// The output of each job is named after its input, e.g., `movie.mkv` is encoded to
+/// `<output_dir>/movie.mp4`. Results are always returned in the order of the inputs.
+pub struct BatchBuilder {
+    inputs: Vec<PathBuf>,
+    template: JobTemplate,
+}
+
+impl BatchBuilder {
+    /// Creates a new `BatchBuilder` instance.
+    ///
+    /// This is typically called via `HandBrake::batch()`.
+    pub fn new(handbrake_path: PathBuf, inputs: impl IntoIterator<Item = impl Into<PathBuf>>) -> Self {
+        BatchBuilder {
+            inputs: inputs.into_iter().map(Into::into).collect(),
+            template: JobTemplate::new(handbrake_path),
+        }
+    }
+
+    /// Sets the directory the outputs are written to.
+    ///
+    /// Defaults to the directory of each input.
+    pub fn output_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.template.output_dir(dir.into());
+        self
+    }
+
+    /// Sets the file extension of the outputs.
+    ///
+    /// Defaults to the container set with `format()`, or `"mp4"`.
+    pub fn extension(mut self, extension: impl Into<String>) -> Self {
+        self.template.extension(extension.into());
+        self
+    }
+
+    /// Applies arbitrary `JobBuilder` options to every job in the batch.
+    ///
+    /// The closure is called once per input. Options are applied in the order they were set on
+    /// the batch, so the last call wins as with `JobBuilder`.
+    ///
+    /// e.g., `.configure(|job| job.subtitle_scan().timeout(Duration::from_secs(3600)))`
+    pub fn configure(
+        mut self,
+        f: impl Fn(JobBuilder) -> JobBuilder + Send + Sync + 'static,
+    ) -> Self {
+        self.template.configure(f);
+        self
+    }
+
+    /// Enables import of HandBrake GUI presets for every job. See `JobBuilder::import_gui_presets()`.
+    pub fn import_gui_presets(self, import_gui_presets: bool) -> Self {
+        self.configure(move |job| job.import_gui_presets(import_gui_presets))
+    }
+
+    /// Sets the preset for every job. See `JobBuilder::preset()`.
+    pub fn preset(self, preset: impl Into<String>) -> Self {
+        let preset = preset.into();
+        self.configure(move |job| job.preset(preset.clone()))
+    }
+
+    /// Sets the video codec for every job. See `JobBuilder::video_codec()`.
+    pub fn video_codec(self, codec: impl Into<String>) -> Self {
+        let codec = codec.into();
+        self.configure(move |job| job.video_codec(codec.clone()))
+    }
+
+    /// Sets the output container format for every job. See `JobBuilder::format()`.
+    pub fn format(mut self, format: impl Into<String>) -> Self {
+        self.template.format(format.into());
+        self
+    }
+
+    /// Overrides the audio codec of a track for every job. See `JobBuilder::audio_codec()`.
+    pub fn audio_codec(self, track: u32, codec: impl Into<String>) -> Self {
+        let codec = codec.into();
+        self.configure(move |job| job.audio_codec(track, codec.clone()))
+    }
+
+    /// Sets the constant quality for every job. See `JobBuilder::quality()`.
+    pub fn quality(self, quality: f32) -> Self {
+        self.configure(move |job| job.quality(quality))
+    }
+
+    /// Sets the average video bitrate for every job. See `JobBuilder::video_bitrate()`.
+    pub fn video_bitrate(self, kbps: u32) -> Self {
+        self.configure(move |job| job.video_bitrate(kbps))
+    }
+
+    /// Sets the output width for every job. See `JobBuilder::width()`.
+    pub fn width(self, width: u32) -> Self {
+        self.configure(move |job| job.width(width))
+    }
+
+    /// Sets the output height for every job. See `JobBuilder::height()`.
+    pub fn height(self, height: u32) -> Self {
+        self.configure(move |job| job.height(height))
+    }
+
+    /// Builds one `JobBuilder` per input, in the order of the inputs.
+    pub fn jobs(self) -> Vec<JobBuilder> {
+        self.inputs
+            .iter()
+            .map(|input| self.template.job(input, output_path(&self.template, input)))
+            .collect()
+    }
+
+    /// Starts all jobs at once in monitored mode, returning a `JobHandle` per input.
+    ///
+    /// Every job runs its own `HandBrakeCLI` process concurrently, so this is best suited to
+    /// small batches. Use `status()` to encode the inputs one after another.
+    pub fn start(self) -> Vec<Result<JobHandle, Error>> {
+        self.jobs().into_iter().map(JobBuilder::start).collect()
+    }
+
+    /// Executes the jobs one after another, returning the final `ExitStatus` per input.
+    ///
+    /// A job that fails to spawn doesn't stop the remaining jobs.
+    pub async fn status(self) -> Vec<Result<ExitStatus, Error>> {
+        let mut results = Vec::with_capacity(self.inputs.len());
+        for job in self.jobs() {
+            results.push(job.status().await);
+        }
+        results
+    }
+}
+
+/// Names the output after the input, without overwriting the input itself.
+fn output_path(template: &JobTemplate, input: &Path) -> PathBuf {
+    let stem = input.file_stem().unwrap_or_default();
+    let output = template.output_path(input, stem);
+    if output == input {
+        let mut name = OsString::from(stem);
+        name.push("-encoded");
+        return template.output_path(input, &name);
+    }
+    output
+}
@@ -0,0 +1,124 @@
+//! A blocking API for applications that don't use `tokio`.
+//!
+//! The blocking `HandBrake` owns a single-threaded `tokio` runtime and drives the asynchronous
+//! API on it, so jobs only make progress while one of its methods is running. Jobs are
+//! configured with the regular `JobBuilder`. The methods panic when called from within an
+//! asynchronous runtime.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use handbrake::blocking::HandBrake;
+//! use handbrake::{InputSource, JobEvent, OutputDestination};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let hb = HandBrake::new()?;
+//! let job = hb
+//!     .job(InputSource::from("input.mkv"), OutputDestination::from("output.mp4"))
+//!     .preset("Fast 1080p30");
+//! let result = hb.run(job, |event| {
+//!     if let JobEvent::Progress(p) = event {
+//!         println!("{:.2}%", p.percentage);
+//!     }
+//! });
+//! match result {
+//!     Ok(summary) => println!("Encoded in {:?}", summary.elapsed),
+//!     Err(failure) => eprintln!("Encoding failed: {}", failure.message),
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::path::PathBuf;
+use std::process::ExitStatus;
+
+use tokio::runtime::Runtime;
+
+use crate::error::Error;
+use crate::event::{FailureKind, JobEvent, JobFailure, JobSummary};
+use crate::job::{InputSource, JobBuilder, OutputDestination};
+
+/// The blocking counterpart of `handbrake::HandBrake`.
+#[derive(Debug)]
+pub struct HandBrake {
+    inner: crate::HandBrake,
+    runtime: Runtime,
+}
+
+impl HandBrake {
+    /// Creates a new `HandBrake` instance by searching for `HandBrakeCLI` in the system `PATH`.
+    ///
+    /// # Errors
+    ///
+    /// See `handbrake::HandBrake::new()`.
+    pub fn new() -> Result<Self, Error> {
+        let runtime = new_runtime()?;
+        let inner = runtime.block_on(crate::HandBrake::new())?;
+        Ok(Self { inner, runtime })
+    }
+
+    /// Creates a new `HandBrake` instance using a specific path to `HandBrakeCLI`.
+    ///
+    /// # Errors
+    ///
+    /// See `handbrake::HandBrake::new_with_path()`.
+    pub fn new_with_path(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let runtime = new_runtime()?;
+        let inner = runtime.block_on(crate::HandBrake::new_with_path(path))?;
+        Ok(Self { inner, runtime })
+    }
+
+    /// Returns the version string of the `HandBrakeCLI` executable.
+    pub fn version(&self) -> &str {
+        self.inner.version()
+    }
+
+    /// Creates a new `JobBuilder` to configure an encoding job.
+    ///
+    /// Run the configured job with `status()` or `run()` of this instance.
+    pub fn job(&self, input: InputSource, output: OutputDestination) -> JobBuilder {
+        self.inner.job(input, output)
+    }
+
+    /// Executes the job and blocks until it completes, returning only the final `ExitStatus`.
+    ///
+    /// See `JobBuilder::status()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the process could not be spawned.
+    pub fn status(&self, job: JobBuilder) -> Result<ExitStatus, Error> {
+        self.runtime.block_on(job.status())
+    }
+
+    /// Executes the job in monitored mode, calling `on_event` for every `JobEvent` until it
+    /// completes.
+    ///
+    /// See `JobBuilder::start()` and `JobHandle::wait()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `JobFailure` if the process could not be spawned or did not complete
+    /// successfully.
+    pub fn run(
+        &self,
+        job: JobBuilder,
+        on_event: impl FnMut(&JobEvent),
+    ) -> Result<JobSummary, JobFailure> {
+        self.runtime.block_on(async {
+            let handle = job.start().map_err(|e| JobFailure {
+                message: format!("Failed to start: {}", e),
+                exit_code: None,
+                kind: FailureKind::Process,
+            })?;
+            handle.wait_inspect(on_event).await
+        })
+    }
+}
+
+fn new_runtime() -> Result<Runtime, Error> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| Error::ProcessSpawnFailed { source: e })
+}
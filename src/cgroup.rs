@@ -0,0 +1,152 @@
+//! Transient cgroups for containing the `HandBrakeCLI` process (Linux, cgroup v2).
+
+use std::ffi::CString;
+use std::fs;
+use std::io;
+use std::os::unix::ffi::OsStringExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use nix::fcntl::{OFlag, open};
+use nix::sys::stat::Mode;
+use tokio::process::Command;
+
+/// Mount point of the unified cgroup v2 hierarchy.
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// The period `cpu.max` quotas are expressed against, in microseconds.
+const CPU_PERIOD_US: u64 = 100_000;
+
+/// Distinguishes the cgroups of concurrent jobs spawned by the same process.
+static NEXT_CGROUP_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// Resource limits applied to the `HandBrakeCLI` process through a transient cgroup.
+///
+/// Requires a cgroup v2 hierarchy where the `parent` cgroup is writable by the current user and
+/// has the `cpu` and `memory` controllers available, e.g. a systemd delegated scope.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CgroupLimits {
+    /// The CPU time the process may use, as a number of CPUs (e.g., `2.5`).
+    pub cpu_quota: Option<f64>,
+    /// The maximum memory the process may use, in bytes.
+    pub memory_max: Option<u64>,
+    /// The cgroup under which the transient cgroup is created, as a path below `/sys/fs/cgroup`.
+    /// Defaults to the cgroup of the current process.
+    pub parent: Option<PathBuf>,
+}
+
+/// A cgroup created for a single job, removed again when dropped.
+#[derive(Debug)]
+pub(crate) struct TransientCgroup {
+    path: PathBuf,
+}
+
+impl TransientCgroup {
+    /// Creates a cgroup with the given limits.
+    pub(crate) fn create(limits: &CgroupLimits) -> io::Result<Self> {
+        let parent = match &limits.parent {
+            Some(parent) => parent.clone(),
+            None => current_cgroup()?,
+        };
+        // The controllers may already be enabled by whoever delegated the parent to us.
+        let _ = fs::write(parent.join("cgroup.subtree_control"), "+cpu +memory");
+
+        let name = format!(
+            "handbrake-rs-{}-{}",
+            std::process::id(),
+            NEXT_CGROUP_ID.fetch_add(1, Ordering::Relaxed)
+        );
+        fs::create_dir(parent.join(&name))?;
+        let cgroup = TransientCgroup {
+            path: parent.join(name),
+        };
+
+        if let Some(cpu_quota) = limits.cpu_quota {
+            fs::write(cgroup.path.join("cpu.max"), cpu_max(cpu_quota))?;
+        }
+        if let Some(memory_max) = limits.memory_max {
+            fs::write(cgroup.path.join("memory.max"), memory_max.to_string())?;
+        }
+        Ok(cgroup)
+    }
+
+    /// Configures the command so that the spawned process joins the cgroup before exec.
+    pub(crate) fn configure(&self, cmd: &mut Command) -> io::Result<()> {
+        let procs = CString::new(self.path.join("cgroup.procs").into_os_string().into_vec())?;
+
+        // SAFETY: the closure only performs `open` and `write` system calls, which are
+        // async-signal-safe, on data prepared before forking.
+        unsafe {
+            cmd.pre_exec(move || {
+                let fd = open(procs.as_c_str(), OFlag::O_WRONLY, Mode::empty())?;
+                // Writing "0" moves the writing process itself.
+                nix::unistd::write(&fd, b"0")?;
+                Ok(())
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Drop for TransientCgroup {
+    fn drop(&mut self) {
+        // Only succeeds once the process has exited, as cgroups with members can't be removed.
+        let _ = fs::remove_dir(&self.path);
+    }
+}
+
+/// Formats a `cpu.max` value for the given number of CPUs.
+fn cpu_max(cpu_quota: f64) -> String {
+    let quota = (cpu_quota * CPU_PERIOD_US as f64).round().max(1.0) as u64;
+    format!("{} {}", quota, CPU_PERIOD_US)
+}
+
+/// Resolves the cgroup of the current process in the cgroup v2 hierarchy.
+fn current_cgroup() -> io::Result<PathBuf> {
+    let content = fs::read_to_string("/proc/self/cgroup")?;
+    let relative = parse_proc_cgroup(&content).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "The current process is not in a cgroup v2 hierarchy",
+        )
+    })?;
+    Ok(Path::new(CGROUP_ROOT).join(relative.trim_start_matches('/')))
+}
+
+/// Extracts the cgroup v2 path from the contents of `/proc/self/cgroup`.
+fn parse_proc_cgroup(content: &str) -> Option<&str> {
+    content.lines().find_map(|line| line.strip_prefix("0::"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cpu_max, parse_proc_cgroup};
+
+    #[test]
+    fn test_cpu_max() {
+        assert_eq!(cpu_max(1.0), "100000 100000");
+        assert_eq!(cpu_max(2.5), "250000 100000");
+        assert_eq!(cpu_max(0.0), "1 100000");
+    }
+
+    #[test]
+    fn test_parse_proc_cgroup_unified() {
+        let content = "0::/user.slice/user-1000.slice/session-2.scope\n";
+        assert_eq!(
+            parse_proc_cgroup(content),
+            Some("/user.slice/user-1000.slice/session-2.scope")
+        );
+    }
+
+    #[test]
+    fn test_parse_proc_cgroup_hybrid() {
+        let content = "12:cpu,cpuacct:/\n1:name=systemd:/init.scope\n0::/init.scope\n";
+        assert_eq!(parse_proc_cgroup(content), Some("/init.scope"));
+    }
+
+    #[test]
+    fn test_parse_proc_cgroup_v1_only() {
+        let content = "12:cpu,cpuacct:/\n1:name=systemd:/init.scope\n";
+        assert_eq!(parse_proc_cgroup(content), None);
+    }
+}
@@ -0,0 +1,88 @@
+//! Running jobs in sequence, where each job depends on the previous one.
+
+use crate::event::{FailureKind, JobFailure, JobSummary};
+use crate::job::JobBuilder;
+
+/// A sequence of jobs where each job only starts after the previous one completed successfully.
+///
+/// This models multi-stage pipelines, e.g., an encode followed by a remux of its output. If a
+/// job fails, the remaining jobs are not started.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use handbrake::{HandBrake, InputSource, OutputDestination};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let hb = HandBrake::new().await?;
+/// let encode = hb.job(InputSource::from("input.mkv"), OutputDestination::from("encoded.mkv"))
+///     .preset("Fast 1080p30");
+/// let remux = hb.job(InputSource::from("encoded.mkv"), OutputDestination::from("output.mp4"))
+///     .format("av_mp4");
+/// match encode.then(remux).run().await {
+///     Ok(summaries) => println!("Completed {} jobs", summaries.len()),
+///     Err(failure) => eprintln!("Job {} failed: {}", failure.stage, failure.failure.message),
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct JobChain {
+    jobs: Vec<JobBuilder>,
+}
+
+/// Details of a `JobChain` that stopped because one of its jobs failed.
+#[derive(Debug, Clone)]
+pub struct ChainFailure {
+    /// The index of the job that failed, in the order the jobs were added.
+    pub stage: usize,
+    /// Why the job failed.
+    pub failure: JobFailure,
+    /// The summaries of the jobs that completed before the failure.
+    pub completed: Vec<JobSummary>,
+}
+
+impl JobChain {
+    /// Creates a chain starting with the given job.
+    ///
+    /// This is typically called via `JobBuilder::then()`.
+    pub fn new(first: JobBuilder) -> Self {
+        JobChain { jobs: vec![first] }
+    }
+
+    /// Appends a job that starts once all previous jobs completed successfully.
+    pub fn then(mut self, next: JobBuilder) -> Self {
+        self.jobs.push(next);
+        self
+    }
+
+    /// Runs the jobs in monitored mode, one after another.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ChainFailure` for the first job that could not be spawned or did not complete
+    /// successfully. The jobs after it are not started.
+    pub async fn run(self) -> Result<Vec<JobSummary>, ChainFailure> {
+        let mut completed = Vec::with_capacity(self.jobs.len());
+        for (stage, job) in self.jobs.into_iter().enumerate() {
+            let result = match job.start() {
+                Ok(handle) => handle.wait().await,
+                Err(e) => Err(JobFailure {
+                    message: format!("Failed to start: {}", e),
+                    exit_code: None,
+                    kind: FailureKind::Process,
+                }),
+            };
+            match result {
+                Ok(summary) => completed.push(summary),
+                Err(failure) => {
+                    return Err(ChainFailure {
+                        stage,
+                        failure,
+                        completed,
+                    });
+                }
+            }
+        }
+        Ok(completed)
+    }
+}
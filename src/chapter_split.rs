@@ -0,0 +1,150 @@
+//! Splitting one input into an output file per chapter.
+
+use std::ffi::OsString;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+
+use crate::batch::JobTemplate;
+use crate::error::Error;
+use crate::handle::JobHandle;
+use crate::job::JobBuilder;
+
+/// The default name of the outputs, e.g., `concert-03.mp4`.
+const DEFAULT_NAME_TEMPLATE: &str = "{name}-{chapter}";
+
+/// A builder for one job per chapter of an input, e.g., to split a concert or TV disc.
+///
+/// The outputs are named with a template, where `{name}` is replaced by the file name of the
+/// input without its extension, and `{chapter}` by the zero-padded chapter number. Jobs are
+/// always returned in the order of the chapters.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use handbrake::HandBrake;
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let hb = HandBrake::new().await?;
+/// let results = hb
+///     .split_chapters("/dev/sr0", 1..=12)
+///     .name_template("Episode {chapter}")
+///     .preset("Fast 1080p30")
+///     .output_dir("episodes")
+///     .status()
+///     .await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ChapterSplit {
+    input: PathBuf,
+    chapters: RangeInclusive<u32>,
+    name_template: String,
+    template: JobTemplate,
+}
+
+impl ChapterSplit {
+    /// Creates a new `ChapterSplit` instance.
+    ///
+    /// This is typically called via `HandBrake::split_chapters()`.
+    pub fn new(handbrake_path: PathBuf, input: impl Into<PathBuf>, chapters: RangeInclusive<u32>) -> Self {
+        ChapterSplit {
+            input: input.into(),
+            chapters,
+            name_template: DEFAULT_NAME_TEMPLATE.to_string(),
+            template: JobTemplate::new(handbrake_path),
+        }
+    }
+
+    /// Sets the template for the file names of the outputs, without the extension.
+    ///
+    /// Defaults to `"{name}-{chapter}"`.
+    pub fn name_template(mut self, template: impl Into<String>) -> Self {
+        self.name_template = template.into();
+        self
+    }
+
+    /// Sets the directory the outputs are written to.
+    ///
+    /// Defaults to the directory of the input.
+    pub fn output_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.template.output_dir(dir.into());
+        self
+    }
+
+    /// Sets the file extension of the outputs.
+    ///
+    /// Defaults to the container set with `format()`, or `"mp4"`.
+    pub fn extension(mut self, extension: impl Into<String>) -> Self {
+        self.template.extension(extension.into());
+        self
+    }
+
+    /// Applies arbitrary `JobBuilder` options to every chapter.
+    ///
+    /// The closure is called once per chapter. Options are applied in the order they were set.
+    pub fn configure(
+        mut self,
+        f: impl Fn(JobBuilder) -> JobBuilder + Send + Sync + 'static,
+    ) -> Self {
+        self.template.configure(f);
+        self
+    }
+
+    /// Sets the preset for every chapter. See `JobBuilder::preset()`.
+    pub fn preset(self, preset: impl Into<String>) -> Self {
+        let preset = preset.into();
+        self.configure(move |job| job.preset(preset.clone()))
+    }
+
+    /// Sets the output container format for every chapter. See `JobBuilder::format()`.
+    pub fn format(mut self, format: impl Into<String>) -> Self {
+        self.template.format(format.into());
+        self
+    }
+
+    /// Builds one `JobBuilder` per chapter, in the order of the chapters.
+    pub fn jobs(self) -> Vec<JobBuilder> {
+        let stem = self.input.file_stem().unwrap_or_default();
+        // Pad to the width of the last chapter, so the outputs sort in order.
+        let width = self.chapters.end().to_string().len().max(2);
+
+        self.chapters
+            .clone()
+            .map(|chapter| {
+                // The stem is inserted as is, so names that aren't valid UTF-8 are kept.
+                let chapter_number = format!("{:0width$}", chapter);
+                let mut name = OsString::new();
+                for (i, part) in self.name_template.split("{name}").enumerate() {
+                    if i > 0 {
+                        name.push(stem);
+                    }
+                    name.push(part.replace("{chapter}", &chapter_number));
+                }
+                let output = self.template.output_path(&self.input, &name);
+                self.template
+                    .job(&self.input, output)
+                    .chapters(chapter..=chapter)
+            })
+            .collect()
+    }
+
+    /// Starts all chapters at once in monitored mode, returning a `JobHandle` per chapter.
+    ///
+    /// Every chapter runs its own `HandBrakeCLI` process concurrently. Use `status()` to
+    /// encode them one after another.
+    pub fn start(self) -> Vec<Result<JobHandle, Error>> {
+        self.jobs().into_iter().map(JobBuilder::start).collect()
+    }
+
+    /// Executes the chapters one after another, returning the final `ExitStatus` per chapter.
+    ///
+    /// A job that fails to spawn doesn't stop the remaining jobs.
+    pub async fn status(self) -> Vec<Result<ExitStatus, Error>> {
+        let mut results = Vec::new();
+        for job in self.jobs() {
+            results.push(job.status().await);
+        }
+        results
+    }
+}
@@ -0,0 +1,395 @@
+//! Parsing `HandBrakeCLI` argument lists into `JobBuilder`s.
+
+use std::ffi::{OsStr, OsString};
+use std::iter::Peekable;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::job::{
+    ColorMatrix, Filter, InputSource, JobBuilder, OutputDestination, PreserveHdrMetadata,
+    RateControl, SAMPLE_PREVIEW, SubtitleBurnMode, SubtitleDefaultMode,
+};
+
+/// The arguments being parsed, and the ones taken for the current option.
+struct Tokens<I: Iterator<Item = OsString>> {
+    iter: Peekable<I>,
+    taken: Vec<OsString>,
+}
+
+impl<I: Iterator<Item = OsString>> Tokens<I> {
+    /// Takes the next argument as the start of an option.
+    fn next_option(&mut self) -> Option<OsString> {
+        let arg = self.iter.next()?;
+        self.taken = vec![arg.clone()];
+        Some(arg)
+    }
+
+    /// Returns the value of the current option, attached with `=` or following it.
+    ///
+    /// A following value that is not valid UTF-8 is taken, but not returned.
+    fn value(&mut self, inline: &Option<String>) -> Option<String> {
+        self.path(inline)?.into_string().ok()
+    }
+
+    /// Like `value()`, but keeps a following value that is not valid UTF-8, e.g., a file name.
+    fn path(&mut self, inline: &Option<String>) -> Option<OsString> {
+        if let Some(inline) = inline {
+            return Some(inline.into());
+        }
+        let value = self.iter.next()?;
+        self.taken.push(value.clone());
+        Some(value)
+    }
+
+    /// Like `value()`, but only takes a following argument if it is accepted, as the value of
+    /// some options is optional.
+    fn value_if(&mut self, inline: &Option<String>, accept: impl Fn(&str) -> bool) -> Option<String> {
+        if inline.is_some() {
+            return inline.clone();
+        }
+        let value = self.iter.next_if(|v| v.to_str().is_some_and(&accept))?;
+        self.taken.push(value.clone());
+        value.into_string().ok()
+    }
+}
+
+type Setter = Box<dyn FnOnce(JobBuilder) -> JobBuilder>;
+
+/// Boxes the `JobBuilder` option a parsed argument maps to.
+fn set(f: impl FnOnce(JobBuilder) -> JobBuilder + 'static) -> Setter {
+    Box::new(f)
+}
+
+/// Maps the short options with a `JobBuilder` equivalent to their long name.
+fn long_name(flag: &str) -> &str {
+    match flag {
+        "-i" => "--input",
+        "-o" => "--output",
+        "-Z" => "--preset",
+        "-e" => "--encoder",
+        "-x" => "--encopts",
+        "-w" => "--width",
+        "-l" => "--height",
+        "-X" => "--maxWidth",
+        "-Y" => "--maxHeight",
+        "-a" => "--audio",
+        "-q" => "--quality",
+        "-b" => "--vb",
+        "-2" => "--two-pass",
+        "-T" => "--turbo",
+        "-f" => "--format",
+        "-s" => "--subtitle",
+        "-c" => "--chapters",
+        "-v" => "--verbose",
+        _ => flag,
+    }
+}
+
+/// A builder with arguments applied to it.
+struct Applied {
+    builder: JobBuilder,
+    /// The arguments without a `JobBuilder` equivalent, in their original order.
+    extra_args: Vec<OsString>,
+    has_input: bool,
+    has_output: bool,
+}
+
+pub(crate) fn parse(handbrake_path: PathBuf, args: &[impl AsRef<OsStr>]) -> Result<JobBuilder, Error> {
+    let builder = JobBuilder::new(handbrake_path, InputSource::Stdin, OutputDestination::Stdout);
+    let Applied {
+        builder,
+        extra_args,
+        has_input,
+        has_output,
+    } = apply(builder, args);
+
+    if !has_input || !has_output {
+        return Err(Error::InvalidArgs {
+            reason: format!("The {} is missing", if has_input { "output" } else { "input" }),
+        });
+    }
+    let extra_args = extra_args
+        .into_iter()
+        .map(|arg| {
+            arg.into_string().map_err(|arg| Error::InvalidArgs {
+                reason: format!("The unknown argument {:?} is not valid UTF-8", arg),
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(builder.extra_args(extra_args))
+}
+
+/// Applies the arguments of a `Profile` on top of the options of a builder.
+pub(crate) fn apply_profile(builder: JobBuilder, args: &[String]) -> JobBuilder {
+    let applied = apply(builder, args);
+    // The unknown arguments were taken from strings, so they are valid UTF-8.
+    let extra_args = applied.extra_args.into_iter().map(|arg| arg.to_string_lossy().into_owned());
+    applied.builder.extra_args(extra_args)
+}
+
+fn apply(mut builder: JobBuilder, args: &[impl AsRef<OsStr>]) -> Applied {
+    let (mut has_input, mut has_output) = (false, false);
+    // The range options only map to the builder in the combinations it emits.
+    let mut start_at: Option<(Duration, Vec<OsString>)> = None;
+    let mut stop_at: Option<(Duration, Vec<OsString>)> = None;
+    let mut start_at_preview: Option<(u32, Vec<OsString>)> = None;
+    // Multi-pass options only apply to a bitrate, which may be set after them.
+    let mut two_pass: Option<Vec<OsString>> = None;
+    let mut turbo: Option<Vec<OsString>> = None;
+    let mut extra_args: Vec<OsString> = Vec::new();
+
+    let mut tokens = Tokens {
+        iter: args.iter().map(|arg| arg.as_ref().to_owned()).peekable(),
+        taken: Vec::new(),
+    };
+    while let Some(arg) = tokens.next_option() {
+        // Options are ASCII, so an argument that is not valid UTF-8 is kept as it is.
+        let arg = arg.to_str().unwrap_or_default();
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if arg.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg, None),
+        };
+        let parsed = match long_name(flag) {
+            "--preset-import-gui" => Some(set(|b| b.import_gui_presets(true))),
+            "--json" => Some(set(|b| b.json_output(true))),
+            // Without a level, `HandBrakeCLI` raises the default level 1 by one.
+            "--verbose" => match &inline {
+                Some(level) => level.parse().ok().map(|level| set(move |b| b.verbose(level))),
+                None => Some(set(|b| b.verbose(2))),
+            },
+            "--input" => tokens.path(&inline).map(|value| {
+                has_input = true;
+                match value.to_str() {
+                    Some("pipe:0") => set(|b| b.input(InputSource::Stdin)),
+                    _ => set(move |b| b.input(PathBuf::from(value))),
+                }
+            }),
+            "--output" => tokens.path(&inline).map(|value| {
+                has_output = true;
+                match value.to_str() {
+                    Some("pipe:1") => set(|b| b.output(OutputDestination::Stdout)),
+                    _ => set(move |b| b.output(PathBuf::from(value))),
+                }
+            }),
+            "--preset" => tokens.value(&inline).map(|preset| set(move |b| b.preset(preset))),
+            "--encoder" => tokens.value(&inline).map(|codec| set(move |b| b.video_codec(codec))),
+            "--encopts" => tokens.value(&inline).map(|encopts| set(move |b| b.encopts(encopts))),
+            "--format" => tokens.value(&inline).map(|format| set(move |b| b.format(format))),
+            "--width" => tokens
+                .value(&inline)
+                .and_then(|w| w.parse().ok())
+                .map(|w| set(move |b| b.width(w))),
+            "--height" => tokens
+                .value(&inline)
+                .and_then(|h| h.parse().ok())
+                .map(|h| set(move |b| b.height(h))),
+            "--maxWidth" => tokens
+                .value(&inline)
+                .and_then(|w| w.parse().ok())
+                .map(|w| set(move |b| b.max_width(w))),
+            "--maxHeight" => tokens
+                .value(&inline)
+                .and_then(|h| h.parse().ok())
+                .map(|h| set(move |b| b.max_height(h))),
+            "--keep-display-aspect" => Some(set(|b| b.keep_display_aspect(true))),
+            "--no-keep-display-aspect" => Some(set(|b| b.keep_display_aspect(false))),
+            "--modulus" => tokens
+                .value(&inline)
+                .and_then(|m| m.parse().ok())
+                .map(|m| set(move |b| b.modulus(m))),
+            "--display-width" => tokens
+                .value(&inline)
+                .and_then(|w| w.parse().ok())
+                .map(|w| set(move |b| b.display_width(w))),
+            "--pixel-aspect" => tokens
+                .value(&inline)
+                .and_then(|value| {
+                    let (x, y) = value.split_once(':')?;
+                    Some((x.parse().ok()?, y.parse().ok()?))
+                })
+                .map(|(x, y)| set(move |b| b.pixel_aspect(x, y))),
+            "--itu-par" => Some(set(|b| b.itu_par(true))),
+            "--color-matrix" => tokens
+                .value(&inline)
+                .and_then(|value| match value.as_str() {
+                    "601" => Some(ColorMatrix::Bt601),
+                    "709" => Some(ColorMatrix::Bt709),
+                    "2020" => Some(ColorMatrix::Bt2020),
+                    _ => None,
+                })
+                .map(|matrix| set(move |b| b.color_matrix(matrix))),
+            "--no-hdr-dynamic-metadata" => Some(set(|b| b.preserve_hdr_metadata(Some(PreserveHdrMetadata::Disabled)))),
+            "--hdr-dynamic-metadata" => tokens
+                .value(&inline)
+                .and_then(|value| match value.as_str() {
+                    "hdr10plus" => Some(PreserveHdrMetadata::Hdr10),
+                    "dolbyvision" => Some(PreserveHdrMetadata::DolbyVision),
+                    "all" => Some(PreserveHdrMetadata::All),
+                    _ => None,
+                })
+                .map(|mode| set(move |b| b.preserve_hdr_metadata(Some(mode)))),
+            // Only the `<track>,<codec>` form emitted by `audio_codec()`, not a list of tracks.
+            "--audio" => tokens
+                .value(&inline)
+                .and_then(|value| {
+                    let (track, codec) = value.split_once(',')?;
+                    let track: u32 = track.parse().ok()?;
+                    let is_codec = !codec.is_empty() && !codec.contains(',') && codec.parse::<u32>().is_err();
+                    is_codec.then(|| (track, codec.to_string()))
+                })
+                .map(|(track, codec)| set(move |b| b.audio_codec(track, codec))),
+            "--quality" => tokens
+                .value(&inline)
+                .and_then(|q| q.parse().ok())
+                .map(|q| set(move |b| b.quality(q))),
+            "--vb" => tokens
+                .value(&inline)
+                .and_then(|kbps| kbps.parse().ok())
+                .map(|kbps| set(move |b| b.video_bitrate(kbps))),
+            "--subtitle" => tokens.value(&inline).and_then(|value| {
+                if value == "scan" {
+                    return Some(set(|b| b.subtitle_scan()));
+                }
+                let tracks: Vec<u32> = value.split(',').map(|t| t.parse().ok()).collect::<Option<_>>()?;
+                Some(set(move |b| tracks.into_iter().fold(b, |b, track| b.subtitle(track))))
+            }),
+            "--subtitle-lang-list" => tokens
+                .value(&inline)
+                .map(|langs| set(move |b| langs.split(',').fold(b, |b, lang| b.subtitle_lang(lang)))),
+            "--subtitle-burned" => tokens
+                .value_if(&inline, |v| v == "native" || v == "none")
+                .and_then(|value| match value.as_str() {
+                    "native" => Some(SubtitleBurnMode::Native),
+                    "none" => Some(SubtitleBurnMode::None),
+                    _ => None,
+                })
+                .map(|mode| set(move |b| b.subtitle_burned(mode))),
+            "--subtitle-forced" => tokens
+                .value_if(&inline, |v| v.parse::<u32>().is_ok())
+                .and_then(|track| track.parse().ok())
+                .map(|track| set(move |b| b.subtitle_forced(track))),
+            "--subtitle-default" => tokens
+                .value_if(&inline, |v| v == "none" || v.parse::<u32>().is_ok())
+                .and_then(|value| match value.as_str() {
+                    "none" => Some(SubtitleDefaultMode::None),
+                    track => track.parse().ok().map(SubtitleDefaultMode::Track),
+                })
+                .map(|mode| set(move |b| b.subtitle_default(mode))),
+            "--native-language" => tokens.value(&inline).map(|lang| set(move |b| b.native_language(lang))),
+            "--native-dub" => Some(set(|b| b.native_dub(true))),
+            "--srt-file" => tokens.value(&inline).map(|file| set(move |b| b.srt_file(file))),
+            "--ssa-file" => tokens.value(&inline).map(|file| set(move |b| b.ssa_file(file))),
+            "--chapters" => tokens
+                .value(&inline)
+                .and_then(|value| match value.split_once('-') {
+                    Some((first, last)) => Some(first.parse().ok()?..=last.parse().ok()?),
+                    None => value.parse().ok().map(|chapter| chapter..=chapter),
+                })
+                .map(|chapters| set(move |b| b.chapters(chapters))),
+            "--angle" => tokens
+                .value(&inline)
+                .and_then(|angle| angle.parse().ok())
+                .map(|angle| set(move |b| b.angle(angle))),
+            "--no-dvdnav" => Some(set(|b| b.dvdnav(false))),
+            "--start-at" | "--stop-at" => {
+                let duration = tokens.value(&inline).as_deref().and_then(parse_duration);
+                if let Some(duration) = duration {
+                    let slot = if flag == "--start-at" { &mut start_at } else { &mut stop_at };
+                    // A repeated option overrides the previous one.
+                    if let Some((_, taken)) = slot.replace((duration, tokens.taken.clone())) {
+                        extra_args.extend(taken);
+                    }
+                    continue;
+                }
+                None
+            }
+            "--two-pass" | "--multi-pass" => {
+                two_pass = Some(tokens.taken.clone());
+                continue;
+            }
+            "--no-two-pass" => {
+                // Overrides the passes given before.
+                two_pass = None;
+                turbo = None;
+                Some(set(|b| b.no_two_pass()))
+            }
+            "--turbo" => {
+                turbo = Some(tokens.taken.clone());
+                continue;
+            }
+            "--start-at-preview" => {
+                if let Some(preview) = tokens.value(&inline).and_then(|p| p.parse().ok()) {
+                    if let Some((_, taken)) = start_at_preview.replace((preview, tokens.taken.clone())) {
+                        extra_args.extend(taken);
+                    }
+                    continue;
+                }
+                None
+            }
+            _ => flag
+                .strip_prefix("--no-")
+                .and_then(disabled_filter)
+                .map(|filter| set(move |b| b.disable_filter(filter))),
+        };
+        match parsed {
+            Some(set) => builder = set(builder),
+            None => extra_args.append(&mut tokens.taken),
+        }
+    }
+
+    match (start_at, start_at_preview, stop_at) {
+        (Some((start, _)), None, Some((duration, _))) => builder = builder.time_range(start, duration),
+        (None, Some((SAMPLE_PREVIEW, _)), Some((duration, _))) => builder = builder.sample(duration),
+        (start_at, start_at_preview, stop_at) => {
+            extra_args.extend(start_at.into_iter().flat_map(|(_, taken)| taken));
+            extra_args.extend(start_at_preview.into_iter().flat_map(|(_, taken)| taken));
+            extra_args.extend(stop_at.into_iter().flat_map(|(_, taken)| taken));
+        }
+    }
+
+    match &mut builder.rate_control {
+        Some(RateControl::Bitrate {
+            two_pass: bitrate_two_pass,
+            turbo: bitrate_turbo,
+            ..
+        }) if two_pass.is_some() => {
+            *bitrate_two_pass = true;
+            *bitrate_turbo = turbo.is_some();
+        }
+        _ => extra_args.extend(two_pass.into_iter().chain(turbo).flatten()),
+    }
+
+    Applied {
+        builder,
+        extra_args,
+        has_input,
+        has_output,
+    }
+}
+
+/// Parses the filter of a `--no-<filter>` option.
+fn disabled_filter(name: &str) -> Option<Filter> {
+    let filter = match name {
+        "comb-detect" => Filter::CombDetect,
+        "deinterlace" => Filter::Deinterlace,
+        "bwdif" => Filter::Bwdif,
+        "decomb" => Filter::Decomb,
+        "detelecine" => Filter::Detelecine,
+        "hqdn3d" => Filter::Hqdn3d,
+        "nlmeans" => Filter::Nlmeans,
+        "chroma-smooth" => Filter::ChromaSmooth,
+        "unsharp" => Filter::Unsharp,
+        "lapsharp" => Filter::Lapsharp,
+        "deblock" => Filter::Deblock,
+        "grayscale" => Filter::Grayscale,
+        _ => return None,
+    };
+    Some(filter)
+}
+
+/// Parses a position in seconds, e.g., `duration:90.5`.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let seconds: f64 = value.strip_prefix("duration:")?.parse().ok()?;
+    Duration::try_from_secs_f64(seconds).ok()
+}
@@ -0,0 +1,139 @@
+//! Framing of the `HandBrakeCLI` output streams.
+//!
+//! Depending on the platform and version, `HandBrakeCLI` terminates progress lines with `\r`,
+//! `\r\n` or `\n`, so all three are treated as line endings.
+
+use std::io;
+
+use bytes::{Buf, BytesMut};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+use tokio_util::codec::Decoder;
+
+/// Splits a stream into lines, replacing invalid UTF-8 instead of failing.
+///
+/// Unlike `LinesCodec`, a single malformed byte, e.g. from odd DVD metadata, only affects the
+/// line it occurs in. Lines end at `\r`, `\r\n` or `\n`.
+#[derive(Debug, Default)]
+pub(crate) struct LossyLinesCodec {
+    /// Whether the last line ended with `\r`, so a leading `\n` belongs to it.
+    after_cr: bool,
+}
+
+impl Decoder for LossyLinesCodec {
+    type Item = String;
+    type Error = io::Error;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<String>, io::Error> {
+        if self.after_cr && !buf.is_empty() {
+            self.after_cr = false;
+            if buf[0] == b'\n' {
+                buf.advance(1);
+            }
+        }
+        let Some(end) = buf.iter().position(|&b| b == b'\r' || b == b'\n') else {
+            return Ok(None);
+        };
+        self.after_cr = buf[end] == b'\r';
+        let line = buf.split_to(end + 1);
+        Ok(Some(to_line(&line[..end])))
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> Result<Option<String>, io::Error> {
+        if let Some(line) = self.decode(buf)? {
+            return Ok(Some(line));
+        }
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        let line = buf.split();
+        Ok(Some(to_line(&line)))
+    }
+}
+
+fn to_line(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// Appends data up to and including the next `\r` or `\n` to `buf`, returning the number of
+/// bytes read, or `0` at the end of the stream.
+///
+/// Data ending with `...` is returned right away, as `HandBrakeCLI` prints status messages such as
+/// `Muxing: this may take awhile...` without a line ending.
+///
+/// Like `read_until()`, this is cancel safe as long as `buf` is kept, since data is only consumed
+/// from the reader once it has been appended.
+pub(crate) async fn read_line_chunk<R>(reader: &mut R, buf: &mut Vec<u8>) -> io::Result<usize>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut read = 0;
+    loop {
+        let available = reader.fill_buf().await?;
+        if available.is_empty() {
+            return Ok(read);
+        }
+        let (done, used) = match available.iter().position(|&b| b == b'\r' || b == b'\n') {
+            Some(end) => (true, end + 1),
+            None => (false, available.len()),
+        };
+        buf.extend_from_slice(&available[..used]);
+        reader.consume(used);
+        read += used;
+        if done || buf.ends_with(b"...") {
+            return Ok(read);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_lossy_lines() {
+        let mut codec = LossyLinesCodec::default();
+        let mut buf = BytesMut::from(&b"caf\xe9\r\nok\npartial"[..]);
+
+        assert_eq!(codec.decode(&mut buf).unwrap().as_deref(), Some("caf\u{FFFD}"));
+        assert_eq!(codec.decode(&mut buf).unwrap().as_deref(), Some("ok"));
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert_eq!(codec.decode_eof(&mut buf).unwrap().as_deref(), Some("partial"));
+        assert_eq!(codec.decode_eof(&mut buf).unwrap(), None);
+    }
+
+    #[test]
+    fn test_decode_mixed_line_endings() {
+        let mut codec = LossyLinesCodec::default();
+        let mut buf = BytesMut::from(&b"one\rtwo\r"[..]);
+
+        assert_eq!(codec.decode(&mut buf).unwrap().as_deref(), Some("one"));
+        assert_eq!(codec.decode(&mut buf).unwrap().as_deref(), Some("two"));
+        // The `\n` of a `\r\n` split across reads doesn't produce an empty line.
+        buf.extend_from_slice(b"\nthree\n\nfour\n");
+        assert_eq!(codec.decode(&mut buf).unwrap().as_deref(), Some("three"));
+        assert_eq!(codec.decode(&mut buf).unwrap().as_deref(), Some(""));
+        assert_eq!(codec.decode(&mut buf).unwrap().as_deref(), Some("four"));
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_read_line_chunk() {
+        let mut reader: &[u8] = b"a\rb\r\nc\nd";
+        let mut chunks = Vec::new();
+        loop {
+            let mut buf = Vec::new();
+            if read_line_chunk(&mut reader, &mut buf).await.unwrap() == 0 {
+                break;
+            }
+            chunks.push(buf);
+        }
+        assert_eq!(chunks, vec![&b"a\r"[..], b"b\r", b"\n", b"c\n", b"d"]);
+
+        let mut reader: &[u8] = b"\rMuxing: this may take awhile...";
+        let mut buf = Vec::new();
+        read_line_chunk(&mut reader, &mut buf).await.unwrap();
+        buf.clear();
+        read_line_chunk(&mut reader, &mut buf).await.unwrap();
+        assert_eq!(buf, b"Muxing: this may take awhile...");
+    }
+}
@@ -0,0 +1,264 @@
+//! Comparing the quality of sample encodes against their source with `ffmpeg`.
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tokio::process::Command;
+
+use crate::error::Error;
+use crate::job::{InputSource, JobBuilder, OutputDestination};
+
+/// Matches the pooled score printed by the `libvmaf` filter, e.g., `VMAF score: 93.512`.
+static VMAF_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"VMAF score: (\d+(?:\.\d+)?)").expect("BUG: Failed to compile VMAF regex")
+});
+
+/// Matches the overall score printed by the `ssim` filter, e.g., `All:0.987654 (19.09)`.
+static SSIM_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"All:(\d+(?:\.\d+)?)").expect("BUG: Failed to compile SSIM regex")
+});
+
+/// The metric a `QualityComparison` scores the samples with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Metric {
+    /// Video Multimethod Assessment Fusion, from 0 to 100. Requires `ffmpeg` built with `libvmaf`.
+    #[default]
+    Vmaf,
+    /// Structural similarity, from 0 to 1.
+    Ssim,
+}
+
+impl Metric {
+    fn filter(self) -> &'static str {
+        match self {
+            Metric::Vmaf => "libvmaf",
+            Metric::Ssim => "ssim",
+        }
+    }
+
+    fn parse(self, stderr: &str) -> Option<f64> {
+        let re = match self {
+            Metric::Vmaf => &VMAF_RE,
+            Metric::Ssim => &SSIM_RE,
+        };
+        re.captures_iter(stderr).last()?[1].parse().ok()
+    }
+}
+
+/// The score of a sample encoded at one quality.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QualityScore {
+    /// The constant quality of the sample.
+    pub quality: f32,
+    /// The score of the sample against the source.
+    pub score: f64,
+    /// The size of the sample in bytes.
+    pub size: u64,
+}
+
+/// Encodes a sample of a job at several qualities, and scores each against the source.
+///
+/// This lets applications pick quality settings automatically, e.g., the highest quality value
+/// that still scores a VMAF of 93. Every sample encodes the same range of the source, which
+/// `ffmpeg` then compares with the encoded sample after scaling it to the source's size.
+///
+/// Requires the `compare` feature and `ffmpeg`.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use handbrake::{HandBrake, InputSource, Metric, OutputDestination};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let hb = HandBrake::new().await?;
+/// let scores = hb
+///     .job(InputSource::from("movie.mkv"), OutputDestination::from("movie.mp4"))
+///     .video_codec("x265")
+///     .compare_qualities([20.0, 24.0, 28.0])
+///     .metric(Metric::Vmaf)
+///     .run()
+///     .await?;
+/// let quality = scores.iter().filter(|s| s.score >= 93.0).map(|s| s.quality).fold(0.0, f32::max);
+/// # Ok(())
+/// # }
+/// ```
+pub struct QualityComparison {
+    job: JobBuilder,
+    qualities: Vec<f32>,
+    metric: Metric,
+    start: Duration,
+    duration: Duration,
+    ffmpeg_path: PathBuf,
+    work_dir: PathBuf,
+}
+
+impl QualityComparison {
+    /// Creates a comparison of the job at the given qualities.
+    ///
+    /// This is typically called via `JobBuilder::compare_qualities()`.
+    pub fn new(job: JobBuilder, qualities: impl IntoIterator<Item = f32>) -> Self {
+        QualityComparison {
+            job,
+            qualities: qualities.into_iter().collect(),
+            metric: Metric::default(),
+            start: Duration::from_secs(60),
+            duration: Duration::from_secs(20),
+            ffmpeg_path: PathBuf::from("ffmpeg"),
+            work_dir: std::env::temp_dir().join(format!("handbrake-rs-compare-{}", std::process::id())),
+        }
+    }
+
+    /// Sets the metric the samples are scored with. Defaults to `Metric::Vmaf`.
+    pub fn metric(mut self, metric: Metric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// Sets the range of the source that is encoded and compared.
+    ///
+    /// Defaults to 20 seconds, starting one minute into the source.
+    pub fn sample(mut self, start: Duration, duration: Duration) -> Self {
+        self.start = start;
+        self.duration = duration;
+        self
+    }
+
+    /// Sets the `ffmpeg` executable used to score the samples.
+    ///
+    /// Defaults to `ffmpeg` in the system `PATH`.
+    pub fn ffmpeg_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ffmpeg_path = path.into();
+        self
+    }
+
+    /// Sets the directory the samples are encoded into, which is removed afterwards.
+    ///
+    /// Defaults to a directory in `std::env::temp_dir()`.
+    pub fn work_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.work_dir = dir.into();
+        self
+    }
+
+    /// Encodes and scores the samples one after another, in the order of the qualities.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the input is not a file, or if a sample encode or `ffmpeg` fails.
+    pub async fn run(self) -> Result<Vec<QualityScore>, Error> {
+        let InputSource::File(source) = &self.job.input else {
+            return Err(Error::ComparisonFailed {
+                reason: "The input must be a file, which ffmpeg compares the samples with".to_string(),
+            });
+        };
+        tokio::fs::create_dir_all(&self.work_dir).await.map_err(file_error)?;
+        let result = self.score_all(source).await;
+        let _ = tokio::fs::remove_dir_all(&self.work_dir).await;
+        result
+    }
+
+    async fn score_all(&self, source: &Path) -> Result<Vec<QualityScore>, Error> {
+        let extension = match &self.job.output {
+            OutputDestination::File(path) => path.extension().map(|e| e.to_string_lossy().into_owned()),
+            _ => None,
+        };
+        let mut scores = Vec::with_capacity(self.qualities.len());
+        for &quality in &self.qualities {
+            let sample = self.work_dir.join(format!(
+                "q{}.{}",
+                quality,
+                extension.as_deref().unwrap_or("mkv")
+            ));
+            let summary = self
+                .sample_job(sample.clone())
+                .quality(quality)
+                .start()?
+                .wait()
+                .await
+                .map_err(|failure| Error::ComparisonFailed {
+                    reason: format!("The sample at quality {} failed: {}", quality, failure.message),
+                })?;
+            scores.push(QualityScore {
+                quality,
+                score: self.score(source, &sample).await?,
+                size: summary.output_size.unwrap_or(0),
+            });
+        }
+        Ok(scores)
+    }
+
+    /// Returns a copy of the job encoding the compared range into the given file.
+    fn sample_job(&self, output: PathBuf) -> JobBuilder {
+        let mut spec = self.job.to_spec();
+        spec.output = OutputDestination::File(output);
+        spec.log_file = None;
+        self.job.derive_from_spec(spec).time_range(self.start, self.duration)
+    }
+
+    /// Scores the sample against the source with `ffmpeg`.
+    async fn score(&self, source: &Path, sample: &Path) -> Result<f64, Error> {
+        let output = Command::new(&self.ffmpeg_path)
+            .args(self.ffmpeg_args(source, sample))
+            .output()
+            .await
+            .map_err(|e| Error::ProcessSpawnFailed { source: e })?;
+        if !output.status.success() {
+            return Err(Error::ComparisonFailed {
+                reason: format!("ffmpeg exited unsuccessfully: {}", output.status),
+            });
+        }
+        self.metric
+            .parse(&String::from_utf8_lossy(&output.stderr))
+            .ok_or_else(|| Error::ComparisonFailed {
+                reason: "ffmpeg didn't report a score".to_string(),
+            })
+    }
+
+    /// The `ffmpeg` arguments comparing the sample with the same range of the source.
+    fn ffmpeg_args(&self, source: &Path, sample: &Path) -> Vec<OsString> {
+        let filter = format!(
+            "[0:v]setpts=PTS-STARTPTS[s];[1:v]setpts=PTS-STARTPTS[r];[s][r]scale2ref[sc][rc];[sc][rc]{}",
+            self.metric.filter()
+        );
+        vec![
+            "-hide_banner".into(),
+            "-i".into(),
+            sample.into(),
+            "-ss".into(),
+            self.start.as_secs_f64().to_string().into(),
+            "-t".into(),
+            self.duration.as_secs_f64().to_string().into(),
+            "-i".into(),
+            source.into(),
+            "-lavfi".into(),
+            filter.into(),
+            "-f".into(),
+            "null".into(),
+            "-".into(),
+        ]
+    }
+}
+
+/// Describes a failure to manage the sample files.
+fn file_error(e: std::io::Error) -> Error {
+    Error::ComparisonFailed {
+        reason: format!("Failed to manage the sample files: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_scores() {
+        assert_eq!(Metric::Vmaf.parse("[libvmaf @ 0x1] VMAF score: 93.512345\n"), Some(93.512345));
+        assert_eq!(
+            Metric::Ssim.parse("[Parsed_ssim_4 @ 0x1] SSIM Y:0.99 (20.1) U:0.98 (19.2) V:0.98 (19.3) All:0.987654 (19.09)\n"),
+            Some(0.987654)
+        );
+        assert_eq!(Metric::Vmaf.parse("no score"), None);
+    }
+}
@@ -0,0 +1,210 @@
+//! Compatibility with the output of different `HandBrakeCLI` versions.
+//!
+//! The progress lines and the JSON job block change little between releases, but fields do
+//! change their types now and then, and nightly builds run ahead of the releases. The JSON job
+//! block is therefore read field by field when it doesn't match the typed `Config`, so that a
+//! single unexpected field doesn't lose the whole configuration.
+
+use serde_json::Value;
+
+use crate::event::Config;
+use crate::job::parse_version;
+
+/// The oldest and newest release series the parsing is tested against, inclusive.
+const SUPPORTED: ((u32, u32), (u32, u32)) = ((1, 5), (1, 9));
+
+/// How well a `HandBrakeCLI` version is supported, see `HandBrake::support()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Support {
+    /// A release from 1.5 to 1.9, whose output the parsing is tested against.
+    Supported,
+    /// A nightly build, e.g., `HandBrake 20250101123456-abcdef012-master`, which is parsed like
+    /// the latest release.
+    Nightly,
+    /// An older or newer release, or an unrecognized version, which is parsed on a best-effort
+    /// basis.
+    Untested,
+}
+
+impl Support {
+    /// Classifies the output of `HandBrakeCLI --version`.
+    pub fn of(version: &str) -> Self {
+        if is_nightly(version) {
+            return Support::Nightly;
+        }
+        match parse_version(version) {
+            Some((major, minor, _)) if (SUPPORTED.0..=SUPPORTED.1).contains(&(major, minor)) => Support::Supported,
+            _ => Support::Untested,
+        }
+    }
+}
+
+/// Nightly builds are versioned by their build time and commit, e.g., `20250101123456-abcdef012-master`.
+fn is_nightly(version: &str) -> bool {
+    version
+        .split_whitespace()
+        .skip_while(|word| *word != "HandBrake")
+        .nth(1)
+        .and_then(|build| build.split_once('-'))
+        .is_some_and(|(time, _)| time.len() >= 8 && time.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// The warning logged at the start of a job run by an untested version, if any.
+pub(crate) fn untested_warning(version: &str) -> Option<String> {
+    (Support::of(version) == Support::Untested).then(|| {
+        let ((min_major, min_minor), (max_major, max_minor)) = SUPPORTED;
+        format!(
+            "{} is untested, only {}.{} to {}.{} and nightly builds are supported; events may be incomplete",
+            version.lines().next().unwrap_or_default().trim(),
+            min_major,
+            min_minor,
+            max_major,
+            max_minor
+        )
+    })
+}
+
+/// Parses the JSON job block, leaving fields of an unexpected type at their defaults.
+///
+/// The skipped fields are listed in `Config::skipped`. Fails only if the block is not a JSON
+/// object.
+pub(crate) fn parse_config(json: &str) -> Result<Config, serde_json::Error> {
+    let raw: Value = serde_json::from_str(json)?;
+    let error = match serde_json::from_value::<Config>(raw.clone()) {
+        Ok(config) => return Ok(config),
+        Err(e) => e,
+    };
+    if !raw.is_object() {
+        return Err(error);
+    }
+    let mut sanitized = raw.clone();
+    let mut skipped = Vec::new();
+    if !sanitize(&mut Vec::new(), &mut sanitized, &mut skipped) {
+        return Err(error);
+    }
+    let mut config = serde_json::from_value::<Config>(sanitized).map_err(|_| error)?;
+    config.raw = raw;
+    config.skipped = skipped;
+    Ok(config)
+}
+
+/// A step from a JSON value into one of its children.
+enum Step {
+    Key(String),
+    Index(usize),
+}
+
+/// Removes the parts of `value` at `path` that don't parse, recording their paths in `skipped`.
+///
+/// Returns whether `value` itself is kept. Every part is tested on its own, in a JSON block with
+/// nothing else, which works as the typed fields default whatever is missing.
+fn sanitize(path: &mut Vec<Step>, value: &mut Value, skipped: &mut Vec<String>) -> bool {
+    if parses(path, value) {
+        return true;
+    }
+    let recorded = skipped.len();
+    match value {
+        Value::Object(map) => map.retain(|key, child| {
+            path.push(Step::Key(key.clone()));
+            let keep = sanitize(path, child, skipped);
+            path.pop();
+            keep
+        }),
+        Value::Array(items) => {
+            let mut index = 0;
+            items.retain_mut(|child| {
+                path.push(Step::Index(index));
+                index += 1;
+                let keep = sanitize(path, child, skipped);
+                path.pop();
+                keep
+            });
+        }
+        _ => {}
+    }
+    // An object may still miss a required field, in which case it is skipped as a whole.
+    if parses(path, value) {
+        return true;
+    }
+    skipped.truncate(recorded);
+    skipped.push(display(path));
+    false
+}
+
+/// Whether `value` parses at `path` in an otherwise empty JSON block.
+fn parses(path: &[Step], value: &Value) -> bool {
+    let block = path.iter().rev().fold(value.clone(), |child, step| match step {
+        Step::Key(key) => Value::Object([(key.clone(), child)].into_iter().collect()),
+        Step::Index(_) => Value::Array(vec![child]),
+    });
+    serde_json::from_value::<Config>(block).is_ok()
+}
+
+/// Formats a path like `Audio.AudioList[0].Bitrate`.
+fn display(path: &[Step]) -> String {
+    let mut text = String::new();
+    for step in path {
+        match step {
+            Step::Key(key) if text.is_empty() => text.push_str(key),
+            Step::Key(key) => {
+                text.push('.');
+                text.push_str(key);
+            }
+            Step::Index(index) => text.push_str(&format!("[{}]", index)),
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_support() {
+        assert_eq!(Support::of("HandBrake 1.5.1\n"), Support::Supported);
+        assert_eq!(Support::of("HandBrake 1.9.2"), Support::Supported);
+        assert_eq!(Support::of("HandBrake 20250101123456-abcdef012-master"), Support::Nightly);
+        assert_eq!(Support::of("HandBrake 1.4.2"), Support::Untested);
+        assert_eq!(Support::of("HandBrake 1.10.0"), Support::Untested);
+        assert_eq!(Support::of("garbage"), Support::Untested);
+    }
+
+    #[test]
+    fn test_untested_warning() {
+        assert_eq!(untested_warning("HandBrake 1.6.0"), None);
+        assert_eq!(
+            untested_warning("HandBrake 1.4.2\n").as_deref(),
+            Some("HandBrake 1.4.2 is untested, only 1.5 to 1.9 and nightly builds are supported; events may be incomplete")
+        );
+    }
+
+    #[test]
+    fn test_parse_config_skips_unexpected_fields() {
+        let config = parse_config(
+            r#"{
+                "Audio": { "AudioList": [
+                    { "PresetEncoder": "aac", "Bitrate": 160 },
+                    { "PresetEncoder": "copy", "Bitrate": -1 }
+                ] },
+                "PAR": { "Num": 1, "Den": "1" },
+                "Video": { "Encoder": "x265", "Quality": "22" }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.skipped, ["Audio.AudioList[1].Bitrate", "PAR", "Video.Quality"]);
+        assert_eq!(config.audio_config.audio_list.len(), 2);
+        assert_eq!(config.audio_config.audio_list[0].bitrate, 160);
+        assert_eq!(config.audio_config.audio_list[1].encoder_name, "copy");
+        assert_eq!(config.video.encoder, "x265");
+        assert!(config.par.is_none());
+        assert_eq!(config.raw["Video"]["Quality"], "22");
+    }
+
+    #[test]
+    fn test_parse_config_rejects_invalid_json() {
+        assert!(parse_config("{ \"Video\": ").is_err());
+        assert!(parse_config("[1, 2]").is_err());
+    }
+}
@@ -0,0 +1,87 @@
+//! Recognizing known problems in the `stderr` lines of `HandBrakeCLI`.
+//!
+//! The recognition is best-effort: the lines are matched against fixed substrings of the
+//! messages of `HandBrakeCLI` and the libraries it uses, and anything else stays a `Log` event.
+
+use crate::event::{Diagnostic, DiagnosticKind, JobEvent, Log};
+
+/// Messages of the encoders, or of HandBrake about them, when they fail to initialize.
+const ENCODER_INIT_FAILURES: &[&str] = &[
+    "Failure to initialise thread",
+    "avcodec_open failed",
+    "x264_encoder_open failed",
+    "x265_encoder_open failed",
+    "x264 [error]:",
+];
+
+/// Messages of `libdvdcss` and HandBrake when the region of a disc doesn't match the drive.
+///
+/// `libdvdnav` also reports the regions of every disc it opens, which is not a problem.
+const REGION_MISMATCHES: &[&str] = &["region code does not match", "region mismatch"];
+
+/// Turns a `stderr` line into a `Warning` or `Error` event if it is a known problem, or into a
+/// `Log` event otherwise.
+pub(crate) fn classify_line(line: String) -> JobEvent {
+    // Region errors are also reported by `libdvdread`, so they are matched first.
+    let (kind, is_error) = if is_region_error(&line) {
+        (DiagnosticKind::DiscRegion, true)
+    } else if line.starts_with("libdvdread:") || line.starts_with("libdvdnav:") {
+        (DiagnosticKind::DvdRead, false)
+    } else if line.contains("No title found") {
+        (DiagnosticKind::NoTitleFound, true)
+    } else if ENCODER_INIT_FAILURES.iter().any(|pattern| line.contains(pattern)) {
+        (DiagnosticKind::EncoderInit, true)
+    } else {
+        return JobEvent::Log(Log { message: line });
+    };
+    let diagnostic = Diagnostic { kind, message: line };
+    if is_error {
+        JobEvent::Error(diagnostic)
+    } else {
+        JobEvent::Warning(diagnostic)
+    }
+}
+
+fn is_region_error(line: &str) -> bool {
+    let line = line.to_ascii_lowercase();
+    REGION_MISMATCHES.iter().any(|pattern| line.contains(pattern))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kind(line: &str) -> Option<(&'static str, DiagnosticKind)> {
+        match classify_line(line.to_string()) {
+            JobEvent::Warning(diagnostic) => Some(("warning", diagnostic.kind)),
+            JobEvent::Error(diagnostic) => Some(("error", diagnostic.kind)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_classify_line() {
+        assert_eq!(
+            kind("libdvdread: CHECK_VALUE failed in nav_read.c:170"),
+            Some(("warning", DiagnosticKind::DvdRead))
+        );
+        assert_eq!(
+            kind("libdvdcss error: the drive's region code does not match the disc"),
+            Some(("error", DiagnosticKind::DiscRegion))
+        );
+        assert_eq!(kind("libdvdcss error: region mismatch"), Some(("error", DiagnosticKind::DiscRegion)));
+        // Reported on every DVD scan, so it must not be a region error.
+        assert_eq!(
+            kind("libdvdnav: DVD disk reports itself with Region mask 0x00fe0000. Regions: 1"),
+            Some(("warning", DiagnosticKind::DvdRead))
+        );
+        assert_eq!(kind("No title found."), Some(("error", DiagnosticKind::NoTitleFound)));
+        assert_eq!(
+            kind("Failure to initialise thread 'H.265/HEVC encoder (NVEnc)'"),
+            Some(("error", DiagnosticKind::EncoderInit))
+        );
+        assert_eq!(kind("encx265: x265_encoder_open failed."), Some(("error", DiagnosticKind::EncoderInit)));
+        assert_eq!(kind("scan: DVD has 2 title(s)"), None);
+        assert_eq!(kind("Encode done!"), None);
+    }
+}
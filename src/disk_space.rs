@@ -0,0 +1,150 @@
+//! Watching the free space on the volume a job writes its output to, see
+//! `JobBuilder::min_free_space()`.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tokio::time::{Interval, MissedTickBehavior};
+
+/// How often the free space is sampled.
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What a sample of the free space calls for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Sample {
+    /// The free space dropped below a warning threshold.
+    Warning { free_bytes: u64, threshold: u64 },
+    /// The free space dropped below the minimum, so the job must be stopped.
+    Exhausted { free_bytes: u64 },
+}
+
+/// Samples the free space on the output volume of a running job.
+pub(crate) struct DiskSpaceWatch {
+    /// The directory of the output, which exists before `HandBrakeCLI` creates the output.
+    dir: PathBuf,
+    min_free: Option<u64>,
+    /// The warning thresholds not crossed yet, in descending order.
+    warnings: Vec<u64>,
+    interval: Interval,
+}
+
+impl DiskSpaceWatch {
+    /// Returns a watch for the volume of the output file, or `None` if nothing is watched for.
+    ///
+    /// Must be called within a Tokio runtime.
+    pub(crate) fn new(output: &Path, min_free: Option<u64>, warnings: &[u64]) -> Option<Self> {
+        if min_free.is_none() && warnings.is_empty() {
+            return None;
+        }
+        let dir = match output.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let mut interval = tokio::time::interval(SAMPLE_INTERVAL);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        Some(DiskSpaceWatch {
+            dir,
+            min_free,
+            warnings: sorted_thresholds(warnings),
+            interval,
+        })
+    }
+
+    /// Waits for the next sample that calls for an action.
+    pub(crate) async fn next(&mut self) -> Sample {
+        loop {
+            self.interval.tick().await;
+            let dir = self.dir.clone();
+            // Failed samples, e.g., while a network volume is unreachable, are skipped.
+            let Ok(Ok(free_bytes)) = tokio::task::spawn_blocking(move || free_space(&dir)).await else {
+                continue;
+            };
+            if let Some(sample) = self.check(free_bytes) {
+                return sample;
+            }
+        }
+    }
+
+    /// Compares a sample against the minimum and the thresholds not crossed yet.
+    ///
+    /// When several thresholds are crossed at once, only the lowest one is reported.
+    fn check(&mut self, free_bytes: u64) -> Option<Sample> {
+        if self.min_free.is_some_and(|min| free_bytes < min) {
+            return Some(Sample::Exhausted { free_bytes });
+        }
+        let crossed = self.warnings.iter().take_while(|&&threshold| free_bytes < threshold).count();
+        let threshold = self.warnings.drain(..crossed).next_back()?;
+        Some(Sample::Warning { free_bytes, threshold })
+    }
+}
+
+fn sorted_thresholds(thresholds: &[u64]) -> Vec<u64> {
+    let mut thresholds = thresholds.to_vec();
+    thresholds.sort_unstable_by(|a, b| b.cmp(a));
+    thresholds.dedup();
+    thresholds
+}
+
+/// Returns the space available to unprivileged users on the volume containing `path`.
+#[cfg(unix)]
+// The field types differ between platforms.
+#[allow(clippy::useless_conversion)]
+pub(crate) fn free_space(path: &Path) -> io::Result<u64> {
+    let stat = nix::sys::statvfs::statvfs(path)?;
+    Ok(u64::from(stat.blocks_available()).saturating_mul(u64::from(stat.fragment_size())))
+}
+
+/// Returns the space available to the current user on the volume containing `path`.
+#[cfg(windows)]
+pub(crate) fn free_space(path: &Path) -> io::Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut available = 0;
+    // SAFETY: the path is NUL-terminated, and the total sizes are optional outputs.
+    let result = unsafe { GetDiskFreeSpaceExW(path.as_ptr(), &mut available, std::ptr::null_mut(), std::ptr::null_mut()) };
+    if result == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(available)
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn free_space(_path: &Path) -> io::Result<u64> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "Free disk space is not supported on this platform"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn watch(min_free: Option<u64>, warnings: &[u64]) -> DiskSpaceWatch {
+        DiskSpaceWatch {
+            dir: PathBuf::from("."),
+            min_free,
+            warnings: sorted_thresholds(warnings),
+            interval: tokio::time::interval(SAMPLE_INTERVAL),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_warnings_are_reported_once() {
+        let mut watch = watch(Some(100), &[1000, 10_000, 500]);
+
+        assert_eq!(watch.check(20_000), None);
+        assert_eq!(watch.check(9_000), Some(Sample::Warning { free_bytes: 9_000, threshold: 10_000 }));
+        assert_eq!(watch.check(8_000), None);
+        // Crossing two thresholds at once reports the lower one.
+        assert_eq!(watch.check(400), Some(Sample::Warning { free_bytes: 400, threshold: 500 }));
+        assert_eq!(watch.check(450), None);
+        assert_eq!(watch.check(99), Some(Sample::Exhausted { free_bytes: 99 }));
+    }
+
+    #[test]
+    fn test_free_space_of_current_dir() {
+        assert!(free_space(Path::new(".")).is_ok());
+        assert!(free_space(Path::new("/nonexistent/handbrake-rs")).is_err());
+    }
+}
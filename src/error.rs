@@ -32,6 +32,72 @@ pub enum Error {
         /// The underlying I/O error that occurred.
         source: std::io::Error,
     },
+    /// A queue file exported by the HandBrake GUI could not be parsed.
+    #[error("Invalid HandBrake GUI queue: {source}")]
+    InvalidGuiQueue {
+        /// The underlying JSON error that occurred.
+        source: serde_json::Error,
+    },
+    /// An option of the job requires a newer `HandBrakeCLI` than the one found.
+    #[error("{option} requires HandBrake {required} or later, found '{version}'")]
+    UnsupportedOption {
+        /// The option, e.g., `preserve_hdr_metadata()`.
+        option: String,
+        /// The oldest version supporting the option.
+        required: String,
+        /// The version reported by `HandBrakeCLI --version`.
+        version: String,
+    },
+    /// Two options of the job can't be used together.
+    #[error("Incompatible options: {reason}")]
+    IncompatibleOptions {
+        /// Which options conflict, e.g., `json_output()` with the output written to `stdout`.
+        reason: String,
+    },
+    /// A `HandBrakeCLI` argument list could not be turned into a `JobBuilder`.
+    #[error("Invalid HandBrakeCLI arguments: {reason}")]
+    InvalidArgs {
+        /// Why the arguments are invalid, e.g., the input is missing.
+        reason: String,
+    },
+    /// The input of a job is a file `HandBrakeCLI` can't read, e.g., an image or a text file.
+    #[error("Unsupported input '{path}': {reason}")]
+    UnsupportedInput {
+        /// The path to the input.
+        path: std::path::PathBuf,
+        /// What the input was recognized as, e.g., a PNG image.
+        reason: String,
+    },
+    /// The encoding time or output size of a job could not be estimated from a sample encode.
+    #[error("Failed to estimate the job: {reason}")]
+    EstimationFailed {
+        /// Why the estimate failed, e.g., the sample encode failed.
+        reason: String,
+    },
+    /// A segment of a `SegmentedEncode` failed, or the segments could not be concatenated.
+    #[error("Segmented encode failed: {reason}")]
+    SegmentedEncodeFailed {
+        /// Why the segmented encode failed.
+        reason: String,
+    },
+    /// The samples of a `QualityComparison` could not be encoded or scored.
+    #[error("Quality comparison failed: {reason}")]
+    ComparisonFailed {
+        /// Why the comparison failed, e.g., `ffmpeg` didn't report a score.
+        reason: String,
+    },
+    /// A job could not be sent to a remote worker, or a worker connection failed.
+    #[error("Remote job failed: {reason}")]
+    RemoteFailed {
+        /// Why the remote job failed, e.g., the worker can't be reached.
+        reason: String,
+    },
+    /// A server could not accept a connection, e.g., the `StatusServer`.
+    #[error("Failed to accept a connection: {source}")]
+    AcceptFailed {
+        /// The underlying I/O error.
+        source: std::io::Error,
+    },
     /// A placeholder for any other kind of error.
     #[error("An unknown error occurred")]
     Unknown,
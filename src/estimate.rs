@@ -0,0 +1,342 @@
+//! Estimating the encoding time and output size of a job from short sample encodes.
+
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tokio::io::AsyncWrite;
+
+use crate::error::Error;
+use crate::event::JobEvent;
+use crate::job::{JobBuilder, OutputDestination};
+
+/// The length of the sample encoded to estimate a job.
+pub(crate) const PROBE_SAMPLE: Duration = Duration::from_secs(60);
+
+/// The qualities sampled first when searching for a target size.
+const INITIAL_QUALITIES: [f32; 2] = [18.0, 28.0];
+
+/// The most sample encodes a target size search runs.
+const MAX_QUALITY_PROBES: usize = 4;
+
+/// How far the estimated size may be off the target size, as a fraction of it.
+const SIZE_TOLERANCE: f64 = 0.05;
+
+/// Matches the duration of the source title in the scan log, e.g., `+ duration: 01:59:04`.
+static DURATION_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\+ duration: (\d+):(\d{2}):(\d{2})")
+        .expect("BUG: Failed to compile duration regex")
+});
+
+/// Matches the duration of a chapter of the source title in the scan log, e.g.,
+/// `+ 3: duration 00:05:12` or `+ 3: cells 4->5, 20480 blocks, duration 00:05:12`.
+static CHAPTER_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\+ (\d+): .*duration (\d+):(\d{2}):(\d{2})").expect("BUG: Failed to compile chapter regex")
+});
+
+/// Matches the frame rate of the source title in the scan log,
+/// e.g., `+ size: 1920x1080, pixel aspect: 1/1, display aspect: 1.78, 23.976 fps`.
+static FRAME_RATE_RE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"\+ size: .*, (\d+(?:\.\d+)?) fps").expect("BUG: Failed to compile frame rate regex")
+});
+
+/// The measurements of a sample encode.
+#[derive(Debug)]
+pub(crate) struct Probe {
+    /// The duration of the source title, which the sample is taken from.
+    title_duration: Duration,
+    /// The duration of the part of the title the job encodes.
+    source_duration: Duration,
+    source_fps: Option<f64>,
+    avg_fps: Option<f32>,
+    elapsed: Duration,
+    output_size: u64,
+}
+
+impl Probe {
+    /// Encodes a sample of the job, counting the bytes of its output instead of keeping them.
+    ///
+    /// The sample is taken from the whole title, and extrapolated to the `time_range()` or
+    /// `chapters()` of the job if one is selected.
+    pub(crate) async fn run(mut job: JobBuilder) -> Result<Self, Error> {
+        let output_size = Arc::new(AtomicU64::new(0));
+        job.output = OutputDestination::writer(ByteCounter(Arc::clone(&output_size)));
+        let (time_range, chapters) = (job.time_range, job.chapters.take());
+        let job = job.sample(PROBE_SAMPLE);
+
+        let mut title_duration = None;
+        let mut chapter_durations = Vec::new();
+        let mut source_fps = None;
+        let summary = job
+            .start()?
+            .wait_inspect(|event| {
+                if let JobEvent::Log(log) = event {
+                    // Only the first title is reported when scanning a single title.
+                    if title_duration.is_none() {
+                        title_duration = parse_duration(&log.message);
+                    }
+                    chapter_durations.extend(parse_chapter(&log.message));
+                    if source_fps.is_none() {
+                        source_fps = FRAME_RATE_RE
+                            .captures(&log.message)
+                            .and_then(|caps| caps[1].parse().ok());
+                    }
+                }
+            })
+            .await
+            .map_err(|failure| Error::EstimationFailed {
+                reason: format!("The sample encode failed: {}", failure.message),
+            })?;
+
+        let title_duration = title_duration.ok_or_else(|| Error::EstimationFailed {
+            reason: "The source duration was not reported".to_string(),
+        })?;
+        let source_duration = match (time_range, chapters) {
+            (Some((start, duration)), _) => duration.min(title_duration.saturating_sub(start)),
+            (None, Some((first, last))) => {
+                let selected: Vec<Duration> = chapter_durations
+                    .iter()
+                    .filter(|(number, _)| (first..=last).contains(number))
+                    .map(|(_, duration)| *duration)
+                    .collect();
+                if selected.is_empty() {
+                    return Err(Error::EstimationFailed {
+                        reason: "The chapter durations were not reported".to_string(),
+                    });
+                }
+                selected.iter().sum()
+            }
+            (None, None) => title_duration,
+        };
+        Ok(Probe {
+            title_duration,
+            source_duration,
+            source_fps,
+            avg_fps: summary.avg_fps,
+            elapsed: summary.elapsed,
+            output_size: output_size.load(Ordering::Relaxed),
+        })
+    }
+
+    /// Extrapolates the encoding time of the whole source.
+    ///
+    /// Uses the average speed of the sample if both it and the source frame rate are known,
+    /// otherwise the wall-clock time of the sample, which includes scanning the source.
+    pub(crate) fn duration(&self) -> Duration {
+        match (self.source_fps, self.avg_fps) {
+            (Some(source_fps), Some(avg_fps)) if avg_fps > 0.0 => Duration::from_secs_f64(
+                self.source_duration.as_secs_f64() * source_fps / f64::from(avg_fps),
+            ),
+            _ => self.elapsed.mul_f64(self.scale()),
+        }
+    }
+
+    /// Extrapolates the output size of the whole source, in bytes.
+    pub(crate) fn size(&self) -> u64 {
+        (self.output_size as f64 * self.scale()).round() as u64
+    }
+
+    /// The ratio of the encoded part of the source to the encoded sample.
+    fn scale(&self) -> f64 {
+        let sample = self.title_duration.min(PROBE_SAMPLE);
+        if sample.is_zero() {
+            return 1.0;
+        }
+        self.source_duration.as_secs_f64() / sample.as_secs_f64()
+    }
+}
+
+/// A search for the constant quality that produces a target output size.
+///
+/// The output size shrinks roughly exponentially as the quality value grows, so the qualities
+/// are interpolated linearly against the logarithm of the estimated sizes.
+#[derive(Debug)]
+pub(crate) struct QualitySearch {
+    target: u64,
+    samples: Vec<(f32, u64)>,
+}
+
+impl QualitySearch {
+    pub(crate) fn new(target: u64) -> Self {
+        QualitySearch {
+            target,
+            samples: Vec::new(),
+        }
+    }
+
+    /// Returns the quality to sample next, or `None` once the search is done.
+    pub(crate) fn next_quality(&self) -> Option<f32> {
+        if self.samples.len() >= MAX_QUALITY_PROBES || self.matched().is_some() {
+            return None;
+        }
+        if let Some(quality) = INITIAL_QUALITIES.get(self.samples.len()) {
+            return Some(*quality);
+        }
+        let quality = self.interpolate();
+        // Sampling the same quality again wouldn't improve the estimate.
+        (!self.samples.iter().any(|(q, _)| *q == quality)).then_some(quality)
+    }
+
+    /// Records the estimated output size of a sampled quality.
+    pub(crate) fn record(&mut self, quality: f32, size: u64) {
+        self.samples.push((quality, size));
+    }
+
+    /// Returns the quality that best matches the target size.
+    pub(crate) fn best(&self) -> f32 {
+        self.matched().unwrap_or_else(|| self.interpolate())
+    }
+
+    /// A sampled quality within the tolerance of the target size.
+    fn matched(&self) -> Option<f32> {
+        self.samples
+            .iter()
+            .find(|(_, size)| (*size as f64 - self.target as f64).abs() <= self.target as f64 * SIZE_TOLERANCE)
+            .map(|(quality, _)| *quality)
+    }
+
+    /// Interpolates between the two samples closest to the target size.
+    fn interpolate(&self) -> f32 {
+        let ln = |size: u64| (size.max(1) as f64).ln();
+        let target = ln(self.target);
+        let mut samples = self.samples.clone();
+        samples.sort_by(|a, b| (ln(a.1) - target).abs().total_cmp(&(ln(b.1) - target).abs()));
+        let quality = match samples.as_slice() {
+            [] => return INITIAL_QUALITIES[0],
+            [(quality, _)] => f64::from(*quality),
+            [(q1, s1), (q2, s2), ..] if ln(*s1) == ln(*s2) => f64::from(*q1),
+            [(q1, s1), (q2, s2), ..] => {
+                let (q1, q2) = (f64::from(*q1), f64::from(*q2));
+                q1 + (target - ln(*s1)) * (q2 - q1) / (ln(*s2) - ln(*s1))
+            }
+        };
+        ((quality.clamp(0.0, 51.0) * 10.0).round() / 10.0) as f32
+    }
+}
+
+/// A writer that discards its data, counting the written bytes.
+struct ByteCounter(Arc<AtomicU64>);
+
+impl AsyncWrite for ByteCounter {
+    fn poll_write(self: Pin<&mut Self>, _: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.0.fetch_add(buf.len() as u64, Ordering::Relaxed);
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+fn parse_duration(line: &str) -> Option<Duration> {
+    let caps = DURATION_RE.captures(line)?;
+    let h: u64 = caps[1].parse().ok()?;
+    let m: u64 = caps[2].parse().ok()?;
+    let s: u64 = caps[3].parse().ok()?;
+    Some(Duration::from_secs(h * 3600 + m * 60 + s))
+}
+
+/// Parses the number and duration of a chapter.
+fn parse_chapter(line: &str) -> Option<(u32, Duration)> {
+    let caps = CHAPTER_RE.captures(line)?;
+    let h: u64 = caps[2].parse().ok()?;
+    let m: u64 = caps[3].parse().ok()?;
+    let s: u64 = caps[4].parse().ok()?;
+    Some((caps[1].parse().ok()?, Duration::from_secs(h * 3600 + m * 60 + s)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe(source_duration: u64, source_fps: Option<f64>, avg_fps: Option<f32>, elapsed: u64) -> Probe {
+        Probe {
+            title_duration: Duration::from_secs(source_duration),
+            source_duration: Duration::from_secs(source_duration),
+            source_fps,
+            avg_fps,
+            elapsed: Duration::from_secs(elapsed),
+            output_size: 1000,
+        }
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("  + duration: 01:59:04"), Some(Duration::from_secs(7144)));
+        assert_eq!(parse_duration("  + size: 1920x1080"), None);
+    }
+
+    #[test]
+    fn test_parse_chapter() {
+        assert_eq!(parse_chapter("    + 3: duration 00:05:12"), Some((3, Duration::from_secs(312))));
+        assert_eq!(
+            parse_chapter("    + 12: cells 4->5, 20480 blocks, duration 01:00:00"),
+            Some((12, Duration::from_secs(3600)))
+        );
+        assert_eq!(parse_chapter("  + duration: 01:59:04"), None);
+    }
+
+    #[test]
+    fn test_duration_from_fps() {
+        // 600 seconds at 25 fps are 15000 frames, encoded at 50 fps.
+        assert_eq!(probe(600, Some(25.0), Some(50.0), 999).duration(), Duration::from_secs(300));
+    }
+
+    #[test]
+    fn test_duration_from_elapsed() {
+        assert_eq!(probe(600, None, Some(50.0), 20).duration(), Duration::from_secs(200));
+        // A source shorter than the sample is encoded completely.
+        assert_eq!(probe(30, Some(25.0), None, 20).duration(), Duration::from_secs(20));
+    }
+
+    /// Runs a search against sizes halving every 6 quality steps, starting at 1000 at 18.
+    fn search(target: u64) -> (f32, usize) {
+        let mut search = QualitySearch::new(target);
+        while let Some(quality) = search.next_quality() {
+            let size = 1000.0 * 0.5f64.powf(f64::from(quality - 18.0) / 6.0);
+            search.record(quality, size as u64);
+        }
+        (search.best(), search.samples.len())
+    }
+
+    #[test]
+    fn test_quality_search_interpolates() {
+        assert_eq!(search(500), (24.0, 3));
+        assert_eq!(search(250), (30.0, 3));
+    }
+
+    #[test]
+    fn test_quality_search_stops_at_match() {
+        assert_eq!(search(1010), (18.0, 1));
+    }
+
+    #[test]
+    fn test_quality_search_clamps_quality() {
+        assert_eq!(search(u64::MAX).0, 0.0);
+    }
+
+    #[test]
+    fn test_size() {
+        assert_eq!(probe(600, None, None, 20).size(), 10_000);
+        assert_eq!(probe(30, None, None, 20).size(), 1000);
+    }
+
+    #[test]
+    fn test_size_of_selected_range() {
+        // Two minutes of a ten-minute title are twice the one-minute sample.
+        let probe = Probe {
+            source_duration: Duration::from_secs(120),
+            ..probe(600, None, None, 20)
+        };
+        assert_eq!(probe.size(), 2000);
+    }
+}
@@ -1,31 +1,108 @@
 use serde::Deserialize;
-use std::{process::ExitStatus, time::Duration};
+use std::{
+    path::PathBuf,
+    process::ExitStatus,
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
 
 /// An event emitted by a monitored `HandBrakeCLI` job.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum JobEvent {
     /// The initial job configuration, parsed from HandBrake's JSON output.
     /// This event is emitted once at the beginning of a monitored job.
-    Config(Config),
+    Config(Box<Config>),
+    /// The titles and tracks of the source, parsed from the listing `HandBrakeCLI` writes to
+    /// `stderr` after scanning it, before the encode begins. The lines of the listing are still
+    /// sent as `Log` events.
+    ScanSummary(ScanSummary),
     /// A progress update, typically emitted every second during an encode.
     Progress(Progress),
     /// A log message from the `HandBrakeCLI` `stderr` stream.
     Log(Log),
+    /// A `stderr` line recognized as a problem the job may recover from, e.g., a read error on
+    /// a damaged DVD. Sent instead of a `Log` event.
+    Warning(Diagnostic),
+    /// A `stderr` line recognized as a problem the job likely fails on, e.g., `No title found`.
+    /// Sent instead of a `Log` event.
+    Error(Diagnostic),
     /// A raw fragment of data from the `HandBrakeCLI` `stdout` stream that is not progress information.
-    /// If the job's output destination is `stdout`, this will contain the encoded video data.
+    /// If the job's output destination is `stdout`, this will contain the encoded video data,
+    /// unless it is being read through `JobHandle::take_output_reader()`.
     Fragment(Vec<u8>),
+    /// The free space on the output volume dropped below a threshold configured with
+    /// `JobBuilder::free_space_warnings()`. Each threshold is reported once.
+    LowDiskSpace {
+        /// The free space on the volume, in bytes.
+        free_bytes: u64,
+        /// The threshold that was crossed, in bytes.
+        threshold: u64,
+    },
+    /// The size of the output file, sampled at the interval configured with
+    /// `JobBuilder::output_size_interval()`.
+    OutputSize {
+        /// The size of the output file, in bytes.
+        bytes: u64,
+        /// How fast the output grew since the previous sample, in bytes per second.
+        bytes_per_sec: f64,
+    },
     /// Signals that the `HandBrakeCLI` process has terminated.
-    /// Contains the final `ExitStatus` on success, or a `JobFailure` on error.
+    /// Contains the final `ExitStatus` on success, or a `JobFailure` on error. A job that exits
+    /// unsuccessfully after being cancelled, killed, timed out, or stopped for lack of disk space fails with the matching
+    /// `FailureKind`.
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_done"))]
     Done(Result<ExitStatus, JobFailure>),
 }
 
+/// A `JobEvent` together with the time it was parsed.
+///
+/// Obtained through `JobHandle::timestamped_events()` or `EventStream::timestamped()`. The times
+/// are taken by the background task when the output is parsed, so they are accurate even if the
+/// events are consumed later.
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Timestamped<T> {
+    /// The event.
+    pub event: T,
+    /// When the event was parsed, on the monotonic clock.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub at: Instant,
+    /// The time between spawning the process and parsing the event.
+    pub since_start: Duration,
+    /// When the event was parsed, on the system clock.
+    pub wall_clock: SystemTime,
+    /// The data attached to the job with `JobBuilder::user_data()`.
+    pub user_data: Option<Arc<serde_json::Value>>,
+}
+
+impl<T> Timestamped<T> {
+    /// Stamps an event with the current time and the data of the job.
+    pub(crate) fn now(event: T, started_at: Instant, user_data: &Option<Arc<serde_json::Value>>) -> Self {
+        let at = Instant::now();
+        Timestamped {
+            event,
+            at,
+            since_start: at.duration_since(started_at),
+            wall_clock: SystemTime::now(),
+            user_data: user_data.clone(),
+        }
+    }
+}
+
 /// The full job configuration as reported by `HandBrakeCLI`.
 ///
-/// This struct captures the most relevant fields from the JSON block
-/// that HandBrake prints at the start of a job, providing confirmation
-/// of the settings being used for the encode.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "PascalCase")]
+/// This struct captures the JSON block that HandBrake prints at the start
+/// of a job, providing confirmation of the settings being used for the encode.
+/// Sections that only some HandBrake versions print are optional.
+///
+/// Parsing is tolerant of schema changes between HandBrake versions: unknown fields
+/// are ignored and missing fields are defaulted. Fields of an unexpected type are defaulted
+/// too when parsing the output of a job, and listed in `skipped`. The complete JSON is kept
+/// in `raw` for anything not covered by the typed fields.
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(remote = "Self", default, rename_all = "PascalCase")]
 pub struct Config {
     /// Details about the input source.
     pub source: SourceConfig,
@@ -36,11 +113,79 @@ pub struct Config {
     /// Details about the audio track configuration.
     #[serde(rename = "Audio")]
     pub audio_config: AudioConfig,
+    /// The subtitle tracks and foreign audio search settings.
+    pub subtitle: Option<SubtitleConfig>,
+    /// The video filters, in the order they are applied.
+    pub filters: Option<FiltersConfig>,
+    /// The pixel aspect ratio of the output.
+    #[serde(rename = "PAR")]
+    pub par: Option<PixelAspectRatio>,
+    /// The fields of the JSON block whose type didn't match, e.g., `Audio.AudioList[1].Bitrate`,
+    /// which are left at their defaults.
+    pub skipped: Vec<String>,
+    /// The complete JSON block as printed by `HandBrakeCLI`.
+    ///
+    /// This field is not serialized, as the typed fields already cover it.
+    #[serde(skip)]
+    pub raw: serde_json::Value,
+}
+
+impl<'de> Deserialize<'de> for Config {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        use serde::de::Error;
+
+        let raw = serde_json::Value::deserialize(deserializer)?;
+        let mut config = Config::deserialize(&raw).map_err(D::Error::custom)?;
+        config.raw = raw;
+        Ok(config)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Config {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        Config::serialize(self, serializer)
+    }
+}
+
+impl Config {
+    /// Returns the output width and height, as set by the crop and scale filter.
+    pub fn dimensions(&self) -> Option<(u32, u32)> {
+        self.filters.as_ref()?.filter_list.iter().find_map(|filter| {
+            let settings = filter.settings.as_ref()?;
+            let width = settings.get("width")?.as_u64()?;
+            let height = settings.get("height")?.as_u64()?;
+            Some((width as u32, height as u32))
+        })
+    }
+
+    /// Returns the index and the chapter of `destination.chapter_list` that contains a position
+    /// in the output, e.g., to show which chapter is being encoded.
+    ///
+    /// Returns `None` past the last chapter, or if the duration of a chapter up to the position is
+    /// unknown.
+    pub fn chapter_at(&self, position: Duration) -> Option<(usize, &ChapterConfig)> {
+        let mut end = Duration::ZERO;
+        for (index, chapter) in self.destination.chapter_list.iter().enumerate() {
+            end += chapter.duration?;
+            if position < end {
+                return Some((index, chapter));
+            }
+        }
+        None
+    }
 }
 
 /// Details about the input source from the job configuration.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "PascalCase")]
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default, rename_all = "PascalCase")]
 pub struct SourceConfig {
     /// The path to the input file.
     pub path: std::path::PathBuf,
@@ -49,18 +194,51 @@ pub struct SourceConfig {
 }
 
 /// Details about the output destination from the job configuration.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "PascalCase")]
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default, rename_all = "PascalCase")]
 pub struct DestinationConfig {
     /// The path to the output file.
     pub file: std::path::PathBuf,
     /// The container format (muxer) being used (e.g., "mp4", "mkv").
     pub mux: String,
+    /// Whether chapter markers are written to the output.
+    pub chapter_markers: Option<bool>,
+    /// The chapters of the output.
+    pub chapter_list: Vec<ChapterConfig>,
+}
+
+/// Details for a single chapter.
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default, rename_all = "PascalCase")]
+pub struct ChapterConfig {
+    /// The name of the chapter, if any.
+    pub name: Option<String>,
+    /// The length of the chapter.
+    #[serde(deserialize_with = "deserialize_ticks")]
+    pub duration: Option<Duration>,
+}
+
+/// Deserializes a HandBrake duration object from its `Ticks` field, counted at 90 kHz.
+fn deserialize_ticks<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Ticks {
+        #[serde(rename = "Ticks")]
+        ticks: u64,
+    }
+
+    Ok(Option::<Ticks>::deserialize(deserializer)?
+        .map(|t| Duration::from_nanos(t.ticks.saturating_mul(100_000) / 9)))
 }
 
 /// Details about the video encoding from the job configuration.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "PascalCase")]
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default, rename_all = "PascalCase")]
 pub struct VideoConfig {
     /// The video codec being used (e.g., "x265", "av1").
     pub encoder: String,
@@ -68,29 +246,152 @@ pub struct VideoConfig {
     pub quality: f64,
     /// The name of the preset being used, if any.
     pub preset: Option<String>,
+    /// The encoder profile, e.g. `"main"`.
+    pub profile: Option<String>,
+    /// The encoder level, e.g. `"4.0"`.
+    pub level: Option<String>,
+    /// The encoder tune, e.g. `"film"`.
+    pub tune: Option<String>,
+    /// The advanced encoder options.
+    pub options: Option<String>,
+    /// The target bitrate in kbit/s, when encoding to a bitrate instead of a quality.
+    pub bitrate: Option<u32>,
+    /// Whether the encode runs in multiple passes.
+    pub multi_pass: Option<bool>,
+    /// The color primaries, as an H.273 code point.
+    pub color_primaries: Option<i32>,
+    /// The transfer characteristics, as an H.273 code point.
+    pub color_transfer: Option<i32>,
+    /// The matrix coefficients, as an H.273 code point.
+    pub color_matrix: Option<i32>,
+    /// The color range, as an `AVColorRange` value.
+    pub color_range: Option<i32>,
+    /// The chroma sample location, as an `AVChromaLocation` value.
+    pub chroma_location: Option<i32>,
 }
 
 /// Details about the audio tracks from the job configuration.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "PascalCase")]
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default, rename_all = "PascalCase")]
 pub struct AudioConfig {
     /// A list of all configured audio tracks for the job.
     pub audio_list: Vec<AudioTrackConfig>,
 }
 
 /// Details for a single audio track.
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "PascalCase")]
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default, rename_all = "PascalCase")]
 pub struct AudioTrackConfig {
     /// The name of the audio codec being used (e.g., "aac", "ac3").
     #[serde(rename = "PresetEncoder")]
     pub encoder_name: String,
     /// The bitrate of the audio track.
     pub bitrate: u32,
+    /// The zero-based source track.
+    pub track: Option<u32>,
+    /// The encoder HandBrake resolved, e.g. a passthru fallback, which may differ from
+    /// `encoder_name`.
+    pub encoder: Option<String>,
+    /// The name of the track, if any.
+    pub name: Option<String>,
+    /// The mixdown, e.g. `"stereo"`.
+    pub mixdown: Option<String>,
+    /// The sample rate in Hz, or `0` to keep the source rate.
+    pub samplerate: Option<u32>,
+    /// The quality, when encoding to a quality instead of a bitrate.
+    pub quality: Option<f64>,
+    /// The compression level.
+    pub compression_level: Option<f64>,
+    /// The gain in dB.
+    pub gain: Option<f64>,
+    /// The dynamic range compression.
+    #[serde(rename = "DRC")]
+    pub drc: Option<f64>,
+}
+
+/// Details about the subtitle tracks from the job configuration.
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default, rename_all = "PascalCase")]
+pub struct SubtitleConfig {
+    /// The foreign audio search settings.
+    pub search: Option<SubtitleSearchConfig>,
+    /// A list of all configured subtitle tracks for the job.
+    pub subtitle_list: Vec<SubtitleTrackConfig>,
+}
+
+/// The foreign audio search settings.
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default, rename_all = "PascalCase")]
+pub struct SubtitleSearchConfig {
+    /// Whether the search is enabled.
+    pub enable: bool,
+    /// Whether the found track is burned into the video.
+    pub burn: bool,
+    /// Whether the found track is the default track.
+    pub default: bool,
+    /// Whether only forced subtitles are searched.
+    pub forced: bool,
+}
+
+/// Details for a single subtitle track.
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default, rename_all = "PascalCase")]
+pub struct SubtitleTrackConfig {
+    /// The zero-based source track.
+    pub track: i32,
+    /// The name of the track, if any.
+    pub name: Option<String>,
+    /// Whether the track is burned into the video.
+    pub burn: bool,
+    /// Whether the track is the default track.
+    pub default: bool,
+    /// Whether only forced subtitles of the track are kept.
+    pub forced: bool,
+    /// The offset in milliseconds.
+    pub offset: Option<i64>,
+}
+
+/// Details about the video filters from the job configuration.
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default, rename_all = "PascalCase")]
+pub struct FiltersConfig {
+    /// A list of all video filters, in the order they are applied.
+    pub filter_list: Vec<FilterConfig>,
+}
+
+/// Details for a single video filter.
+#[derive(Debug, Default, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(default, rename_all = "PascalCase")]
+pub struct FilterConfig {
+    /// The `libhb` filter ID.
+    #[serde(rename = "ID")]
+    pub id: i32,
+    /// The filter settings, whose fields depend on the filter.
+    pub settings: Option<serde_json::Value>,
+}
+
+/// A pixel aspect ratio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[serde(rename_all = "PascalCase")]
+pub struct PixelAspectRatio {
+    /// The numerator.
+    pub num: u32,
+    /// The denominator.
+    pub den: u32,
 }
 
 /// A progress update from an ongoing `HandBrakeCLI` job.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "remote", derive(Deserialize))]
 pub struct Progress {
     /// The completion percentage of the current task.
     pub percentage: f32,
@@ -100,20 +401,181 @@ pub struct Progress {
     pub avg_fps: Option<f32>,
     /// The estimated time remaining until completion.
     pub eta: Option<Duration>,
+    /// The position in the media being encoded, derived from the percentage and the duration
+    /// of the output, once a `Config` or `ScanSummary` event told it.
+    pub position: Option<Duration>,
+    /// The stage of the job the update belongs to.
+    pub phase: Phase,
+}
+
+/// The stage of a job a `Progress` update belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "remote", derive(Deserialize))]
+pub enum Phase {
+    /// The video is being encoded.
+    #[default]
+    Encoding,
+    /// The source is being searched for foreign audio subtitles, before the actual encode.
+    ///
+    /// This pass runs when `JobBuilder::subtitle_scan()` is used, and its percentage restarts
+    /// from zero once the encode begins.
+    SubtitleScan,
+    /// The encode is complete and the output file is being finalized.
+    ///
+    /// HandBrake reports no percentage while muxing, so the update has a percentage of 100 and
+    /// no speed. Muxing large files can take a while.
+    Muxing,
 }
 
 /// A log message from the `HandBrakeCLI` process.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "remote", derive(Deserialize))]
 pub struct Log {
     /// The content of the log message.
     pub message: String,
 }
 
+/// The source titles `HandBrakeCLI` listed after its scan at the start of a job.
+///
+/// Only the titles the job scanned are listed, usually the one being encoded.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "remote", derive(Deserialize))]
+pub struct ScanSummary {
+    /// The scanned titles.
+    pub titles: Vec<ScannedTitle>,
+}
+
+/// A source title from the scan listing, e.g., `+ title 1:`.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "remote", derive(Deserialize))]
+pub struct ScannedTitle {
+    /// The number of the title.
+    pub number: u32,
+    /// The duration, to the second.
+    pub duration: Option<Duration>,
+    /// The width and height of the video.
+    pub size: Option<(u32, u32)>,
+    /// The frame rate of the video.
+    pub frame_rate: Option<f64>,
+    /// The audio tracks, in the order of their numbers.
+    pub audio_tracks: Vec<ScannedTrack>,
+    /// The subtitle tracks, in the order of their numbers.
+    pub subtitle_tracks: Vec<ScannedTrack>,
+}
+
+/// An audio or subtitle track from the scan listing, e.g.,
+/// `+ 1, English (AC3) (5.1 ch) (iso639-2: eng), 48000Hz, 448000bps`.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "remote", derive(Deserialize))]
+pub struct ScannedTrack {
+    /// The number of the track, as passed to `JobBuilder::audio_codec()` or `subtitle()`.
+    pub number: u32,
+    /// The description of the track, as listed.
+    pub description: String,
+    /// The ISO 639-2 language code, e.g., `"eng"`.
+    pub language: Option<String>,
+    /// The codec as HandBrake names it, e.g., `"AC3"` or `"PGS"`.
+    pub codec: Option<String>,
+}
+
+/// A `stderr` line of `HandBrakeCLI` recognized as a known problem.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "remote", derive(Deserialize))]
+pub struct Diagnostic {
+    /// What the line was recognized as.
+    pub kind: DiagnosticKind,
+    /// The line, as `HandBrakeCLI` wrote it.
+    pub message: String,
+}
+
+/// The known problems reported as `JobEvent::Warning` or `JobEvent::Error`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "remote", derive(Deserialize))]
+pub enum DiagnosticKind {
+    /// `libdvdread` failed to read from a DVD, e.g., on a scratched disc or a missing `libdvdcss`.
+    DvdRead,
+    /// The DVD or Blu-ray drive refused to read a disc of another region.
+    DiscRegion,
+    /// The video encoder failed to initialize, e.g., with unsupported settings or a hardware
+    /// encoder without a device.
+    EncoderInit,
+    /// The scan found no title to encode, typically as the input is no video.
+    NoTitleFound,
+}
+
+/// A summary of a successfully completed `HandBrakeCLI` job, returned by `JobHandle::wait()`.
+#[derive(Debug, Clone)]
+pub struct JobSummary {
+    /// The final exit status of the `HandBrakeCLI` process.
+    pub status: ExitStatus,
+    /// The path of the encoded file, or `None` if the output was written to `stdout`.
+    pub output: Option<PathBuf>,
+    /// The size of the encoded file in bytes, or `None` if it was written to `stdout` or can't
+    /// be read.
+    pub output_size: Option<u64>,
+    /// The wall-clock time from starting the job until the process exited.
+    pub elapsed: Duration,
+    /// The last average frames per second (FPS) reported by `HandBrakeCLI`, if any.
+    pub avg_fps: Option<f32>,
+}
+
 /// Details of a job failure.
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "remote", derive(Deserialize))]
 pub struct JobFailure {
     /// A message describing the failure.
     pub message: String,
     /// The exit code of the `HandBrakeCLI` process, if available.
     pub exit_code: Option<i32>,
+    /// What caused the failure.
+    pub kind: FailureKind,
+}
+
+/// The cause of a `JobFailure`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "remote", derive(Deserialize))]
+pub enum FailureKind {
+    /// The `HandBrakeCLI` process exited unsuccessfully, or could not be monitored.
+    Process,
+    /// The job ran longer than the timeout configured with `JobBuilder::timeout()`.
+    Timeout,
+    /// The job was cancelled, e.g., with `JobHandle::cancel()` or a cancellation token.
+    Cancelled,
+    /// The job was killed with `JobHandle::kill()`.
+    Killed,
+    /// The job was stopped because the free space on the output volume dropped below the
+    /// minimum configured with `JobBuilder::min_free_space()`.
+    DiskSpace,
+}
+
+/// Serializes the result of `JobEvent::Done`, representing an `ExitStatus` by its exit code.
+#[cfg(feature = "serde")]
+fn serialize_done<S>(result: &Result<ExitStatus, JobFailure>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Status {
+        success: bool,
+        exit_code: Option<i32>,
+    }
+
+    result
+        .as_ref()
+        .map(|status| Status {
+            success: status.success(),
+            exit_code: status.code(),
+        })
+        .serialize(serializer)
 }
@@ -0,0 +1,278 @@
+//! Conversion between `JobBuilder`s and the queue files exported by the HandBrake GUI.
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::job::{
+    InputSource, JobBuilder, OutputDestination, RateControl, SubtitleBurnMode,
+    SubtitleDefaultMode, SubtitleSelection,
+};
+
+/// An entry of a GUI queue export. Older exports contain the job settings directly.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum QueueEntry {
+    Wrapped {
+        #[serde(rename = "Job")]
+        job: QueueJob,
+    },
+    Bare(QueueJob),
+}
+
+/// The job settings of a queue entry, in the JSON format used by `libhb`.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct QueueJob {
+    source: QueueSource,
+    destination: QueueDestination,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    video: Option<QueueVideo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    audio: Option<QueueAudio>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    subtitle: Option<QueueSubtitle>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    filters: Option<QueueFilters>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct QueueSource {
+    path: PathBuf,
+    #[serde(default = "default_title")]
+    title: u32,
+}
+
+fn default_title() -> u32 {
+    1
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct QueueDestination {
+    file: PathBuf,
+    /// The muxer name, e.g. `"av_mp4"`. Some versions write a numeric ID instead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mux: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct QueueVideo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    encoder: Option<String>,
+    /// The constant quality, or a negative value when encoding to a bitrate.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    quality: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    options: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct QueueAudio {
+    #[serde(default)]
+    audio_list: Vec<QueueAudioTrack>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct QueueAudioTrack {
+    /// The zero-based source track.
+    track: u32,
+    encoder: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct QueueSubtitle {
+    #[serde(default)]
+    subtitle_list: Vec<QueueSubtitleTrack>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct QueueSubtitleTrack {
+    /// The zero-based source track, or `-1` for the foreign audio search.
+    track: i32,
+    #[serde(default)]
+    burn: bool,
+    #[serde(default)]
+    default: bool,
+    #[serde(default)]
+    forced: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct QueueFilters {
+    #[serde(default)]
+    filter_list: Vec<QueueFilter>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct QueueFilter {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    settings: Option<serde_json::Value>,
+}
+
+/// Parses a GUI queue export into one `JobBuilder` per entry.
+pub(crate) fn import(handbrake_path: PathBuf, json: &str) -> Result<Vec<JobBuilder>, Error> {
+    let entries: Vec<QueueEntry> =
+        serde_json::from_str(json).map_err(|e| Error::InvalidGuiQueue { source: e })?;
+
+    Ok(entries
+        .into_iter()
+        .map(|entry| match entry {
+            QueueEntry::Wrapped { job } | QueueEntry::Bare(job) => {
+                to_job_builder(handbrake_path.clone(), job)
+            }
+        })
+        .collect())
+}
+
+fn to_job_builder(handbrake_path: PathBuf, job: QueueJob) -> JobBuilder {
+    let mut builder = JobBuilder::new(
+        handbrake_path,
+        InputSource::File(job.source.path),
+        OutputDestination::File(job.destination.file),
+    );
+
+    if let Some(serde_json::Value::String(mux)) = job.destination.mux {
+        builder = builder.format(mux);
+    }
+
+    if let Some(video) = job.video {
+        if let Some(encoder) = video.encoder {
+            builder = builder.video_codec(encoder);
+        }
+        if let Some(quality) = video.quality.filter(|q| *q >= 0.0) {
+            builder = builder.quality(quality as f32);
+        }
+        if let Some(options) = video.options.filter(|o| !o.is_empty()) {
+            builder = builder.advanced_encoder_options(options);
+        }
+    }
+
+    // The scale filter is the only one carrying the output dimensions.
+    let dimensions = job
+        .filters
+        .into_iter()
+        .flat_map(|filters| filters.filter_list)
+        .filter_map(|filter| filter.settings)
+        .find_map(|settings| {
+            let width = settings.get("width")?.as_u64()?;
+            let height = settings.get("height")?.as_u64()?;
+            Some((width as u32, height as u32))
+        });
+    if let Some((width, height)) = dimensions {
+        builder = builder.width(width).height(height);
+    }
+
+    // The GUI counts tracks from zero, while `HandBrakeCLI` counts them from one.
+    for track in job.audio.into_iter().flat_map(|audio| audio.audio_list) {
+        builder = builder.audio_codec(track.track + 1, track.encoder);
+    }
+
+    for track in job.subtitle.into_iter().flat_map(|subtitle| subtitle.subtitle_list) {
+        if track.track < 0 {
+            builder = builder.subtitle_scan();
+            if track.burn {
+                builder = builder.subtitle_burned(SubtitleBurnMode::Native);
+            }
+            continue;
+        }
+        let index = track.track as u32 + 1;
+        builder = builder.subtitle(index);
+        if track.forced {
+            builder = builder.subtitle_forced(index);
+        }
+        if track.default {
+            builder = builder.subtitle_default(SubtitleDefaultMode::Track(index));
+        }
+    }
+
+    builder
+}
+
+/// Converts a `JobBuilder` into a GUI queue entry.
+pub(crate) fn export(builder: &JobBuilder) -> serde_json::Value {
+    // Pipes are written as in the `HandBrakeCLI` arguments, though the GUI can't open them.
+    let source = match &builder.input {
+        InputSource::File(file) | InputSource::Device(file) => file.clone(),
+        InputSource::Stdin | InputSource::Reader(_) => PathBuf::from("pipe:0"),
+    };
+    let destination = match &builder.output {
+        OutputDestination::File(file) => file.clone(),
+        OutputDestination::Stdout | OutputDestination::Writer(_) => PathBuf::from("pipe:1"),
+    };
+
+    // Only the constant quality is carried over; the GUI keeps bitrates in other fields.
+    let quality = match builder.rate_control {
+        Some(RateControl::ConstantQuality(quality)) => Some(f64::from(quality)),
+        _ => None,
+    };
+    let video = (builder.video_codec.is_some()
+        || quality.is_some()
+        || builder.advanced_encoder_options.is_some())
+    .then(|| QueueVideo {
+        encoder: builder.video_codec.clone(),
+        quality,
+        options: builder.advanced_encoder_options.clone(),
+    });
+
+    let mut audio_list: Vec<QueueAudioTrack> = builder
+        .audio_codecs
+        .iter()
+        .map(|(track, encoder)| QueueAudioTrack {
+            track: track.saturating_sub(1),
+            encoder: encoder.clone(),
+        })
+        .collect();
+    audio_list.sort_by_key(|track| track.track);
+
+    let subtitle_list = match &builder.subtitle_selection {
+        Some(SubtitleSelection::Tracks(tracks)) => tracks
+            .iter()
+            .map(|&track| QueueSubtitleTrack {
+                track: track as i32 - 1,
+                burn: false,
+                default: matches!(builder.subtitle_default, Some(SubtitleDefaultMode::Track(t)) if t == track),
+                forced: builder.subtitle_forced == Some(track),
+            })
+            .collect(),
+        Some(SubtitleSelection::Scan) => vec![QueueSubtitleTrack {
+            track: -1,
+            burn: matches!(builder.subtitle_burned, Some(SubtitleBurnMode::Native)),
+            default: false,
+            forced: false,
+        }],
+        None => Vec::new(),
+    };
+
+    let filter_list = match (builder.width, builder.height) {
+        (Some(width), Some(height)) => vec![QueueFilter {
+            settings: Some(serde_json::json!({ "width": width, "height": height })),
+        }],
+        _ => Vec::new(),
+    };
+
+    let job = QueueJob {
+        source: QueueSource {
+            path: source,
+            title: default_title(),
+        },
+        destination: QueueDestination {
+            file: destination,
+            mux: builder.format.clone().map(serde_json::Value::String),
+        },
+        video,
+        audio: (!audio_list.is_empty()).then_some(QueueAudio { audio_list }),
+        subtitle: (!subtitle_list.is_empty()).then_some(QueueSubtitle { subtitle_list }),
+        filters: (!filter_list.is_empty()).then_some(QueueFilters { filter_list }),
+    };
+    serde_json::to_value(QueueEntry::Wrapped { job }).unwrap_or_default()
+}
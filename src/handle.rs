@@ -1,30 +1,115 @@
 use crate::error::Error;
-use crate::event::JobEvent;
+use crate::event::{FailureKind, JobEvent, JobFailure, JobSummary, Progress, Timestamped};
 use async_stream::stream;
 use futures::Stream;
+use std::ffi::OsString;
 use std::io;
+use std::path::PathBuf;
+use std::process::ExitStatus;
 use std::pin::Pin;
 use std::sync::Arc;
-use tokio::process::Child;
-use tokio::sync::{Mutex, mpsc};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tokio::io::{AsyncRead, AsyncWrite, DuplexStream};
+use tokio::process::{Child, ChildStdin};
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
 
 #[cfg(windows)]
 use windows_sys;
-
 /// A handle to a running `HandBrakeCLI` job.
 ///
 /// This struct provides two key functionalities:
 /// 1.  An async stream of `JobEvent`s parsed from the process's output.
 /// 2.  Control methods (`cancel`, `kill`) to manage the underlying process.
+///
+/// Use `split()` to separate the two, e.g. to hand them to different tasks.
 #[derive(Debug)]
 pub struct JobHandle {
-    /// The handle to the child process, shared for control operations.
-    pub(crate) child: Arc<Mutex<Child>>,
-    /// The receiver for job events from the background parsing task.
-    pub(crate) event_rx: mpsc::Receiver<JobEvent>,
+    /// The control half of the handle.
+    pub(crate) controller: JobController,
+    /// The receiver for job events from the background parsing task, present until taken.
+    pub(crate) event_rx: Option<mpsc::Receiver<Timestamped<JobEvent>>>,
+    /// The `stdin` pipe of the process, present until taken when the input is `stdin`.
+    pub(crate) input_writer: Option<ChildStdin>,
+    /// The read end of the video data pipe, present until taken when the output is `stdout`.
+    pub(crate) output_reader: Option<DuplexStream>,
+    /// Tells the background task that `stdout` data should go to the output reader.
+    pub(crate) output_taken: Arc<AtomicBool>,
+    /// The path of the output file, if the output is not `stdout`.
+    pub(crate) output_path: Option<PathBuf>,
+    /// When the process was spawned.
+    pub(crate) started_at: Instant,
+    /// The unparsed `stderr` lines, retained from the start until `raw_lines()` is first called.
+    pub(crate) raw_lines: broadcast::Receiver<String>,
+    /// Whether `raw_lines()` was already called.
+    pub(crate) raw_lines_taken: bool,
+    /// The data attached with `JobBuilder::user_data()`.
+    pub(crate) user_data: Option<Arc<serde_json::Value>>,
+    /// The program and arguments the process was spawned with.
+    pub(crate) command_line: Vec<OsString>,
+    /// How many events were dropped as nobody read them, see `dropped_events()`.
+    pub(crate) dropped_events: Arc<AtomicU64>,
 }
 
-impl JobHandle {
+/// A cloneable controller for a running `HandBrakeCLI` job.
+///
+/// Obtained through `JobHandle::split()` or `JobHandle::controller()`. All clones control the
+/// same process, so a controller can be handed to a supervisor task while the events are
+/// consumed elsewhere.
+#[derive(Debug, Clone)]
+pub struct JobController {
+    /// Sends control operations to the task waiting for the child process.
+    pub(crate) control: mpsc::UnboundedSender<ControlRequest>,
+    /// The latest progress, maintained by the background parsing task.
+    pub(crate) progress_rx: watch::Receiver<Option<Progress>>,
+    /// The lifecycle state, updated by the controller and the background parsing task.
+    pub(crate) state: Arc<watch::Sender<JobState>>,
+    /// The PID of the process, if it was still running when spawned.
+    pub(crate) pid: Option<u32>,
+    /// Whether the process was killed through the controller.
+    pub(crate) killed: Arc<AtomicBool>,
+    /// The Job Object containing the process and any helper processes it started.
+    #[cfg(windows)]
+    pub(crate) job_object: Arc<crate::job_object::JobObject>,
+}
+
+/// An operation on the child process, together with the sender for its result.
+///
+/// The operations are run by the task waiting for the process, which owns the `Child`. So
+/// controlling the job doesn't wait for the process to exit, and a PID can't be reused by
+/// another process before an operation on it completed.
+pub(crate) type ControlRequest = (
+    Box<dyn FnOnce(&mut Child) -> io::Result<()> + Send>,
+    oneshot::Sender<io::Result<()>>,
+);
+
+/// The controllers of the monitored jobs started by a `HandBrake` instance.
+pub(crate) type JobRegistry = Arc<std::sync::Mutex<Vec<JobController>>>;
+
+/// The lifecycle state of a monitored `HandBrakeCLI` job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    /// The process is running.
+    Running,
+    /// A graceful shutdown was requested, but the process has not exited yet.
+    Cancelling,
+    /// The process has exited with the given status.
+    Exited(ExitStatus),
+    /// Waiting for the process failed, so its exit status is unknown.
+    ///
+    /// The process is no longer controlled, and `JobEvent::Done` reports the error.
+    Failed,
+}
+
+impl JobState {
+    /// Returns whether the job has ended, either `Exited` or `Failed`.
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobState::Exited(_) | JobState::Failed)
+    }
+}
+
+impl JobController {
     /// Attempts to gracefully shut down the `HandBrakeCLI` process.
     ///
     /// This is the preferred method for stopping a job.
@@ -36,46 +121,43 @@ impl JobHandle {
     /// Returns an `Error` if the control signal could not be sent, for example if the
     /// process has already terminated.
     pub async fn cancel(&self) -> Result<(), Error> {
-        let child = self.child.lock().await;
-        let pid = child.id().ok_or(Error::ControlFailed {
-            action: "cancel",
-            source: io::Error::new(io::ErrorKind::NotFound, "Process already exited"),
-        })?;
+        self.send_cancel().await?;
+        self.state.send_if_modified(|state| {
+            let running = *state == JobState::Running;
+            if running {
+                *state = JobState::Cancelling;
+            }
+            running
+        });
+        Ok(())
+    }
 
+    async fn send_cancel(&self) -> Result<(), Error> {
         #[cfg(unix)]
         {
-            use nix::sys::signal::{self, Signal};
-            use nix::unistd::Pid;
-            match signal::kill(Pid::from_raw(pid as i32), Signal::SIGINT) {
-                Ok(()) => Ok(()),
-                Err(e) => Err(Error::ControlFailed {
-                    action: "cancel",
-                    source: io::Error::new(
-                        io::ErrorKind::Unsupported,
-                        format!("Failed with errno: {e}"),
-                    ),
-                }),
-            }
+            self.send_signal("cancel", nix::sys::signal::Signal::SIGINT)
+                .await
         }
 
         #[cfg(windows)]
         {
-            // Sending CTRL_BREAK_EVENT to the process group ID (which is the same as the PID
-            // when CREATE_NEW_PROCESS_GROUP is used) will attempt to gracefully shutdown the process.
-            let result = unsafe {
-                use windows_sys::Win32::System::Console::CTRL_BREAK_EVENT;
-                windows_sys::Win32::System::Console::GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, pid)
-            };
-
-            if result == 0 {
+            self.control("cancel", |child| {
+                let pid = running_pid(child)?;
+                // Sending CTRL_BREAK_EVENT to the process group ID (which is the same as the PID
+                // when CREATE_NEW_PROCESS_GROUP is used) will attempt to gracefully shutdown the process.
+                let result = unsafe {
+                    use windows_sys::Win32::System::Console::CTRL_BREAK_EVENT;
+                    windows_sys::Win32::System::Console::GenerateConsoleCtrlEvent(CTRL_BREAK_EVENT, pid)
+                };
+
                 // A non-zero value indicates success.
-                return Err(Error::ControlFailed {
-                    action: "cancel",
-                    source: io::Error::last_os_error(),
-                });
-            } else {
-                return Ok(());
-            }
+                if result == 0 {
+                    Err(io::Error::last_os_error())
+                } else {
+                    Ok(())
+                }
+            })
+            .await
         }
 
         #[cfg(not(any(unix, windows)))]
@@ -96,22 +178,434 @@ impl JobHandle {
     /// This should be used as a last resort, as it may leave orphaned files or
     /// result in a corrupt output file.
     /// - On Unix, it sends a `SIGKILL` signal.
+    /// - On Windows, it terminates the process together with any helper processes it started.
     ///
     /// # Errors
     ///
     /// Returns an `Error` if the process could not be killed, for example if it has
     /// already terminated.
     pub async fn kill(&self) -> Result<(), Error> {
-        let mut child = self.child.lock().await;
-        child.kill().await.map_err(|e| Error::ControlFailed {
+        #[cfg(windows)]
+        self.job_object.terminate().map_err(|e| Error::ControlFailed {
             action: "kill",
             source: e,
+        })?;
+        #[cfg(not(windows))]
+        self.control("kill", Child::start_kill).await?;
+        self.killed.store(true, Ordering::Release);
+        Ok(())
+    }
+
+    /// Suspends the `HandBrakeCLI` process until `resume()` is called.
+    ///
+    /// - On Unix, it sends a `SIGSTOP` signal.
+    /// - Pausing is not supported on other platforms.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the process could not be paused, for example if it has
+    /// already terminated.
+    pub async fn pause(&self) -> Result<(), Error> {
+        #[cfg(unix)]
+        {
+            self.send_signal("pause", nix::sys::signal::Signal::SIGSTOP)
+                .await
+        }
+
+        #[cfg(not(unix))]
+        {
+            Err(Error::ControlFailed {
+                action: "pause",
+                source: io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Pause is not supported on this platform",
+                ),
+            })
+        }
+    }
+
+    /// Resumes a `HandBrakeCLI` process previously suspended with `pause()`.
+    ///
+    /// - On Unix, it sends a `SIGCONT` signal.
+    /// - Resuming is not supported on other platforms.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the process could not be resumed, for example if it has
+    /// already terminated.
+    pub async fn resume(&self) -> Result<(), Error> {
+        #[cfg(unix)]
+        {
+            self.send_signal("resume", nix::sys::signal::Signal::SIGCONT)
+                .await
+        }
+
+        #[cfg(not(unix))]
+        {
+            Err(Error::ControlFailed {
+                action: "resume",
+                source: io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    "Resume is not supported on this platform",
+                ),
+            })
+        }
+    }
+
+    /// Returns the PID of the `HandBrakeCLI` process.
+    ///
+    /// Returns `None` if the process had already exited when it was spawned.
+    pub fn pid(&self) -> Option<u32> {
+        self.pid
+    }
+
+    /// Returns the current lifecycle state of the job.
+    ///
+    /// This does not consume any events; the state is updated by the background task that
+    /// parses the process output.
+    pub fn state(&self) -> JobState {
+        *self.state.borrow()
+    }
+
+    /// Waits until the process has exited.
+    pub(crate) async fn exited(&self) {
+        let mut rx = self.state.subscribe();
+        let _ = rx.wait_for(JobState::is_finished).await;
+    }
+
+    /// Returns a watch receiver holding the latest `Progress` of the job.
+    ///
+    /// The value is `None` until the first progress update has been parsed. Unlike the event
+    /// stream, the watch only retains the most recent value, so it does not need to be drained.
+    pub fn progress_watch(&self) -> watch::Receiver<Option<Progress>> {
+        self.progress_rx.clone()
+    }
+
+    /// Runs an operation on the child process in the task waiting for it, failing the given
+    /// control action if the process already exited.
+    async fn control<F>(&self, action: &'static str, operation: F) -> Result<(), Error>
+    where
+        F: FnOnce(&mut Child) -> io::Result<()> + Send + 'static,
+    {
+        let (reply_tx, reply_rx) = oneshot::channel();
+        // The task stops handling operations once the process was reaped.
+        let result = match self.control.send((Box::new(operation), reply_tx)) {
+            Ok(()) => reply_rx.await.unwrap_or_else(|_| Err(exited())),
+            Err(_) => Err(exited()),
+        };
+        result.map_err(|source| Error::ControlFailed { action, source })
+    }
+
+    #[cfg(unix)]
+    async fn send_signal(
+        &self,
+        action: &'static str,
+        signal: nix::sys::signal::Signal,
+    ) -> Result<(), Error> {
+        use nix::unistd::Pid;
+        self.control(action, move |child| {
+            let pid = running_pid(child)?;
+            nix::sys::signal::kill(Pid::from_raw(pid as i32), signal).map_err(|e| {
+                io::Error::new(io::ErrorKind::Unsupported, format!("Failed with errno: {e}"))
+            })
+        })
+        .await
+    }
+}
+
+/// Returns the PID of a process that hasn't been reaped yet.
+#[cfg(any(unix, windows))]
+fn running_pid(child: &Child) -> io::Result<u32> {
+    child.id().ok_or_else(exited)
+}
+
+fn exited() -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, "Process already exited")
+}
+
+/// Waits for the child process, running the control operations sent meanwhile.
+///
+/// Returns the exit status once the process was reaped.
+pub(crate) async fn wait_for_child(
+    mut child: Child,
+    mut control_rx: mpsc::UnboundedReceiver<ControlRequest>,
+) -> io::Result<ExitStatus> {
+    loop {
+        tokio::select! {
+            result = child.wait() => return result,
+            Some((operation, reply_tx)) = control_rx.recv() => {
+                let _ = reply_tx.send(operation(&mut child));
+            }
+        }
+    }
+}
+
+impl JobHandle {
+    /// Attempts to gracefully shut down the `HandBrakeCLI` process.
+    ///
+    /// See `JobController::cancel()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the control signal could not be sent, for example if the
+    /// process has already terminated.
+    pub async fn cancel(&self) -> Result<(), Error> {
+        self.controller.cancel().await
+    }
+
+    /// Forcefully terminates the `HandBrakeCLI` process immediately.
+    ///
+    /// See `JobController::kill()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the process could not be killed, for example if it has
+    /// already terminated.
+    pub async fn kill(&self) -> Result<(), Error> {
+        self.controller.kill().await
+    }
+
+    /// Suspends the `HandBrakeCLI` process until `resume()` is called.
+    ///
+    /// See `JobController::pause()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the process could not be paused.
+    pub async fn pause(&self) -> Result<(), Error> {
+        self.controller.pause().await
+    }
+
+    /// Resumes a `HandBrakeCLI` process previously suspended with `pause()`.
+    ///
+    /// See `JobController::resume()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the process could not be resumed.
+    pub async fn resume(&self) -> Result<(), Error> {
+        self.controller.resume().await
+    }
+
+    /// Returns a watch receiver holding the latest `Progress` of the job.
+    ///
+    /// See `JobController::progress_watch()`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use handbrake::{HandBrake, InputSource, OutputDestination};
+    /// # use std::path::PathBuf;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let hb = HandBrake::new().await?;
+    /// # let job_handle = hb.job(InputSource::File(PathBuf::from("")),
+    ///                           OutputDestination::File(PathBuf::from(""))).start()?;
+    /// let mut progress = job_handle.progress_watch();
+    /// while progress.changed().await.is_ok() {
+    ///     if let Some(p) = progress.borrow_and_update().as_ref() {
+    ///         println!("{:.2}%", p.percentage);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn progress_watch(&self) -> watch::Receiver<Option<Progress>> {
+        self.controller.progress_watch()
+    }
+
+    /// Returns the PID of the `HandBrakeCLI` process.
+    ///
+    /// See `JobController::pid()`.
+    pub fn pid(&self) -> Option<u32> {
+        self.controller.pid()
+    }
+
+    /// Returns the current lifecycle state of the job.
+    ///
+    /// See `JobController::state()`.
+    pub fn state(&self) -> JobState {
+        self.controller.state()
+    }
+
+    /// Returns the data attached with `JobBuilder::user_data()`.
+    pub fn user_data(&self) -> Option<&serde_json::Value> {
+        self.user_data.as_deref()
+    }
+
+    /// Returns the program and the arguments the process was spawned with, e.g., for an audit
+    /// log of what was executed.
+    ///
+    /// This is the final command line, including the arguments added by `JobBuilder` options
+    /// and `extra_args()`. Arguments are returned as they were passed, so paths that are not
+    /// valid UTF-8 are kept unchanged.
+    pub fn command_line(&self) -> &[OsString] {
+        &self.command_line
+    }
+
+    /// Returns how many events were dropped because 128 events were already waiting to be read.
+    ///
+    /// Only `Progress`, `Log`, and `OutputSize` events are dropped, so that following only
+    /// `progress_watch()` or `state()` doesn't stall the job. All other events, e.g., `Fragment`,
+    /// `Config`, and `Done`, wait until there is room.
+    pub fn dropped_events(&self) -> u64 {
+        self.dropped_events.load(Ordering::Relaxed)
+    }
+
+    /// Returns a cloneable `JobController` for the running job.
+    pub fn controller(&self) -> JobController {
+        self.controller.clone()
+    }
+
+    /// Splits the handle into a cloneable `JobController` and an owned `EventStream`.
+    ///
+    /// The `stdin` writer and `stdout` reader are not part of either half, so take them with
+    /// `take_input_writer()` and `take_output_reader()` before splitting. If the events were
+    /// already taken with `take_events()`, the returned stream is empty.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use handbrake::{HandBrake, InputSource, OutputDestination};
+    /// # use futures::StreamExt;
+    /// # use std::path::PathBuf;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let hb = HandBrake::new().await?;
+    /// # let job_handle = hb.job(InputSource::File(PathBuf::from("")),
+    ///                           OutputDestination::File(PathBuf::from(""))).start()?;
+    /// let (controller, mut events) = job_handle.split();
+    /// tokio::spawn(async move {
+    ///     tokio::time::sleep(std::time::Duration::from_secs(60)).await;
+    ///     let _ = controller.cancel().await;
+    /// });
+    /// while let Some(event) = events.next().await {
+    ///     println!("{:?}", event);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn split(mut self) -> (JobController, EventStream) {
+        let events = self.take_events().unwrap_or_else(|| {
+            let (_, event_rx) = mpsc::channel(1);
+            EventStream { event_rx }
+        });
+        (self.controller, events)
+    }
+
+    /// Takes a writer for feeding the source video into the `stdin` of the `HandBrakeCLI` process.
+    ///
+    /// Only available when the job's input source is `InputSource::Stdin`, and only once;
+    /// subsequent calls return `None`. Drop the writer (or call `shutdown()` on it) once all
+    /// data has been written, so that `HandBrakeCLI` sees the end of the input.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use handbrake::{HandBrake, InputSource, OutputDestination};
+    /// # use std::path::PathBuf;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let hb = HandBrake::new().await?;
+    /// let mut job_handle = hb
+    ///     .job(InputSource::Stdin, OutputDestination::File(PathBuf::from("output.mp4")))
+    ///     .start()?;
+    /// let mut writer = job_handle.take_input_writer().expect("input comes from stdin");
+    /// let mut file = tokio::fs::File::open("input.mkv").await?;
+    /// tokio::io::copy(&mut file, &mut writer).await?;
+    /// drop(writer);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn take_input_writer(&mut self) -> Option<impl AsyncWrite + Send + Unpin + 'static> {
+        self.input_writer.take()
+    }
+
+    /// Takes a reader for the encoded video data written to `stdout`.
+    ///
+    /// Only available when the job's output destination is `OutputDestination::Stdout`, and
+    /// only once; subsequent calls return `None`. From the moment the reader is taken, the
+    /// non-progress `stdout` data bypasses the event channel and is no longer emitted as
+    /// `JobEvent::Fragment`. Progress lines are still parsed and reported as events.
+    ///
+    /// The reader must be drained, as the `HandBrakeCLI` process stalls once the pipe is full.
+    /// Dropping the reader discards any remaining output.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use handbrake::{HandBrake, InputSource, OutputDestination};
+    /// # use std::path::PathBuf;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let hb = HandBrake::new().await?;
+    /// let mut job_handle = hb
+    ///     .job(InputSource::File(PathBuf::from("input.mkv")), OutputDestination::Stdout)
+    ///     .start()?;
+    /// let mut reader = job_handle.take_output_reader().expect("output goes to stdout");
+    /// let mut file = tokio::fs::File::create("output.mkv").await?;
+    /// tokio::io::copy(&mut reader, &mut file).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn take_output_reader(&mut self) -> Option<impl AsyncRead + Send + Unpin + 'static> {
+        let reader = self.output_reader.take()?;
+        self.output_taken.store(true, Ordering::Release);
+        Some(reader)
+    }
+
+    /// Returns a stream of the unparsed lines `HandBrakeCLI` writes to `stderr`.
+    ///
+    /// This is independent of the event stream, e.g., to archive the exact output or to apply
+    /// custom parsing. The lines include the JSON job configuration, and progress written to
+    /// `stdout` is not part of it. The stream ends once the process has closed `stderr`.
+    ///
+    /// The stream returned by the first call starts at the beginning of the output, while
+    /// streams from later calls start at the next line. A stream that falls more than 1024 lines
+    /// behind skips the oldest ones.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use handbrake::{HandBrake, InputSource, OutputDestination};
+    /// # use futures::StreamExt;
+    /// # use std::path::PathBuf;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let hb = HandBrake::new().await?;
+    /// # let mut job_handle = hb.job(InputSource::File(PathBuf::from("")),
+    ///                               OutputDestination::File(PathBuf::from(""))).start()?;
+    /// let mut lines = job_handle.raw_lines();
+    /// tokio::spawn(async move {
+    ///     while let Some(line) = lines.next().await {
+    ///         eprintln!("{}", line);
+    ///     }
+    /// });
+    /// let _ = job_handle.wait().await;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn raw_lines(&mut self) -> impl Stream<Item = String> + Send + Unpin + 'static {
+        let mut rx = self.raw_lines.resubscribe();
+        if !self.raw_lines_taken {
+            self.raw_lines_taken = true;
+            std::mem::swap(&mut rx, &mut self.raw_lines);
+        }
+        Box::pin(stream! {
+            loop {
+                match rx.recv().await {
+                    Ok(line) => yield line,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                }
+            }
         })
     }
 
     /// Returns an async stream of `JobEvent`s from the running job.
     ///
     /// This is the primary way to monitor the state of an encoding job.
+    /// The stream is empty once the events have been taken with `take_events()`.
     ///
     /// # Example
     ///
@@ -136,10 +630,181 @@ impl JobHandle {
     /// ```
     pub fn events(&mut self) -> Pin<Box<impl Stream<Item = JobEvent> + '_>> {
         let s = stream! {
-            while let Some(event) = self.event_rx.recv().await {
-                yield event;
+            if let Some(event_rx) = self.event_rx.as_mut() {
+                while let Some(event) = event_rx.recv().await {
+                    yield event.event;
+                }
             }
         };
         Box::pin(s)
     }
+
+    /// Like `events()`, but yields every `JobEvent` together with the time it was parsed.
+    ///
+    /// This allows measuring latencies and stalls downstream, e.g., the time between two
+    /// progress updates. Both streams consume the same events.
+    pub fn timestamped_events(&mut self) -> Pin<Box<impl Stream<Item = Timestamped<JobEvent>> + '_>> {
+        let s = stream! {
+            if let Some(event_rx) = self.event_rx.as_mut() {
+                while let Some(event) = event_rx.recv().await {
+                    yield event;
+                }
+            }
+        };
+        Box::pin(s)
+    }
+
+    /// Waits for the job to finish, draining its events internally.
+    ///
+    /// This is a convenience for callers that don't need to stream events. Any `Fragment` data
+    /// is discarded, so take the output reader first when encoding to `stdout`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `JobFailure` if the process exited unsuccessfully, could not be waited on, or
+    /// if the events were already taken with `take_events()`.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use handbrake::{HandBrake, InputSource, OutputDestination};
+    /// # use std::path::PathBuf;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let hb = HandBrake::new().await?;
+    /// let job_handle = hb
+    ///     .job(InputSource::File(PathBuf::from("input.mkv")), OutputDestination::File(PathBuf::from("output.mp4")))
+    ///     .preset("Fast 1080p30")
+    ///     .start()?;
+    /// match job_handle.wait().await {
+    ///     Ok(summary) => println!("Encoded {:?} in {:?}", summary.output, summary.elapsed),
+    ///     Err(failure) => eprintln!("Encoding failed: {}", failure.message),
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn wait(self) -> Result<JobSummary, JobFailure> {
+        self.wait_inspect(|_| {}).await
+    }
+
+    /// Like `wait()`, but passes every event to `inspect` before it is discarded.
+    pub(crate) async fn wait_inspect(
+        mut self,
+        mut inspect: impl FnMut(&JobEvent),
+    ) -> Result<JobSummary, JobFailure> {
+        let mut event_rx = self.event_rx.take().ok_or_else(|| JobFailure {
+            message: "The job's events were already taken".to_string(),
+            exit_code: None,
+            kind: FailureKind::Process,
+        })?;
+
+        let mut avg_fps = None;
+        while let Some(Timestamped { event, .. }) = event_rx.recv().await {
+            inspect(&event);
+            match event {
+                JobEvent::Progress(progress) => avg_fps = progress.avg_fps.or(avg_fps),
+                JobEvent::Done(result) => {
+                    let status = result?;
+                    if !status.success() {
+                        return Err(JobFailure {
+                            message: format!("HandBrakeCLI exited unsuccessfully: {}", status),
+                            exit_code: status.code(),
+                            kind: FailureKind::Process,
+                        });
+                    }
+                    let output_size = match &self.output_path {
+                        Some(path) => tokio::fs::metadata(path).await.ok().map(|m| m.len()),
+                        None => None,
+                    };
+                    return Ok(JobSummary {
+                        status,
+                        output: self.output_path,
+                        output_size,
+                        elapsed: self.started_at.elapsed(),
+                        avg_fps,
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        Err(JobFailure {
+            message: "The event stream ended without a completion event".to_string(),
+            exit_code: None,
+            kind: FailureKind::Process,
+        })
+    }
+
+    /// Takes an owned, `'static` stream of the job's `JobEvent`s.
+    ///
+    /// Unlike `events()`, the returned stream does not borrow the handle, so it can be moved
+    /// into a spawned task while the handle is kept around to `cancel()` or `kill()` the job.
+    /// Returns `None` if the events have already been taken.
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # use handbrake::{HandBrake, JobEvent, InputSource, OutputDestination};
+    /// # use futures::StreamExt;
+    /// # use std::path::PathBuf;
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let hb = HandBrake::new().await?;
+    /// # let mut job_handle = hb.job(InputSource::File(PathBuf::from("")),
+    ///                               OutputDestination::File(PathBuf::from(""))).start()?;
+    /// let mut events = job_handle.take_events().expect("events not taken yet");
+    /// tokio::spawn(async move {
+    ///     while let Some(event) = events.next().await {
+    ///         println!("{:?}", event);
+    ///     }
+    /// });
+    /// job_handle.cancel().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn take_events(&mut self) -> Option<EventStream> {
+        self.event_rx.take().map(|event_rx| EventStream { event_rx })
+    }
+}
+
+/// An owned stream of `JobEvent`s, detached from its `JobHandle`.
+///
+/// Obtained through `JobHandle::take_events()`. The stream ends after the `JobEvent::Done` event
+/// has been yielded.
+#[derive(Debug)]
+pub struct EventStream {
+    event_rx: mpsc::Receiver<Timestamped<JobEvent>>,
+}
+
+impl EventStream {
+    /// Turns the stream into one that yields every `JobEvent` together with the time it was parsed.
+    pub fn timestamped(self) -> TimestampedEventStream {
+        TimestampedEventStream {
+            event_rx: self.event_rx,
+        }
+    }
+}
+
+impl Stream for EventStream {
+    type Item = JobEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.event_rx
+            .poll_recv(cx)
+            .map(|event| event.map(|event| event.event))
+    }
+}
+
+/// An owned stream of `Timestamped` `JobEvent`s, obtained through `EventStream::timestamped()`.
+#[derive(Debug)]
+pub struct TimestampedEventStream {
+    event_rx: mpsc::Receiver<Timestamped<JobEvent>>,
+}
+
+impl Stream for TimestampedEventStream {
+    type Item = Timestamped<JobEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.event_rx.poll_recv(cx)
+    }
 }
@@ -1,63 +1,337 @@
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::ffi::{OsStr, OsString};
+use std::ops::{Range, RangeInclusive};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::process::ExitStatus;
 use std::process::Stdio;
-use std::str::FromStr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 
 use futures::StreamExt;
-use futures::io;
 use once_cell::sync::Lazy;
-use regex::bytes::Captures;
-use regex::bytes::Regex;
-use tokio::io::AsyncBufReadExt;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
 use tokio::io::BufReader;
-use tokio::process::Command;
+use tokio::process::{Child, Command};
 use tokio::select;
-use tokio::sync::{Mutex, mpsc};
+use tokio::sync::{broadcast, mpsc, watch};
+use tokio::time::sleep_until;
 use tokio_util::codec::FramedRead;
-use tokio_util::codec::LinesCodec;
+use tokio_util::sync::CancellationToken;
 
+use crate::codec::{LossyLinesCodec, read_line_chunk};
+use crate::disk_space::{DiskSpaceWatch, Sample};
 use crate::error::Error;
-use crate::event::{JobEvent, Log};
-use crate::handle::JobHandle;
-
-static PROGRESS_RE: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(
-        r"Encoding: task \d+ of \d+, (?P<pct>\d{1,2}\.\d{2}) %( \((?P<fps>\d+\.\d{2}) fps, avg (?P<avg_fps>\d+\.\d{2}) fps, ETA (?P<eta>\d{2}h\d{2}m\d{2}s)\))?",
-    )
-    .expect("BUG: Failed to compile progress regex")
+use crate::event::{FailureKind, JobEvent, Log, Phase, Timestamped};
+use crate::handle::{JobController, JobHandle, JobRegistry, JobState};
+use crate::output_size::OutputSizeWatch;
+use crate::progress_line::{find_muxing_line, find_progress_line};
+use crate::scan_listing::ScanListing;
+use crate::profile::Profile;
+
+/// Capacity of the in-memory pipe used to hand `stdout` video data to `JobHandle::take_output_reader()`.
+const OUTPUT_PIPE_CAPACITY: usize = 64 * 1024;
+
+/// Capacity of the buffer `stdout` is read through, the default of `BufReader`.
+const STDOUT_BUFFER_CAPACITY: usize = 8 * 1024;
+
+/// How many unread events a job retains before dropping status updates, see `send_event()`.
+const EVENT_CHANNEL_CAPACITY: usize = 128;
+
+/// How many unread `stderr` lines a `JobHandle::raw_lines()` stream retains before skipping ahead.
+const RAW_LINES_CAPACITY: usize = 1024;
+
+/// The preview a sample encode starts at, the middle one of HandBrake's default 10 previews.
+pub(crate) const SAMPLE_PREVIEW: u32 = 5;
+
+/// How long a job that timed out or runs out of disk space gets to shut down gracefully before
+/// it is killed.
+const TIMEOUT_KILL_GRACE: Duration = Duration::from_secs(10);
+
+/// Copies `stdout` into the writer of an `OutputDestination::Writer`, without progress lines.
+///
+/// `stdout` is drained even after a failed write, so `HandBrakeCLI` never blocks on it.
+async fn pump_output(output: Option<(tokio::process::ChildStdout, BoxedWriter)>, capacity: usize) {
+    let Some((stdout, writer)) = output else {
+        return;
+    };
+    let mut writer = Some(writer);
+    let mut reader = BufReader::with_capacity(capacity, stdout);
+    let mut buf = Vec::new();
+    while let Ok(read) = read_line_chunk(&mut reader, &mut buf).await {
+        if read == 0 {
+            break;
+        }
+        strip_progress(&mut buf);
+        if let Some(w) = writer.as_mut()
+            && w.write_all(&buf).await.is_err()
+        {
+            writer = None;
+        }
+        buf.clear();
+    }
+    if let Some(mut writer) = writer {
+        let _ = writer.shutdown().await;
+    }
+}
+
+/// Waits until the token is cancelled, or forever if there is none.
+async fn token_cancelled(token: Option<CancellationToken>) {
+    match token {
+        Some(token) => token.cancelled_owned().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Matches the release version in the output of `HandBrakeCLI --version`, e.g., `HandBrake 1.7.2`.
+static VERSION_RE: Lazy<regex::Regex> = Lazy::new(|| {
+    regex::Regex::new(r"HandBrake (\d+)\.(\d+)\.(\d+)").expect("BUG: Failed to compile version regex")
 });
 
-/// Parses HandBrake's `HHhMMmSSs` ETA format into a `Duration`.
-fn parse_eta(eta_str: &str) -> Duration {
-    let h_str = &eta_str[0..2];
-    let m_str = &eta_str[3..5];
-    let s_str = &eta_str[6..8];
+pub(crate) fn parse_version(version: &str) -> Option<(u32, u32, u32)> {
+    let caps = VERSION_RE.captures(version)?;
+    Some((caps[1].parse().ok()?, caps[2].parse().ok()?, caps[3].parse().ok()?))
+}
+
+/// Reads the next line of `stderr`, or `None` if it is redirected to a file.
+async fn next_line<R: AsyncRead + Unpin>(reader: &mut Option<FramedRead<R, LossyLinesCodec>>) -> Option<io::Result<String>> {
+    reader.as_mut()?.next().await
+}
+
+/// Appends a line to the job's log file, if any, closing it when the write fails.
+async fn write_log(log_file: &mut Option<(tokio::fs::File, LogLevel)>, line: &str) {
+    if let Some((file, _)) = log_file
+        && file.write_all(format!("{}\n", line).as_bytes()).await.is_err()
+    {
+        *log_file = None;
+    }
+}
+
+/// Waits for the next disk space sample that calls for an action, or forever if there is no watch.
+async fn next_disk_space_sample(watch: &mut Option<DiskSpaceWatch>) -> Sample {
+    match watch {
+        Some(watch) => watch.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Waits for the next output size sample, or forever if the size isn't sampled.
+async fn next_output_size(watch: &mut Option<OutputSizeWatch>) -> (u64, f64) {
+    match watch {
+        Some(watch) => watch.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Sends an event, dropping it instead of waiting if it is a frequent status update and the
+/// channel is full.
+///
+/// A caller that only follows `JobHandle::progress_watch()` or `state()` never reads the events,
+/// and waiting for room would stall the job once `HandBrakeCLI` fills its pipes. The latest
+/// progress is still published on the watch. Events that can't be recovered, e.g., `Fragment`
+/// and `Config`, wait for room instead.
+async fn send_event(
+    event_tx: &mpsc::Sender<Timestamped<JobEvent>>,
+    dropped_events: &AtomicU64,
+    event: Timestamped<JobEvent>,
+) {
+    if !matches!(event.event, JobEvent::Progress(_) | JobEvent::Log(_) | JobEvent::OutputSize { .. }) {
+        let _ = event_tx.send(event).await;
+    } else if let Err(mpsc::error::TrySendError::Full(_)) = event_tx.try_send(event) {
+        dropped_events.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Sleeps until the deadline, or forever if there is none.
+async fn sleep_until_deadline(deadline: Option<tokio::time::Instant>) {
+    match deadline {
+        Some(deadline) => sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Returns the duration of the output, if an event tells it.
+///
+/// The chapters of the output in the `Config` are preferred over the title in the
+/// `ScanSummary`, which may be longer than the encoded range. `Config` is sent after the scan.
+fn known_duration(event: &JobEvent) -> Option<Duration> {
+    match event {
+        JobEvent::Config(config) => config
+            .destination
+            .chapter_list
+            .iter()
+            .map(|chapter| chapter.duration)
+            .sum::<Option<Duration>>()
+            .filter(|duration| !duration.is_zero()),
+        JobEvent::ScanSummary(summary) => summary.titles.first()?.duration,
+        _ => None,
+    }
+}
+
+/// Parses the first progress line or muxing status in `data`, if any.
+///
+/// With `subtitle_scan`, the first of several tasks is the foreign audio search pass.
+fn parse_progress(data: &[u8], subtitle_scan: bool) -> Option<crate::Progress> {
+    if let Some(line) = find_progress_line(data) {
+        let phase = if subtitle_scan && line.task == 1 && line.task_count > 1 {
+            Phase::SubtitleScan
+        } else {
+            Phase::Encoding
+        };
+        return Some(crate::Progress {
+            percentage: line.percentage,
+            fps: line.rate.map(|(fps, _, _)| fps).unwrap_or_default(),
+            avg_fps: line.rate.map(|(_, avg_fps, _)| avg_fps),
+            eta: line.rate.map(|(_, _, eta)| eta),
+            position: None,
+            phase,
+        });
+    }
+    find_muxing_line(data).is_some().then_some(crate::Progress {
+        percentage: 100.0,
+        fps: 0.0,
+        avg_fps: None,
+        eta: None,
+        position: None,
+        phase: Phase::Muxing,
+    })
+}
 
-    let h = h_str.parse::<u64>().unwrap_or(0);
-    let m = m_str.parse::<u64>().unwrap_or(0);
-    let s = s_str.parse::<u64>().unwrap_or(0);
+/// Parses a JSON block written to `stdout` in `--json` mode, e.g., `Progress: {...}`.
+///
+/// Progress blocks of the encode and muxing states become `Progress` events, while other blocks,
+/// such as `Version: {...}`, are reported as logs.
+fn parse_json_block(block: &str) -> Option<JobEvent> {
+    let (name, json) = block.split_once(": ")?;
+    if name != "Progress" {
+        return Some(JobEvent::Log(Log {
+            message: block.trim_end().to_string(),
+        }));
+    }
+    let value: serde_json::Value = match serde_json::from_str(json) {
+        Ok(value) => value,
+        Err(e) => {
+            return Some(JobEvent::Log(Log {
+                message: format!("JSON Parse Error: {}, \n{}", e, block),
+            }));
+        }
+    };
+    let progress = match value.get("State")?.as_str()? {
+        "WORKING" => {
+            let working = value.get("Working")?;
+            let number = |key: &str| working.get(key).and_then(serde_json::Value::as_f64);
+            // The subtitle scan is reported as its own pass, with the ID -1.
+            let phase = match working.get("PassID").and_then(serde_json::Value::as_i64) {
+                Some(-1) => Phase::SubtitleScan,
+                _ => Phase::Encoding,
+            };
+            crate::Progress {
+                percentage: (number("Progress")? * 100.0) as f32,
+                fps: number("Rate").unwrap_or_default() as f32,
+                avg_fps: number("RateAvg").map(|fps| fps as f32),
+                // Some versions only report the remaining time split into its components.
+                eta: number("ETASeconds")
+                    .or_else(|| Some(number("Hours")? * 3600.0 + number("Minutes")? * 60.0 + number("Seconds")?))
+                    .filter(|secs| *secs >= 0.0)
+                    .map(Duration::from_secs_f64),
+                position: None,
+                phase,
+            }
+        }
+        "MUXING" => crate::Progress {
+            percentage: 100.0,
+            fps: 0.0,
+            avg_fps: None,
+            eta: None,
+            position: None,
+            phase: Phase::Muxing,
+        },
+        _ => return None,
+    };
+    Some(JobEvent::Progress(progress))
+}
 
-    Duration::from_secs(h * 3600 + m * 60 + s)
+/// Removes all progress lines and muxing statuses from `data`, in place.
+fn strip_progress(data: &mut Vec<u8>) {
+    strip_matches(data, |data| find_progress_line(data).map(|line| line.range));
+    strip_matches(data, find_muxing_line);
 }
 
-fn parse_caps<T>(caps: &Captures, name: &str) -> Option<T>
-where
-    T: Default + FromStr,
-{
-    caps.name(name).map(|v| String::from_utf8_lossy(v.as_bytes())
-        .parse::<T>()
-        .unwrap_or_default())
+/// Removes all matches of `find` from `data` by moving the remaining bytes forward.
+fn strip_matches(data: &mut Vec<u8>, find: impl Fn(&[u8]) -> Option<Range<usize>>) {
+    let mut kept = 0;
+    let mut start = 0;
+    while let Some(range) = find(&data[start..]) {
+        let range = start + range.start..start + range.end;
+        data.copy_within(start..range.start, kept);
+        kept += range.start - start;
+        start = range.end;
+    }
+    data.copy_within(start.., kept);
+    data.truncate(kept + data.len() - start);
 }
 
 /// Represents the input source for a `HandBrakeCLI` job.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum InputSource {
     /// Use a file as the input source.
     File(PathBuf),
     /// Use `stdin` as the input source.
     Stdin,
+    /// Read a disc directly from an optical drive, e.g., `/dev/sr0` on Linux or `D:\` on Windows.
+    ///
+    /// HandBrake reads DVDs and Blu-rays from the drive itself, so it needs permission to open
+    /// the device.
+    Device(PathBuf),
+    /// Copy the input from an async reader into `stdin`, e.g., a network stream.
+    ///
+    /// The copy runs in a background task, so no writer needs to be taken from the `JobHandle`.
+    /// The reader can only be used by a single job, and can't be serialized.
+    #[serde(skip)]
+    Reader(InputReader),
+}
+
+impl InputSource {
+    /// Creates an `InputSource::Reader` from an async reader.
+    pub fn reader(reader: impl AsyncRead + Send + 'static) -> Self {
+        InputSource::Reader(InputReader::new(reader))
+    }
+}
+
+/// An async reader feeding a job through `InputSource::Reader`.
+///
+/// Clones share the same reader, which is taken by the first job that is spawned with it.
+#[derive(Clone)]
+pub struct InputReader(Arc<std::sync::Mutex<Option<BoxedReader>>>);
+
+type BoxedReader = Pin<Box<dyn AsyncRead + Send>>;
+
+impl InputReader {
+    /// Wraps an async reader.
+    pub fn new(reader: impl AsyncRead + Send + 'static) -> Self {
+        InputReader(Arc::new(std::sync::Mutex::new(Some(Box::pin(reader)))))
+    }
+
+    /// Takes the reader, or returns `None` if a job already took it.
+    fn take(&self) -> Option<BoxedReader> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+impl std::fmt::Debug for InputReader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InputReader").finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for InputReader {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
 }
 
 impl From<PathBuf> for InputSource {
@@ -72,12 +346,65 @@ impl From<&str> for InputSource {
     }
 }
 
+impl From<String> for InputSource {
+    fn from(p: String) -> Self {
+        InputSource::File(p.into())
+    }
+}
+
 /// Represents the output destination for a `HandBrakeCLI` job.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum OutputDestination {
     /// Write the output to a file.
     File(PathBuf),
     /// Write the output to `stdout`.
     Stdout,
+    /// Copy the output from `stdout` into an async writer, e.g., a network stream.
+    ///
+    /// The copy runs in the background task, and applies backpressure to `HandBrakeCLI` when the
+    /// writer is slow. The writer can only be used by a single job, and can't be serialized.
+    #[serde(skip)]
+    Writer(OutputWriter),
+}
+
+impl OutputDestination {
+    /// Creates an `OutputDestination::Writer` from an async writer.
+    pub fn writer(writer: impl AsyncWrite + Send + 'static) -> Self {
+        OutputDestination::Writer(OutputWriter::new(writer))
+    }
+}
+
+/// An async writer receiving the output of a job through `OutputDestination::Writer`.
+///
+/// Clones share the same writer, which is taken by the first job that is spawned with it.
+#[derive(Clone)]
+pub struct OutputWriter(Arc<std::sync::Mutex<Option<BoxedWriter>>>);
+
+type BoxedWriter = Pin<Box<dyn AsyncWrite + Send>>;
+
+impl OutputWriter {
+    /// Wraps an async writer.
+    pub fn new(writer: impl AsyncWrite + Send + 'static) -> Self {
+        OutputWriter(Arc::new(std::sync::Mutex::new(Some(Box::pin(writer)))))
+    }
+
+    /// Takes the writer, or returns `None` if a job already took it.
+    fn take(&self) -> Option<BoxedWriter> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+impl std::fmt::Debug for OutputWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OutputWriter").finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for OutputWriter {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
 }
 
 impl From<PathBuf> for OutputDestination {
@@ -92,7 +419,15 @@ impl From<&str> for OutputDestination {
     }
 }
 
+impl From<String> for OutputDestination {
+    fn from(p: String) -> Self {
+        OutputDestination::File(p.into())
+    }
+}
+
 /// Represents the subtitle selection mode.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SubtitleSelection {
     /// Select specific subtitle tracks by their index.
     Tracks(Vec<u32>),
@@ -101,6 +436,8 @@ pub enum SubtitleSelection {
 }
 
 /// Represents the subtitle burn-in mode as per user request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SubtitleBurnMode {
     /// Burn subtitles from foreign language audio tracks marked as "forced".
     Native,
@@ -109,6 +446,8 @@ pub enum SubtitleBurnMode {
 }
 
 /// Represents the default subtitle track selection.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum SubtitleDefaultMode {
     /// Set a specific track as the default.
     Track(u32),
@@ -116,10 +455,24 @@ pub enum SubtitleDefaultMode {
     None,
 }
 
+/// Which HDR dynamic metadata is passed through to the output. See
+/// `JobBuilder::preserve_hdr_metadata()`.
+///
+/// The static HDR10 metadata, i.e., the mastering display and content light levels, is passed
+/// through by `HandBrakeCLI` whenever the encoder supports it, without an option.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PreserveHdrMetadata {
+    /// HDR10+ metadata.
     Hdr10,
+    /// The Dolby Vision RPU. Requires an encoder supporting it, e.g., `x265_10bit` or `svt_av1_10bit`.
     DolbyVision,
-    All
+    /// Both HDR10+ and Dolby Vision metadata.
+    All,
+    /// No dynamic metadata, e.g., to get a plain HDR10 output from a Dolby Vision source.
+    ///
+    /// `HandBrakeCLI` uses `--no-hdr-dynamic-metadata`.
+    Disabled,
 }
 
 impl std::fmt::Display for PreserveHdrMetadata {
@@ -128,38 +481,570 @@ impl std::fmt::Display for PreserveHdrMetadata {
             PreserveHdrMetadata::Hdr10 => f.write_str("hdr10plus"),
             PreserveHdrMetadata::DolbyVision => f.write_str("dolbyvision"),
             PreserveHdrMetadata::All => f.write_str("all"),
+            PreserveHdrMetadata::Disabled => f.write_str("none"),
+        }
+    }
+}
+
+/// How the video bitrate is controlled. See `JobBuilder::rate_control()`.
+///
+/// The modes are mutually exclusive, so setting one replaces the other.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateControl {
+    /// A constant quality (RF), typically from 0 to 51, where lower is better.
+    ///
+    /// `HandBrakeCLI` uses `--quality <value>`.
+    ConstantQuality(f32),
+    /// An average bitrate.
+    ///
+    /// `HandBrakeCLI` uses `--vb <kbps>`, and `--two-pass` and `--turbo`.
+    Bitrate {
+        /// The average bitrate in kbit/s.
+        kbps: u32,
+        /// Whether to analyze the video in a first pass, to distribute the bitrate better.
+        two_pass: bool,
+        /// Whether to speed up the first pass. Only applies with `two_pass`.
+        turbo: bool,
+    },
+}
+
+/// A video filter that a preset may enable. See `JobBuilder::disable_filter()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Filter {
+    /// Detects combing, to only deinterlace the frames that need it.
+    CombDetect,
+    /// The Yadif deinterlacer.
+    Deinterlace,
+    /// The Bwdif deinterlacer.
+    Bwdif,
+    /// The decomb deinterlacer.
+    Decomb,
+    /// Removes telecine, e.g., from NTSC film sources.
+    Detelecine,
+    /// The hqdn3d denoiser.
+    Hqdn3d,
+    /// The NLMeans denoiser.
+    Nlmeans,
+    /// Chroma smoothing.
+    ChromaSmooth,
+    /// The unsharp sharpener.
+    Unsharp,
+    /// The lapsharp sharpener.
+    Lapsharp,
+    /// Deblocking.
+    Deblock,
+    /// Grayscale encoding.
+    Grayscale,
+}
+
+impl std::fmt::Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Filter::CombDetect => f.write_str("comb-detect"),
+            Filter::Deinterlace => f.write_str("deinterlace"),
+            Filter::Bwdif => f.write_str("bwdif"),
+            Filter::Decomb => f.write_str("decomb"),
+            Filter::Detelecine => f.write_str("detelecine"),
+            Filter::Hqdn3d => f.write_str("hqdn3d"),
+            Filter::Nlmeans => f.write_str("nlmeans"),
+            Filter::ChromaSmooth => f.write_str("chroma-smooth"),
+            Filter::Unsharp => f.write_str("unsharp"),
+            Filter::Lapsharp => f.write_str("lapsharp"),
+            Filter::Deblock => f.write_str("deblock"),
+            Filter::Grayscale => f.write_str("grayscale"),
+        }
+    }
+}
+
+/// The settings of comb detection. See `JobBuilder::comb_detect()`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CombDetect {
+    /// The default settings.
+    Default,
+    /// Detects more frames as combed, e.g., for sources with faint combing.
+    Permissive,
+    /// Detects combing faster, but less reliably.
+    Fast,
+    /// Custom settings, e.g., `"mode=3:spatial-metric=2"`.
+    Custom(String),
+}
+
+impl std::fmt::Display for CombDetect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CombDetect::Default => f.write_str("default"),
+            CombDetect::Permissive => f.write_str("permissive"),
+            CombDetect::Fast => f.write_str("fast"),
+            CombDetect::Custom(settings) => f.write_str(settings),
+        }
+    }
+}
+
+/// How the channels of an audio track are mixed down. See `JobBuilder::audio_mixdown()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Mixdown {
+    /// Keeps the channel layout of the source, for encoders that support it.
+    None,
+    /// A single channel.
+    Mono,
+    /// Only the left channel of the source.
+    LeftOnly,
+    /// Only the right channel of the source.
+    RightOnly,
+    /// Two channels.
+    Stereo,
+    /// Two channels with Dolby Surround matrix encoding.
+    DolbySurround,
+    /// Two channels with Dolby Pro Logic II matrix encoding.
+    DolbyProLogicII,
+    /// 5.1 channels.
+    FivePointOne,
+    /// 6.1 channels.
+    SixPointOne,
+    /// 7.1 channels.
+    SevenPointOne,
+    /// 5 front channels and 2 LFE channels.
+    FivePointTwoLfe,
+}
+
+impl std::fmt::Display for Mixdown {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mixdown::None => f.write_str("none"),
+            Mixdown::Mono => f.write_str("mono"),
+            Mixdown::LeftOnly => f.write_str("left_only"),
+            Mixdown::RightOnly => f.write_str("right_only"),
+            Mixdown::Stereo => f.write_str("stereo"),
+            Mixdown::DolbySurround => f.write_str("dpl1"),
+            Mixdown::DolbyProLogicII => f.write_str("dpl2"),
+            Mixdown::FivePointOne => f.write_str("5point1"),
+            Mixdown::SixPointOne => f.write_str("6point1"),
+            Mixdown::SevenPointOne => f.write_str("7point1"),
+            Mixdown::FivePointTwoLfe => f.write_str("5_2_lfe"),
+        }
+    }
+}
+
+/// The color matrix signaled by the output. See `JobBuilder::color_matrix()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorMatrix {
+    /// ITU-R BT.601, used by standard definition sources.
+    Bt601,
+    /// ITU-R BT.709, used by high definition sources.
+    Bt709,
+    /// ITU-R BT.2020, used by ultra high definition and HDR sources.
+    Bt2020,
+}
+
+impl std::fmt::Display for ColorMatrix {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorMatrix::Bt601 => f.write_str("601"),
+            ColorMatrix::Bt709 => f.write_str("709"),
+            ColorMatrix::Bt2020 => f.write_str("2020"),
+        }
+    }
+}
+
+/// The range of the color values. See `JobBuilder::color_range()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorRange {
+    /// The limited, or TV, range, e.g., 16 to 235 for 8-bit luma.
+    Limited,
+    /// The full, or PC, range, e.g., 0 to 255 for 8-bit luma.
+    Full,
+}
+
+impl std::fmt::Display for ColorRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorRange::Limited => f.write_str("limited"),
+            ColorRange::Full => f.write_str("full"),
+        }
+    }
+}
+
+/// The color primaries. See `JobBuilder::color_primaries()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorPrimaries {
+    /// ITU-R BT.709.
+    Bt709,
+    /// ITU-R BT.470 BG, used by PAL sources.
+    Bt470bg,
+    /// SMPTE 170M, used by NTSC sources.
+    Smpte170m,
+    /// ITU-R BT.2020.
+    Bt2020,
+}
+
+impl std::fmt::Display for ColorPrimaries {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorPrimaries::Bt709 => f.write_str("bt709"),
+            ColorPrimaries::Bt470bg => f.write_str("bt470bg"),
+            ColorPrimaries::Smpte170m => f.write_str("smpte170m"),
+            ColorPrimaries::Bt2020 => f.write_str("bt2020"),
+        }
+    }
+}
+
+/// The transfer characteristics. See `JobBuilder::color_transfer()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColorTransfer {
+    /// ITU-R BT.709, used by SDR sources.
+    Bt709,
+    /// SMPTE ST 2084, or PQ, used by HDR10 and Dolby Vision sources.
+    Smpte2084,
+    /// ARIB STD-B67, or HLG.
+    AribStdB67,
+}
+
+impl std::fmt::Display for ColorTransfer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorTransfer::Bt709 => f.write_str("bt709"),
+            ColorTransfer::Smpte2084 => f.write_str("smpte2084"),
+            ColorTransfer::AribStdB67 => f.write_str("arib-std-b67"),
         }
     }
 }
 
+/// What a job's log file contains. See `JobBuilder::log_file()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    /// The `stderr` output of `HandBrakeCLI`, like the activity log of the HandBrake GUI.
+    Activity,
+    /// The `stderr` output, and a line for every parsed progress update and the completion.
+    Events,
+}
+
+/// A serializable description of a job, covering every `JobBuilder` option except the path to
+/// `HandBrakeCLI` and the options that only exist at runtime, such as a cancellation token.
+///
+/// Specs can be stored in configuration files or sent between processes, and turned back into a
+/// `JobBuilder` with `HandBrake::job_from_spec()`. Only the input and output are required when
+/// deserializing.
+///
+/// # Example
+///
+/// ```rust
+/// # use handbrake::{JobSpec, RateControl};
+/// let spec: JobSpec = serde_json::from_str(r#"{
+///     "input": { "file": "input.mkv" },
+///     "output": { "file": "output.mp4" },
+///     "preset": "Fast 1080p30",
+///     "rate_control": { "constant_quality": 22.0 }
+/// }"#).unwrap();
+/// assert_eq!(spec.preset.as_deref(), Some("Fast 1080p30"));
+/// assert_eq!(spec.rate_control, Some(RateControl::ConstantQuality(22.0)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JobSpec {
+    /// The input source for the job.
+    pub input: InputSource,
+    /// The output destination for the job.
+    pub output: OutputDestination,
+    /// See `JobBuilder::import_gui_presets()`.
+    #[serde(default)]
+    pub import_gui_presets: bool,
+    /// See `JobBuilder::preset()`.
+    pub preset: Option<String>,
+    /// See `JobBuilder::video_codec()`.
+    pub video_codec: Option<String>,
+    /// See `JobBuilder::advanced_encoder_options()`.
+    pub advanced_encoder_options: Option<String>,
+    /// See `JobBuilder::preserve_hdr_metadata()`.
+    pub preserve_hdr_metadata: Option<PreserveHdrMetadata>,
+    /// See `JobBuilder::width()`.
+    pub width: Option<u32>,
+    /// See `JobBuilder::height()`.
+    pub height: Option<u32>,
+    /// See `JobBuilder::max_width()`.
+    #[serde(default)]
+    pub max_width: Option<u32>,
+    /// See `JobBuilder::max_height()`.
+    #[serde(default)]
+    pub max_height: Option<u32>,
+    /// See `JobBuilder::keep_display_aspect()`.
+    #[serde(default)]
+    pub keep_display_aspect: Option<bool>,
+    /// See `JobBuilder::modulus()`.
+    #[serde(default)]
+    pub modulus: Option<u32>,
+    /// See `JobBuilder::display_width()`.
+    #[serde(default)]
+    pub display_width: Option<u32>,
+    /// See `JobBuilder::pixel_aspect()`.
+    #[serde(default)]
+    pub pixel_aspect: Option<(u32, u32)>,
+    /// See `JobBuilder::itu_par()`.
+    #[serde(default)]
+    pub itu_par: bool,
+    /// See `JobBuilder::color_matrix()`.
+    #[serde(default)]
+    pub color_matrix: Option<ColorMatrix>,
+    /// See `JobBuilder::color_range()`.
+    #[serde(default)]
+    pub color_range: Option<ColorRange>,
+    /// See `JobBuilder::color_primaries()`.
+    #[serde(default)]
+    pub color_primaries: Option<ColorPrimaries>,
+    /// See `JobBuilder::color_transfer()`.
+    #[serde(default)]
+    pub color_transfer: Option<ColorTransfer>,
+    /// See `JobBuilder::disable_filter()`.
+    #[serde(default)]
+    pub disabled_filters: BTreeSet<Filter>,
+    /// See `JobBuilder::comb_detect()`.
+    #[serde(default)]
+    pub comb_detect: Option<CombDetect>,
+    /// The audio codec per track. See `JobBuilder::audio_codec()`.
+    #[serde(default)]
+    pub audio_codecs: HashMap<u32, String>,
+    /// See `JobBuilder::audio_mixdown()`.
+    #[serde(default)]
+    pub audio_mixdowns: HashMap<u32, Mixdown>,
+    /// See `JobBuilder::normalize_mix()`.
+    #[serde(default)]
+    pub normalize_mix: HashMap<u32, bool>,
+    /// See `JobBuilder::audio_quality()`.
+    #[serde(default)]
+    pub audio_qualities: HashMap<u32, f32>,
+    /// See `JobBuilder::rate_control()`, `JobBuilder::quality()`, and
+    /// `JobBuilder::video_bitrate()`.
+    #[serde(default)]
+    pub rate_control: Option<RateControl>,
+    /// See `JobBuilder::no_two_pass()`.
+    #[serde(default)]
+    pub no_two_pass: bool,
+    /// See `JobBuilder::format()`.
+    pub format: Option<String>,
+    /// See `JobBuilder::subtitle()` and `JobBuilder::subtitle_scan()`.
+    pub subtitle_selection: Option<SubtitleSelection>,
+    /// See `JobBuilder::subtitle_lang()`.
+    #[serde(default)]
+    pub subtitle_langs: Vec<String>,
+    /// See `JobBuilder::native_language()`.
+    #[serde(default)]
+    pub native_language: Option<String>,
+    /// See `JobBuilder::native_dub()`.
+    #[serde(default)]
+    pub native_dub: bool,
+    /// See `JobBuilder::subtitle_burned()`.
+    pub subtitle_burned: Option<SubtitleBurnMode>,
+    /// See `JobBuilder::subtitle_forced()`.
+    pub subtitle_forced: Option<u32>,
+    /// See `JobBuilder::subtitle_default()`.
+    pub subtitle_default: Option<SubtitleDefaultMode>,
+    /// See `JobBuilder::srt_file()`.
+    pub srt_file: Option<String>,
+    /// See `JobBuilder::ssa_file()`.
+    pub ssa_file: Option<String>,
+    /// See `JobBuilder::chapters()`.
+    #[serde(default)]
+    pub chapters: Option<(u32, u32)>,
+    /// See `JobBuilder::angle()`.
+    #[serde(default)]
+    pub angle: Option<u32>,
+    /// See `JobBuilder::dvdnav()`.
+    #[serde(default)]
+    pub no_dvdnav: bool,
+    /// See `JobBuilder::time_range()`.
+    #[serde(default)]
+    pub time_range: Option<(Duration, Duration)>,
+    /// See `JobBuilder::sample()`.
+    #[serde(default)]
+    pub sample: Option<Duration>,
+    /// See `JobBuilder::verbose()`.
+    #[serde(default)]
+    pub verbosity: Option<u8>,
+    /// See `JobBuilder::json_output()`.
+    #[serde(default)]
+    pub json_output: bool,
+    /// See `JobBuilder::extra_args()`.
+    #[serde(default)]
+    pub extra_args: Vec<String>,
+    /// See `JobBuilder::user_data()`.
+    #[serde(default)]
+    pub user_data: Option<serde_json::Value>,
+    /// See `JobBuilder::timeout()`.
+    pub timeout: Option<Duration>,
+    /// See `JobBuilder::stdout_buffer_capacity()`.
+    #[serde(default)]
+    pub stdout_buffer_capacity: Option<usize>,
+    /// See `JobBuilder::stdout_chunk_size()`.
+    #[serde(default)]
+    pub stdout_chunk_size: Option<usize>,
+    /// See `JobBuilder::check_input()`.
+    #[serde(default)]
+    pub check_input: bool,
+    /// See `JobBuilder::min_free_space()`.
+    #[serde(default)]
+    pub min_free_space: Option<u64>,
+    /// See `JobBuilder::free_space_warnings()`.
+    #[serde(default)]
+    pub free_space_warnings: Vec<u64>,
+    /// See `JobBuilder::output_size_interval()`.
+    #[serde(default)]
+    pub output_size_interval: Option<Duration>,
+    /// See `JobBuilder::cpu_affinity()`.
+    pub cpu_affinity: Option<Vec<usize>>,
+    /// See `JobBuilder::log_file()`.
+    #[serde(default)]
+    pub log_file: Option<(PathBuf, LogLevel)>,
+    /// See `JobBuilder::locale()` and `JobBuilder::inherit_locale()`.
+    #[serde(default = "default_locale")]
+    pub locale: Option<String>,
+    /// See `JobBuilder::env()`.
+    #[serde(default)]
+    pub envs: BTreeMap<String, String>,
+    /// See `JobBuilder::env_clear()`.
+    #[serde(default)]
+    pub env_clear: bool,
+    /// See `JobBuilder::current_dir()`.
+    #[serde(default)]
+    pub current_dir: Option<PathBuf>,
+    /// See `JobBuilder::stderr_file()`.
+    #[serde(default)]
+    pub stderr_file: Option<PathBuf>,
+}
+
+/// The locale `HandBrakeCLI` runs with by default, whose number format the parser expects.
+fn default_locale() -> Option<String> {
+    Some("C".to_string())
+}
+
 /// A fluent builder for configuring a `HandBrakeCLI` encoding job.
+///
+/// A configured builder can be cloned as a template for many inputs, changing only the input and
+/// output of every clone. Clones share the cancellation token, notifier, and any
+/// `InputSource::Reader` or `OutputDestination::Writer`, which only the first spawned job can use.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use handbrake::{HandBrake, InputSource, OutputDestination};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let hb = HandBrake::new().await?;
+/// let template = hb
+///     .job(InputSource::Stdin, OutputDestination::Stdout)
+///     .preset("Fast 1080p30")
+///     .quality(22.0);
+/// for episode in ["e01", "e02", "e03"] {
+///     template
+///         .clone()
+///         .input(format!("{episode}.mkv"))
+///         .output(format!("{episode}.mp4"))
+///         .status()
+///         .await?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
 pub struct JobBuilder {
     // The path to the HandBrakeCLI executable, copied from HandBrake instance
-    handbrake_path: PathBuf,
+    pub(crate) handbrake_path: PathBuf,
     // The input source for the job
-    input: InputSource,
+    pub(crate) input: InputSource,
     // The output destination for the job
-    output: OutputDestination,
+    pub(crate) output: OutputDestination,
 
     // Configuration options, stored to ensure "last call wins"
-    import_gui_presets: bool,
-    preset: Option<String>,
-    video_codec: Option<String>,
-    advanced_encoder_options: Option<String>,
-    preserve_hdr_metadata: Option<PreserveHdrMetadata>,
-    width: Option<u32>,
-    height: Option<u32>,
+    pub(crate) import_gui_presets: bool,
+    pub(crate) preset: Option<String>,
+    pub(crate) video_codec: Option<String>,
+    pub(crate) advanced_encoder_options: Option<String>,
+    pub(crate) preserve_hdr_metadata: Option<PreserveHdrMetadata>,
+    pub(crate) width: Option<u32>,
+    pub(crate) height: Option<u32>,
+    pub(crate) max_width: Option<u32>,
+    pub(crate) max_height: Option<u32>,
+    pub(crate) keep_display_aspect: Option<bool>,
+    pub(crate) modulus: Option<u32>,
+    pub(crate) display_width: Option<u32>,
+    pub(crate) pixel_aspect: Option<(u32, u32)>,
+    pub(crate) itu_par: bool,
+    pub(crate) color_matrix: Option<ColorMatrix>,
+    pub(crate) color_range: Option<ColorRange>,
+    pub(crate) color_primaries: Option<ColorPrimaries>,
+    pub(crate) color_transfer: Option<ColorTransfer>,
+    pub(crate) disabled_filters: BTreeSet<Filter>,
+    pub(crate) comb_detect: Option<CombDetect>,
     // Maps track number to codec string. Allows overriding specific tracks.
-    audio_codecs: HashMap<u32, String>,
-    quality: Option<f32>,
-    format: Option<String>,
-    subtitle_selection: Option<SubtitleSelection>,
-    subtitle_langs: Vec<String>,
-    subtitle_burned: Option<SubtitleBurnMode>,
-    subtitle_forced: Option<u32>,
-    subtitle_default: Option<SubtitleDefaultMode>,
-    srt_file: Option<String>,
-    ssa_file: Option<String>,
+    pub(crate) audio_codecs: HashMap<u32, String>,
+    pub(crate) audio_mixdowns: HashMap<u32, Mixdown>,
+    pub(crate) normalize_mix: HashMap<u32, bool>,
+    pub(crate) audio_qualities: HashMap<u32, f32>,
+    pub(crate) rate_control: Option<RateControl>,
+    pub(crate) no_two_pass: bool,
+    pub(crate) format: Option<String>,
+    pub(crate) subtitle_selection: Option<SubtitleSelection>,
+    pub(crate) subtitle_langs: Vec<String>,
+    pub(crate) native_language: Option<String>,
+    pub(crate) native_dub: bool,
+    pub(crate) subtitle_burned: Option<SubtitleBurnMode>,
+    pub(crate) subtitle_forced: Option<u32>,
+    pub(crate) subtitle_default: Option<SubtitleDefaultMode>,
+    pub(crate) srt_file: Option<String>,
+    pub(crate) ssa_file: Option<String>,
+    pub(crate) chapters: Option<(u32, u32)>,
+    pub(crate) angle: Option<u32>,
+    pub(crate) no_dvdnav: bool,
+    pub(crate) time_range: Option<(Duration, Duration)>,
+    pub(crate) sample: Option<Duration>,
+    pub(crate) verbosity: Option<u8>,
+    pub(crate) json_output: bool,
+    pub(crate) extra_args: Vec<String>,
+    pub(crate) user_data: Option<serde_json::Value>,
+
+    // Execution options, not passed to HandBrakeCLI
+    timeout: Option<Duration>,
+    stdout_buffer_capacity: Option<usize>,
+    stdout_chunk_size: Option<usize>,
+    check_input: bool,
+    min_free_space: Option<u64>,
+    free_space_warnings: Vec<u64>,
+    output_size_interval: Option<Duration>,
+    cancel_token: Option<CancellationToken>,
+    notifier: Option<Arc<dyn crate::notify::Notifier>>,
+    // The registry of the `HandBrake` instance that created the job, for `HandBrake::shutdown()`.
+    registry: Option<JobRegistry>,
+    // The version reported by `HandBrakeCLI --version`, if the job was created by `HandBrake`.
+    handbrake_version: Option<String>,
+    cpu_affinity: Option<Vec<usize>>,
+    log_file: Option<(PathBuf, LogLevel)>,
+    locale: Option<String>,
+    envs: BTreeMap<String, String>,
+    env_clear: bool,
+    current_dir: Option<PathBuf>,
+    stderr_file: Option<PathBuf>,
+    #[cfg(all(feature = "cgroup", target_os = "linux"))]
+    cgroup: Option<crate::CgroupLimits>,
+}
+
+/// Resources tied to a spawned process, released once dropped after the process exited.
+#[derive(Debug)]
+struct ProcessResources {
+    #[cfg(all(feature = "cgroup", target_os = "linux"))]
+    _cgroup: Option<crate::cgroup::TransientCgroup>,
+    #[cfg(windows)]
+    job_object: Arc<crate::job_object::JobObject>,
+}
+
+impl ProcessResources {
+    /// Releases the resources, once the process has exited.
+    fn release(self) {}
 }
 
 impl JobBuilder {
@@ -178,19 +1063,323 @@ impl JobBuilder {
             preserve_hdr_metadata: None,
             width: None,
             height: None,
+            max_width: None,
+            max_height: None,
+            keep_display_aspect: None,
+            modulus: None,
+            display_width: None,
+            pixel_aspect: None,
+            itu_par: false,
+            color_matrix: None,
+            color_range: None,
+            color_primaries: None,
+            color_transfer: None,
+            disabled_filters: BTreeSet::new(),
+            comb_detect: None,
             audio_codecs: HashMap::new(),
-            quality: None,
+            audio_mixdowns: HashMap::new(),
+            normalize_mix: HashMap::new(),
+            audio_qualities: HashMap::new(),
+            rate_control: None,
+            no_two_pass: false,
             format: None,
             subtitle_selection: None,
             subtitle_langs: Vec::new(),
+            native_language: None,
+            native_dub: false,
             subtitle_burned: None,
             subtitle_forced: None,
             subtitle_default: None,
             srt_file: None,
             ssa_file: None,
+            chapters: None,
+            angle: None,
+            no_dvdnav: false,
+            time_range: None,
+            sample: None,
+            verbosity: None,
+            json_output: false,
+            extra_args: Vec::new(),
+            user_data: None,
+            timeout: None,
+            stdout_buffer_capacity: None,
+            stdout_chunk_size: None,
+            check_input: false,
+            min_free_space: None,
+            free_space_warnings: Vec::new(),
+            output_size_interval: None,
+            cancel_token: None,
+            notifier: None,
+            registry: None,
+            handbrake_version: None,
+            cpu_affinity: None,
+            log_file: None,
+            locale: default_locale(),
+            envs: BTreeMap::new(),
+            env_clear: false,
+            current_dir: None,
+            stderr_file: None,
+            #[cfg(all(feature = "cgroup", target_os = "linux"))]
+            cgroup: None,
         }
     }
 
+    /// Creates a `JobBuilder` from a `JobSpec`.
+    ///
+    /// This is typically called via `HandBrake::job_from_spec()`.
+    pub fn from_spec(handbrake_path: PathBuf, spec: JobSpec) -> Self {
+        let JobSpec {
+            input,
+            output,
+            import_gui_presets,
+            preset,
+            video_codec,
+            advanced_encoder_options,
+            preserve_hdr_metadata,
+            width,
+            height,
+            max_width,
+            max_height,
+            keep_display_aspect,
+            modulus,
+            display_width,
+            pixel_aspect,
+            itu_par,
+            color_matrix,
+            color_range,
+            color_primaries,
+            color_transfer,
+            disabled_filters,
+            comb_detect,
+            audio_codecs,
+            audio_mixdowns,
+            normalize_mix,
+            audio_qualities,
+            rate_control,
+            no_two_pass,
+            format,
+            subtitle_selection,
+            subtitle_langs,
+            native_language,
+            native_dub,
+            subtitle_burned,
+            subtitle_forced,
+            subtitle_default,
+            srt_file,
+            ssa_file,
+            chapters,
+            angle,
+            no_dvdnav,
+            time_range,
+            sample,
+            verbosity,
+            json_output,
+            extra_args,
+            user_data,
+            timeout,
+            stdout_buffer_capacity,
+            stdout_chunk_size,
+            check_input,
+            min_free_space,
+            free_space_warnings,
+            output_size_interval,
+            cpu_affinity,
+            log_file,
+            locale,
+            envs,
+            env_clear,
+            current_dir,
+            stderr_file,
+        } = spec;
+        JobBuilder {
+            import_gui_presets,
+            preset,
+            video_codec,
+            advanced_encoder_options,
+            preserve_hdr_metadata,
+            width,
+            height,
+            max_width,
+            max_height,
+            keep_display_aspect,
+            modulus,
+            display_width,
+            pixel_aspect,
+            itu_par,
+            color_matrix,
+            color_range,
+            color_primaries,
+            color_transfer,
+            disabled_filters,
+            comb_detect,
+            audio_codecs,
+            audio_mixdowns,
+            normalize_mix,
+            audio_qualities,
+            rate_control,
+            no_two_pass,
+            format,
+            subtitle_selection,
+            subtitle_langs,
+            native_language,
+            native_dub,
+            subtitle_burned,
+            subtitle_forced,
+            subtitle_default,
+            srt_file,
+            ssa_file,
+            chapters,
+            angle,
+            no_dvdnav,
+            time_range,
+            sample,
+            verbosity,
+            json_output,
+            extra_args,
+            user_data,
+            timeout,
+            stdout_buffer_capacity,
+            stdout_chunk_size,
+            check_input,
+            min_free_space,
+            free_space_warnings,
+            output_size_interval,
+            cpu_affinity,
+            log_file,
+            locale,
+            envs,
+            env_clear,
+            current_dir,
+            stderr_file,
+            ..JobBuilder::new(handbrake_path, input, output)
+        }
+    }
+
+    /// Returns the `JobSpec` describing this job.
+    ///
+    /// Options that can't be serialized, such as `cancel_token()`, are not part of the spec.
+    pub fn to_spec(&self) -> JobSpec {
+        JobSpec {
+            input: self.input.clone(),
+            output: self.output.clone(),
+            import_gui_presets: self.import_gui_presets,
+            preset: self.preset.clone(),
+            video_codec: self.video_codec.clone(),
+            advanced_encoder_options: self.advanced_encoder_options.clone(),
+            preserve_hdr_metadata: self.preserve_hdr_metadata.clone(),
+            width: self.width,
+            height: self.height,
+            max_width: self.max_width,
+            max_height: self.max_height,
+            keep_display_aspect: self.keep_display_aspect,
+            modulus: self.modulus,
+            display_width: self.display_width,
+            pixel_aspect: self.pixel_aspect,
+            itu_par: self.itu_par,
+            color_matrix: self.color_matrix,
+            color_range: self.color_range,
+            color_primaries: self.color_primaries,
+            color_transfer: self.color_transfer,
+            disabled_filters: self.disabled_filters.clone(),
+            comb_detect: self.comb_detect.clone(),
+            audio_codecs: self.audio_codecs.clone(),
+            audio_mixdowns: self.audio_mixdowns.clone(),
+            normalize_mix: self.normalize_mix.clone(),
+            audio_qualities: self.audio_qualities.clone(),
+            rate_control: self.rate_control,
+            no_two_pass: self.no_two_pass,
+            format: self.format.clone(),
+            subtitle_selection: self.subtitle_selection.clone(),
+            subtitle_langs: self.subtitle_langs.clone(),
+            native_language: self.native_language.clone(),
+            native_dub: self.native_dub,
+            subtitle_burned: self.subtitle_burned.clone(),
+            subtitle_forced: self.subtitle_forced,
+            subtitle_default: self.subtitle_default.clone(),
+            srt_file: self.srt_file.clone(),
+            ssa_file: self.ssa_file.clone(),
+            chapters: self.chapters,
+            angle: self.angle,
+            no_dvdnav: self.no_dvdnav,
+            time_range: self.time_range,
+            sample: self.sample,
+            verbosity: self.verbosity,
+            json_output: self.json_output,
+            extra_args: self.extra_args.clone(),
+            user_data: self.user_data.clone(),
+            timeout: self.timeout,
+            stdout_buffer_capacity: self.stdout_buffer_capacity,
+            stdout_chunk_size: self.stdout_chunk_size,
+            check_input: self.check_input,
+            min_free_space: self.min_free_space,
+            free_space_warnings: self.free_space_warnings.clone(),
+            output_size_interval: self.output_size_interval,
+            cpu_affinity: self.cpu_affinity.clone(),
+            log_file: self.log_file.clone(),
+            locale: self.locale.clone(),
+            envs: self.envs.clone(),
+            env_clear: self.env_clear,
+            current_dir: self.current_dir.clone(),
+            stderr_file: self.stderr_file.clone(),
+        }
+    }
+
+    /// Creates a `JobBuilder` for every entry of a queue file exported by the HandBrake GUI.
+    ///
+    /// The mapping is best-effort: the source, destination, container, video encoder, quality,
+    /// encoder options, dimensions, audio encoders, and subtitle tracks are carried over, while
+    /// settings without a `JobBuilder` equivalent, such as the source title or filters, are
+    /// ignored.
+    ///
+    /// This is typically called via `HandBrake::import_gui_queue()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the queue is not valid JSON in the GUI queue format.
+    pub fn from_gui_queue(handbrake_path: PathBuf, json: &str) -> Result<Vec<JobBuilder>, Error> {
+        crate::gui_queue::import(handbrake_path, json)
+    }
+
+    /// Sets the input of the job, e.g., on a clone of a configured template.
+    pub fn input(mut self, input: impl Into<InputSource>) -> Self {
+        self.input = input.into();
+        self
+    }
+
+    /// Sets the output of the job, e.g., on a clone of a configured template.
+    pub fn output(mut self, output: impl Into<OutputDestination>) -> Self {
+        self.output = output.into();
+        self
+    }
+
+    /// Applies the settings of a `Profile` on top of the job.
+    ///
+    /// See `Profile` for how the settings combine with the preset and the other options.
+    pub fn profile(self, profile: &Profile) -> Self {
+        let job = match &profile.preset {
+            Some(preset) => self.preset(preset.clone()),
+            None => self,
+        };
+        crate::cli_args::apply_profile(job, &profile.args)
+    }
+
+    /// Creates a `JobBuilder` from a `HandBrakeCLI` argument list, e.g., taken from a shell script.
+    ///
+    /// The mapping is best-effort: every option with a `JobBuilder` equivalent is carried over,
+    /// while unknown options and values that can't be parsed are kept as `extra_args()`, in their
+    /// original order. Short options with attached values, such as `-q22`, are kept as well.
+    /// Only the input and output may be paths that are not valid UTF-8.
+    ///
+    /// This is typically called via `HandBrake::job_from_args()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the arguments lack an input or output, or if an argument kept as
+    /// `extra_args()` is not valid UTF-8.
+    pub fn from_args(handbrake_path: PathBuf, args: &[impl AsRef<OsStr>]) -> Result<JobBuilder, Error> {
+        crate::cli_args::parse(handbrake_path, args)
+    }
+
     /// Enables import of HandBrake GUI presets.
     /// 
     /// Equivalent to setting the `--preset-import-gui` flag.
@@ -218,8 +1407,36 @@ impl JobBuilder {
     /// Set advanced encoder options.
     /// 
     /// e.g., 'scm=0:enable-tf=0'
-    pub fn advanced_encoder_options(mut self, advanced_encoder_options: String) -> Self {
-        self.advanced_encoder_options = Some(advanced_encoder_options);
+    ///
+    /// Same as `encopts()`.
+    pub fn advanced_encoder_options(self, advanced_encoder_options: String) -> Self {
+        self.encopts(advanced_encoder_options)
+    }
+
+    /// Sets the options passed to the video encoder, replacing any set before.
+    ///
+    /// `HandBrakeCLI` uses `--encopts <key=value:key=value>`. The keys depend on the encoder,
+    /// e.g., `"aq-mode=3:psy-rd=1.0"` for x264 or x265.
+    pub fn encopts(mut self, encopts: impl Into<String>) -> Self {
+        self.advanced_encoder_options = Some(encopts.into());
+        self
+    }
+
+    /// Sets a single encoder option, keeping the others set with `encopts()` or `encopt()`.
+    ///
+    /// e.g., `.encopt("tune", "grain").encopt("aq-mode", 3)`
+    /// If called multiple times for the same key, the last call wins.
+    pub fn encopt(mut self, key: impl Into<String>, value: impl ToString) -> Self {
+        let key = key.into();
+        let mut options: Vec<String> = self
+            .advanced_encoder_options
+            .iter()
+            .flat_map(|options| options.split(':'))
+            .filter(|option| !option.is_empty() && option.split('=').next() != Some(key.as_str()))
+            .map(str::to_string)
+            .collect();
+        options.push(format!("{}={}", key, value.to_string()));
+        self.advanced_encoder_options = Some(options.join(":"));
         self
     }
 
@@ -228,6 +1445,9 @@ impl JobBuilder {
     /// e.g., `Some(All), Some(DolbyVision), None`
     /// 
     /// `None` behaviour is the same as if neither `--hdr-dynamic-metadata` or `--no-hdr-dynamic-metadata` were set
+    ///
+    /// Requires HandBrake 1.7 or later. Jobs created by a `HandBrake` instance fail to start with
+    /// an older version.
     pub fn preserve_hdr_metadata(mut self, preserve_hdr_metadata: Option<PreserveHdrMetadata>) -> Self {
         self.preserve_hdr_metadata = preserve_hdr_metadata;
         self
@@ -250,6 +1470,39 @@ impl JobBuilder {
         self
     }
 
+    /// Sets how the channels of a specific track are mixed down, e.g., from 5.1 to stereo.
+    ///
+    /// `HandBrakeCLI` uses `--mixdown <mixdown>,...`, in the order of the track numbers.
+    /// Earlier tracks without a mixdown are left empty, so they keep the default mixdown of their
+    /// encoder.
+    /// If called multiple times for the same track, the last call wins.
+    pub fn audio_mixdown(mut self, track: u32, mixdown: Mixdown) -> Self {
+        self.audio_mixdowns.insert(track, mixdown);
+        self
+    }
+
+    /// Whether to normalize the mix of a specific track, to avoid clipping when mixing down.
+    ///
+    /// Normalizing keeps the loudness consistent between tracks, at the cost of a quieter mix.
+    /// `HandBrakeCLI` uses `--normalize-mix <0|1>,...`, in the order of the track numbers.
+    /// If called multiple times for the same track, the last call wins.
+    pub fn normalize_mix(mut self, track: u32, normalize: bool) -> Self {
+        self.normalize_mix.insert(track, normalize);
+        self
+    }
+
+    /// Encodes a specific track with a quality, instead of a bitrate.
+    ///
+    /// Only encoders with a variable bitrate support it, e.g., Vorbis, Opus, or MP3, and the scale
+    /// depends on the encoder, e.g., `-1.0` to `10.0` for Vorbis.
+    /// `HandBrakeCLI` uses `--aq <quality>,...`, in the order of the track numbers, where earlier
+    /// tracks without a quality keep their bitrate.
+    /// If called multiple times for the same track, the last call wins.
+    pub fn audio_quality(mut self, track: u32, quality: f32) -> Self {
+        self.audio_qualities.insert(track, quality);
+        self
+    }
+
     /// Adds a subtitle track to the job.
     ///
     /// This can be called multiple times to include multiple subtitle tracks.
@@ -283,6 +1536,24 @@ impl JobBuilder {
         self
     }
 
+    /// Sets the native language, using the `iso639-2` code, e.g., `"eng"`.
+    ///
+    /// When the selected audio is in another language, `HandBrakeCLI` selects a subtitle track
+    /// in this language, or only its forced subtitles with `subtitle_scan()`.
+    /// `HandBrakeCLI` uses `--native-language <lang>`.
+    pub fn native_language(mut self, lang: impl Into<String>) -> Self {
+        self.native_language = Some(lang.into());
+        self
+    }
+
+    /// Whether to prefer audio in the native language over subtitles, when a dub exists.
+    ///
+    /// Requires `native_language()`. `HandBrakeCLI` uses `--native-dub`.
+    pub fn native_dub(mut self, native_dub: bool) -> Self {
+        self.native_dub = native_dub;
+        self
+    }
+
     /// Sets the subtitle burn-in mode.
     pub fn subtitle_burned(mut self, mode: SubtitleBurnMode) -> Self {
         self.subtitle_burned = Some(mode);
@@ -317,13 +1588,46 @@ impl JobBuilder {
         self
     }
 
+    /// Sets how the video bitrate is controlled, replacing any quality or bitrate set before.
+    ///
+    /// e.g., `.rate_control(RateControl::Bitrate { kbps: 6000, two_pass: true, turbo: true })`
+    pub fn rate_control(mut self, rate_control: RateControl) -> Self {
+        self.rate_control = Some(rate_control);
+        self.no_two_pass = false;
+        self
+    }
+
+    /// Encodes in a single pass, even if the preset enables two passes.
+    ///
+    /// `HandBrakeCLI` uses `--no-two-pass`. Disables the passes of a bitrate set with
+    /// `rate_control()`, until it is called again.
+    pub fn no_two_pass(mut self) -> Self {
+        if let Some(RateControl::Bitrate { two_pass, turbo, .. }) = &mut self.rate_control {
+            *two_pass = false;
+            *turbo = false;
+        }
+        self.no_two_pass = true;
+        self
+    }
+
     /// Sets the constant quality (RF) for video encoding.
     ///
     /// `HandBrakeCLI` uses `--quality <value>` or `-q <value>`.
     /// Value typically ranges from 0 to 51 (lower is better quality).
-    pub fn quality(mut self, quality: f32) -> Self {
-        self.quality = Some(quality);
-        self
+    /// Shorthand for `rate_control(RateControl::ConstantQuality(quality))`.
+    pub fn quality(self, quality: f32) -> Self {
+        self.rate_control(RateControl::ConstantQuality(quality))
+    }
+
+    /// Sets the average video bitrate in kbit/s, instead of a constant quality.
+    ///
+    /// `HandBrakeCLI` uses `--vb <kbps>`. Shorthand for a single-pass `RateControl::Bitrate`.
+    pub fn video_bitrate(self, kbps: u32) -> Self {
+        self.rate_control(RateControl::Bitrate {
+            kbps,
+            two_pass: false,
+            turbo: false,
+        })
     }
 
     pub fn width(mut self, width: u32) -> Self {
@@ -336,50 +1640,701 @@ impl JobBuilder {
         self
     }
 
-    fn create_process(self) -> Result<Command, Error> {
-        let args = self.build_args();
+    /// Limits the output width, scaling larger sources down while keeping their aspect ratio.
+    ///
+    /// `HandBrakeCLI` uses `--maxWidth <width>`.
+    pub fn max_width(mut self, width: u32) -> Self {
+        self.max_width = Some(width);
+        self
+    }
 
-        let stdin_cfg = match self.input {
-            InputSource::Stdin => Stdio::piped(),
-            _ => Stdio::inherit(), // Default to inheriting stdin
-        };
+    /// Limits the output height, scaling larger sources down while keeping their aspect ratio.
+    ///
+    /// `HandBrakeCLI` uses `--maxHeight <height>`.
+    pub fn max_height(mut self, height: u32) -> Self {
+        self.max_height = Some(height);
+        self
+    }
 
-        let stdout_cfg = match self.output {
-            OutputDestination::Stdout => Stdio::piped(),
-            _ => Stdio::inherit(), // Default to inheriting stdout
-        };
+    /// Sets whether the display aspect ratio of the source is kept when only one of `width()` or
+    /// `height()` is set.
+    ///
+    /// `HandBrakeCLI` uses `--keep-display-aspect` or `--no-keep-display-aspect`.
+    pub fn keep_display_aspect(mut self, keep: bool) -> Self {
+        self.keep_display_aspect = Some(keep);
+        self
+    }
 
-        let mut cmd = Command::new(&self.handbrake_path);
-        cmd.args(args).stdin(stdin_cfg).stdout(stdout_cfg);
-        #[cfg(windows)]
+    /// Rounds the output dimensions to a multiple of `modulus`, e.g., 2 or 16.
+    ///
+    /// `HandBrakeCLI` uses `--modulus <modulus>`.
+    pub fn modulus(mut self, modulus: u32) -> Self {
+        self.modulus = Some(modulus);
+        self
+    }
+
+    /// Sets the width the output is stretched to on playback, e.g., 1024 to show a 720x576 PAL
+    /// encode at 16:9. `HandBrakeCLI` switches to custom anamorphic.
+    ///
+    /// `HandBrakeCLI` uses `--display-width <width>`.
+    pub fn display_width(mut self, width: u32) -> Self {
+        self.display_width = Some(width);
+        self
+    }
+
+    /// Sets the pixel aspect ratio of the output, e.g., `(64, 45)` for 16:9 PAL.
+    /// `HandBrakeCLI` switches to custom anamorphic.
+    ///
+    /// `HandBrakeCLI` uses `--pixel-aspect <x>:<y>`.
+    pub fn pixel_aspect(mut self, x: u32, y: u32) -> Self {
+        self.pixel_aspect = Some((x, y));
+        self
+    }
+
+    /// Uses the ITU pixel aspect ratios for DVD-sized sources, which are slightly narrower
+    /// than the ratios computed from the full frame.
+    ///
+    /// `HandBrakeCLI` uses `--itu-par`.
+    pub fn itu_par(mut self, itu_par: bool) -> Self {
+        self.itu_par = itu_par;
+        self
+    }
+
+    /// Overrides the color matrix signaled by the output, e.g., for a source with wrong metadata.
+    ///
+    /// `HandBrakeCLI` uses `--color-matrix <601|709|2020>`.
+    pub fn color_matrix(mut self, matrix: ColorMatrix) -> Self {
+        self.color_matrix = Some(matrix);
+        self
+    }
+
+    /// Overrides the color range of the output.
+    ///
+    /// The range, primaries, and transfer are set through the `colorspace` filter, which
+    /// requires HandBrake 1.7 or later: `--colorspace=range=<range>:primaries=<..>:transfer=<..>`.
+    pub fn color_range(mut self, range: ColorRange) -> Self {
+        self.color_range = Some(range);
+        self
+    }
+
+    /// Overrides the color primaries of the output. See `color_range()`.
+    pub fn color_primaries(mut self, primaries: ColorPrimaries) -> Self {
+        self.color_primaries = Some(primaries);
+        self
+    }
+
+    /// Overrides the transfer characteristics of the output. See `color_range()`.
+    pub fn color_transfer(mut self, transfer: ColorTransfer) -> Self {
+        self.color_transfer = Some(transfer);
+        self
+    }
+
+    /// Disables a video filter, e.g., one the preset enables.
+    ///
+    /// `HandBrakeCLI` uses `--no-<filter>`, e.g., `--no-nlmeans` for `Filter::Nlmeans`.
+    pub fn disable_filter(mut self, filter: Filter) -> Self {
+        if filter == Filter::CombDetect {
+            self.comb_detect = None;
+        }
+        self.disabled_filters.insert(filter);
+        self
+    }
+
+    /// Enables comb detection, so that a deinterlacer only processes the combed frames.
+    ///
+    /// This complements the decomb and deinterlace filters, e.g., for mixed progressive and
+    /// interlaced sources. `HandBrakeCLI` uses `--comb-detect[=<preset or settings>]`.
+    /// Replaces a previous call to `no_comb_detect()`.
+    ///
+    /// e.g., `.comb_detect(CombDetect::Permissive)`
+    pub fn comb_detect(mut self, comb_detect: CombDetect) -> Self {
+        self.disabled_filters.remove(&Filter::CombDetect);
+        self.comb_detect = Some(comb_detect);
+        self
+    }
+
+    /// Disables comb detection. Shorthand for `disable_filter(Filter::CombDetect)`.
+    pub fn no_comb_detect(self) -> Self {
+        self.disable_filter(Filter::CombDetect)
+    }
+
+    /// Disables the Yadif deinterlacer. Shorthand for `disable_filter(Filter::Deinterlace)`.
+    pub fn no_deinterlace(self) -> Self {
+        self.disable_filter(Filter::Deinterlace)
+    }
+
+    /// Disables the decomb deinterlacer. Shorthand for `disable_filter(Filter::Decomb)`.
+    pub fn no_decomb(self) -> Self {
+        self.disable_filter(Filter::Decomb)
+    }
+
+    /// Disables detelecine. Shorthand for `disable_filter(Filter::Detelecine)`.
+    pub fn no_detelecine(self) -> Self {
+        self.disable_filter(Filter::Detelecine)
+    }
+
+    /// Encodes only the given range of chapters, e.g., `3..=3` for the third chapter.
+    ///
+    /// `HandBrakeCLI` uses `--chapters <first>-<last>`.
+    pub fn chapters(mut self, chapters: RangeInclusive<u32>) -> Self {
+        self.chapters = Some(chapters.into_inner());
+        self
+    }
+
+    /// Selects the angle to encode on a multi-angle DVD or Blu-ray title, starting at 1.
+    ///
+    /// `HandBrakeCLI` uses `--angle <number>`.
+    pub fn angle(mut self, angle: u32) -> Self {
+        self.angle = Some(angle);
+        self
+    }
+
+    /// Whether DVDs are read with libdvdnav, which is the default.
+    ///
+    /// Disabling it falls back to libdvdread, which works around discs that hang libdvdnav.
+    /// `HandBrakeCLI` uses `--no-dvdnav`.
+    pub fn dvdnav(mut self, enabled: bool) -> Self {
+        self.no_dvdnav = !enabled;
+        self
+    }
+
+    /// Encodes only the given length of the source, starting at the given offset.
+    ///
+    /// `HandBrakeCLI` uses `--start-at duration:<seconds> --stop-at duration:<seconds>`.
+    /// Replaces a sample set with `sample()`.
+    pub fn time_range(mut self, start: Duration, duration: Duration) -> Self {
+        self.time_range = Some((start, duration));
+        self.sample = None;
+        self
+    }
+
+    /// Encodes only a sample of the given length from the middle of the source.
+    ///
+    /// This is useful for checking the quality of the settings before a long encode.
+    /// `HandBrakeCLI` uses `--start-at-preview 5 --stop-at duration:<seconds>`. Replaces a range
+    /// set with `time_range()`.
+    ///
+    /// e.g., `.sample(Duration::from_secs(30))`
+    pub fn sample(mut self, duration: Duration) -> Self {
+        self.sample = Some(duration);
+        self.time_range = None;
+        self
+    }
+
+    /// Sets the verbosity of the log, from 0 (quiet) to 3 (most verbose), e.g., for debugging.
+    ///
+    /// `HandBrakeCLI` uses `--verbose=<level>`, as the level is an optional argument. Levels
+    /// above 3 are treated as 3.
+    pub fn verbose(mut self, level: u8) -> Self {
+        self.verbosity = Some(level.min(3));
+        self
+    }
+
+    /// Makes `HandBrakeCLI` report its progress as JSON, which includes the pass of multi-pass
+    /// encodes. The events are the same in both modes.
+    ///
+    /// `HandBrakeCLI` uses `--json`, which writes the JSON to `stdout`, so this can't be combined
+    /// with writing the output to `stdout`: starting such a job fails with
+    /// `Error::IncompatibleOptions`.
+    pub fn json_output(mut self, json_output: bool) -> Self {
+        self.json_output = json_output;
+        self
+    }
+
+    /// Estimates the wall-clock time of the job by encoding a sample of one minute.
+    ///
+    /// The total time is extrapolated from the average speed of the sample and the duration and
+    /// frame rate of the source, as reported by `HandBrakeCLI` when it scans the source. The
+    /// sample is discarded, and the job itself can be started afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the input is read from `stdin` or an `InputSource::Reader`, which
+    /// can only be read once, or if the sample encode fails or doesn't report the source duration.
+    pub async fn estimate_duration(&self) -> Result<Duration, Error> {
+        let probe = crate::estimate::Probe::run(self.probe_job()?).await?;
+        Ok(probe.duration())
+    }
+
+    /// Estimates the size of the output in bytes by encoding a sample of one minute.
+    ///
+    /// The total size is extrapolated from the size of the sample and the duration of the source,
+    /// e.g., to check whether the output fits on the destination disk. With a `time_range()` or
+    /// `chapters()`, the sample is still taken from the whole title, and extrapolated to the
+    /// selected part. The sample is discarded, and the job itself can be started afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the input is read from `stdin` or an `InputSource::Reader`, which
+    /// can only be read once, or if the sample encode fails or doesn't report the source duration.
+    pub async fn estimate_size(&self) -> Result<u64, Error> {
+        let probe = crate::estimate::Probe::run(self.probe_job()?).await?;
+        Ok(probe.size())
+    }
+
+    /// Searches for the constant quality that produces an output of about the given size in bytes.
+    ///
+    /// Encodes samples of one minute at a few quality values, as with `estimate_size()`, and
+    /// interpolates between them. Returns the job with the found `quality()`, which can be
+    /// started afterwards.
+    ///
+    /// e.g., `job.target_size(700_000_000).await?.start()?`
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the input is read from `stdin` or an `InputSource::Reader`, which
+    /// can only be read once, or if a sample encode fails or doesn't report the source duration.
+    pub async fn target_size(self, bytes: u64) -> Result<JobBuilder, Error> {
+        let mut search = crate::estimate::QualitySearch::new(bytes);
+        while let Some(quality) = search.next_quality() {
+            let probe = crate::estimate::Probe::run(self.probe_job()?.quality(quality)).await?;
+            search.record(quality, probe.size());
+        }
+        let quality = search.best();
+        Ok(self.quality(quality))
+    }
+
+    /// Returns a copy of the job to encode a sample of.
+    fn probe_job(&self) -> Result<JobBuilder, Error> {
+        if matches!(self.input, InputSource::Stdin | InputSource::Reader(_)) {
+            return Err(Error::EstimationFailed {
+                reason: "The input can only be read once".to_string(),
+            });
+        }
+        let mut job = self.derive_from_spec(self.to_spec());
+        // The source is measured from the log on `stderr`.
+        job.log_file = None;
+        job.stderr_file = None;
+        job.cancel_token = self.cancel_token.clone();
+        #[cfg(all(feature = "cgroup", target_os = "linux"))]
         {
-            use windows_sys::Win32::System::Threading::CREATE_NEW_PROCESS_GROUP;
-            cmd.creation_flags(CREATE_NEW_PROCESS_GROUP);
+            job.cgroup = self.cgroup.clone();
         }
-        Ok(cmd)
+        Ok(job)
+    }
+
+    /// Appends arguments that have no `JobBuilder` option, e.g., `["--comb-detect", "--decomb"]`.
+    ///
+    /// The arguments are passed after all other options, in the order they were added.
+    pub fn extra_args(mut self, args: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.extra_args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Attaches data of the caller to the job, e.g., the ID of the database row it encodes.
+    ///
+    /// The data is returned with every `Timestamped` event and by `JobHandle::user_data()`, so
+    /// events can be matched to the caller's own objects without a separate map. Tags can be
+    /// passed as an array or an object, e.g., `json!({ "id": 42, "tags": ["anime"] })`.
+    pub fn user_data(mut self, user_data: serde_json::Value) -> Self {
+        self.user_data = Some(user_data);
+        self
+    }
+
+    /// Sets the maximum time a monitored job may run.
+    ///
+    /// When the timeout elapses, the job is cancelled as with `JobHandle::cancel()`, and killed
+    /// if it has not exited 10 seconds later. The job then completes with a `JobFailure` of
+    /// kind `FailureKind::Timeout`. Only applies to jobs started with `start()`.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the capacity of the buffer `stdout` is read through, 8 KiB by default.
+    ///
+    /// When encoding to `OutputDestination::Stdout` or `OutputDestination::Writer`, a larger
+    /// buffer, e.g., several MiB, lowers the overhead of piping high bitrate video to another
+    /// process.
+    pub fn stdout_buffer_capacity(mut self, bytes: usize) -> Self {
+        self.stdout_buffer_capacity = Some(bytes);
+        self
+    }
+
+    /// Sets how much video data a single read from `JobHandle::take_output_reader()` returns at
+    /// most, 64 KiB by default.
+    ///
+    /// This is the capacity of the pipe between the background task and the reader, so larger
+    /// chunks also let `HandBrakeCLI` run further ahead of a slow reader.
+    pub fn stdout_chunk_size(mut self, bytes: usize) -> Self {
+        self.stdout_chunk_size = Some(bytes);
+        self
+    }
+
+    /// Checks the start of a file input before spawning `HandBrakeCLI`, failing with
+    /// `Error::UnsupportedInput` if it is an image, a document, an archive, or a text file.
+    ///
+    /// `HandBrakeCLI` only rejects such inputs after scanning them, with "No title found".
+    /// Inputs that can't be read, and directories such as `VIDEO_TS` folders, are left to
+    /// `HandBrakeCLI`.
+    pub fn check_input(mut self, enabled: bool) -> Self {
+        self.check_input = enabled;
+        self
+    }
+
+    /// Stops the job before the output volume fills up, once its free space drops below `bytes`.
+    ///
+    /// The free space is sampled every second while the job runs. When it drops below the
+    /// minimum, the job is cancelled as with `JobHandle::cancel()`, and killed if it has not
+    /// exited 10 seconds later. The job then completes with a `JobFailure` of kind
+    /// `FailureKind::DiskSpace`. Only applies to jobs started with `start()` that encode to an
+    /// `OutputDestination::File`.
+    pub fn min_free_space(mut self, bytes: u64) -> Self {
+        self.min_free_space = Some(bytes);
+        self
+    }
+
+    /// Emits a `JobEvent::LowDiskSpace` warning when the free space on the output volume drops
+    /// below each of the thresholds, in bytes.
+    ///
+    /// The free space is sampled as with `min_free_space()`, and each threshold is reported
+    /// once. Replaces the thresholds set before.
+    pub fn free_space_warnings(mut self, thresholds: impl IntoIterator<Item = u64>) -> Self {
+        self.free_space_warnings = thresholds.into_iter().collect();
+        self
+    }
+
+    /// Emits a `JobEvent::OutputSize` event with the size of the output file at this interval,
+    /// e.g., to project the final size or to detect a runaway bitrate early.
+    ///
+    /// No events are emitted until `HandBrakeCLI` creates the output. Only applies to jobs started
+    /// with `start()` that encode to an `OutputDestination::File`.
+    pub fn output_size_interval(mut self, interval: Duration) -> Self {
+        self.output_size_interval = Some(interval);
+        self
+    }
+
+    /// Cancels the job as with `JobHandle::cancel()` once the token is cancelled.
+    ///
+    /// This ties the job to an existing shutdown signal, e.g., a child token of the application's
+    /// root token. Only applies to jobs started with `start()`.
+    pub fn cancel_token(mut self, token: CancellationToken) -> Self {
+        self.cancel_token = Some(token);
+        self
     }
 
-    fn create_process_with_args(self, args: Vec<String>) -> Result<Command, Error> {
+    /// Sends notifications about the job's lifecycle to the given notifier.
+    ///
+    /// See the `notify` module for when notifications are sent. Only applies to jobs started with
+    /// `start()`.
+    ///
+    /// e.g., `.notifier(Arc::new(WebhookNotifier::new("http://localhost:8080/hooks/handbrake")))`
+    pub fn notifier(mut self, notifier: Arc<dyn crate::notify::Notifier>) -> Self {
+        self.notifier = Some(notifier);
+        self
+    }
+
+    /// Writes the output of the job to a log file, replacing any existing file.
+    ///
+    /// This mirrors the activity log of the HandBrake GUI, e.g., to keep a per-job log for
+    /// debugging failed encodes. Starting the job fails if the file can't be created. Only
+    /// applies to jobs started with `start()`.
+    ///
+    /// e.g., `.log_file("logs/movie.log", LogLevel::Activity)`
+    pub fn log_file(mut self, path: impl Into<PathBuf>, level: LogLevel) -> Self {
+        self.log_file = Some((path.into(), level));
+        self
+    }
+
+    /// Sets the locale of the `HandBrakeCLI` process through `LC_ALL` and `LANG`.
+    ///
+    /// Defaults to `"C"`, as other locales may change the number format of the progress output,
+    /// e.g., `12,34 %` in German, which the progress parser doesn't understand.
+    pub fn locale(mut self, locale: impl Into<String>) -> Self {
+        self.locale = Some(locale.into());
+        self
+    }
+
+    /// Runs `HandBrakeCLI` with the locale of the current process instead of `"C"`.
+    ///
+    /// Progress may not be reported if the locale formats numbers differently.
+    pub fn inherit_locale(mut self) -> Self {
+        self.locale = None;
+        self
+    }
+
+    /// Sets an environment variable of the `HandBrakeCLI` process, e.g., `CUDA_VISIBLE_DEVICES`.
+    ///
+    /// Variables set here take precedence over `locale()`.
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.envs.insert(key.into(), value.into());
+        self
+    }
+
+    /// Starts `HandBrakeCLI` without the environment of the current process, and without the
+    /// variables set with `env()` before.
+    ///
+    /// Variables set with `env()` afterwards and the `locale()` still apply.
+    pub fn env_clear(mut self) -> Self {
+        self.env_clear = true;
+        self.envs.clear();
+        self
+    }
+
+    /// Redirects the `stderr` of `HandBrakeCLI` straight to a file, replacing any existing file.
+    ///
+    /// Monitored jobs then skip parsing `stderr`, which saves a task's worth of work per job when
+    /// running many of them at once. Progress is still parsed from `stdout`, but there are no
+    /// `Log` or `Config` events, `raw_lines()` ends immediately, and a `log_file()` only receives
+    /// the lines of `LogLevel::Events`. A relative path is resolved against the `current_dir()`,
+    /// like the output. The file is created when the job is started, once its options have been
+    /// checked, and starting the job fails if the file can't be created.
+    ///
+    /// e.g., `.stderr_file("logs/movie.stderr")`
+    pub fn stderr_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.stderr_file = Some(path.into());
+        self
+    }
+
+    /// Sets the working directory of the `HandBrakeCLI` process.
+    ///
+    /// Relative paths passed to `HandBrakeCLI`, such as the input, output, and subtitle files,
+    /// are resolved against it, as is the path of `stderr_file()`. The path of `log_file()` is
+    /// still relative to the current process.
+    pub fn current_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.current_dir = Some(dir.into());
+        self
+    }
+
+    /// Pins the `HandBrakeCLI` process to the given CPU cores, identified by their index.
+    ///
+    /// On Linux, the affinity is set before `HandBrakeCLI` starts and is inherited by the encoder
+    /// threads. On Windows, it is set right after the process is spawned. Spawning the job fails
+    /// with an `Unsupported` error on other platforms.
+    ///
+    /// e.g., `.cpu_affinity(0..4)` to use the first four cores.
+    pub fn cpu_affinity(mut self, cores: impl IntoIterator<Item = usize>) -> Self {
+        self.cpu_affinity = Some(cores.into_iter().collect());
+        self
+    }
+
+    /// Runs the `HandBrakeCLI` process in a transient cgroup with the given resource limits.
+    ///
+    /// The cgroup is created below `limits.parent`, or the cgroup of the current process, and
+    /// removed after `HandBrakeCLI` exits. Spawning the job fails if the cgroup can't be created,
+    /// e.g., because the parent isn't delegated to the current user.
+    ///
+    /// Only available on Linux with the `cgroup` feature enabled.
+    ///
+    /// e.g., `.cgroup(CgroupLimits { cpu_quota: Some(2.0), memory_max: Some(4 << 30), ..Default::default() })`
+    #[cfg(all(feature = "cgroup", target_os = "linux"))]
+    pub fn cgroup(mut self, limits: crate::CgroupLimits) -> Self {
+        self.cgroup = Some(limits);
+        self
+    }
+
+    /// Encodes a sample of the job at each quality, and scores it against the source with
+    /// `ffmpeg`. See `QualityComparison`.
+    ///
+    /// Requires the `compare` feature.
+    #[cfg(feature = "compare")]
+    pub fn compare_qualities(self, qualities: impl IntoIterator<Item = f32>) -> crate::QualityComparison {
+        crate::QualityComparison::new(self, qualities)
+    }
+
+    /// Splits the job into segments that are encoded in parallel. See `SegmentedEncode`.
+    ///
+    /// Requires the `segmented` feature.
+    #[cfg(feature = "segmented")]
+    pub fn segmented(self) -> crate::SegmentedEncode {
+        crate::SegmentedEncode::new(self)
+    }
+
+    /// Chains another job that only starts after this job completed successfully.
+    ///
+    /// See `JobChain` for running the chained jobs.
+    pub fn then(self, next: JobBuilder) -> crate::JobChain {
+        crate::JobChain::new(self).then(next)
+    }
+
+    /// Converts the job into an entry for a HandBrake GUI queue file.
+    ///
+    /// The entry contains the same settings that `from_gui_queue()` imports, so a list of entries
+    /// can be serialized into a queue file for the GUI, and read back into `JobBuilder`s. Presets
+    /// and other settings the queue format can't express are left out, so the GUI applies its
+    /// defaults for them.
+    pub fn to_gui_queue_entry(&self) -> serde_json::Value {
+        crate::gui_queue::export(self)
+    }
+
+    fn create_process(&self) -> Result<Command, Error> {
+        self.create_process_with_args(self.build_args())
+    }
+
+    /// Records the version of `HandBrakeCLI`, which options requiring a newer version are
+    /// checked against before spawning the job.
+    pub(crate) fn with_version(mut self, version: &str) -> Self {
+        self.handbrake_version = Some(version.to_string());
+        self
+    }
+
+    /// Creates a job from a spec derived from the one of this job, keeping the version and the
+    /// registry of the `HandBrake` instance this job was created by, which a `JobSpec` lacks.
+    pub(crate) fn derive_from_spec(&self, spec: JobSpec) -> JobBuilder {
+        let mut job = JobBuilder::from_spec(self.handbrake_path.clone(), spec);
+        job.handbrake_version = self.handbrake_version.clone();
+        job.registry = self.registry.clone();
+        job
+    }
+
+    /// Registers the controller of the job once spawned, so `HandBrake::shutdown()` can stop it.
+    pub(crate) fn tracked_by(mut self, registry: &JobRegistry) -> Self {
+        self.registry = Some(Arc::clone(registry));
+        self
+    }
+
+    /// The capacity of the buffer `stdout` is read through, which must not be empty.
+    fn effective_stdout_buffer_capacity(&self) -> usize {
+        self.stdout_buffer_capacity.unwrap_or(STDOUT_BUFFER_CAPACITY).max(1)
+    }
+
+    /// Fails if an option requires a newer `HandBrakeCLI` than the recorded version.
+    ///
+    /// Versions that can't be parsed, e.g., of development builds, are assumed to be recent.
+    fn check_version(&self) -> Result<(), Error> {
+        let Some(version) = self.handbrake_version.as_deref() else {
+            return Ok(());
+        };
+        let Some(found) = parse_version(version) else {
+            return Ok(());
+        };
+        let colorspace = self.color_range.is_some() || self.color_primaries.is_some() || self.color_transfer.is_some();
+        let required = [
+            (self.preserve_hdr_metadata.is_some(), "preserve_hdr_metadata()", (1, 7, 0)),
+            (colorspace, "the colorspace filter", (1, 7, 0)),
+        ];
+        for (used, option, (major, minor, patch)) in required {
+            if used && found < (major, minor, patch) {
+                return Err(Error::UnsupportedOption {
+                    option: option.to_string(),
+                    required: format!("{}.{}.{}", major, minor, patch),
+                    version: version.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    fn create_process_with_args(&self, args: Vec<OsString>) -> Result<Command, Error> {
+        self.check_version()?;
+        if self.json_output && matches!(self.output, OutputDestination::Stdout | OutputDestination::Writer(_)) {
+            // The JSON progress would be mixed into the video data.
+            return Err(Error::IncompatibleOptions {
+                reason: "json_output() writes to stdout, which is the output of the job".to_string(),
+            });
+        }
+        if self.check_input
+            && let InputSource::File(path) = &self.input
+        {
+            crate::sniff::check_input(&self.in_current_dir(path))?;
+        }
         let stdin_cfg = match self.input {
-            InputSource::Stdin => Stdio::piped(),
+            InputSource::Stdin | InputSource::Reader(_) => Stdio::piped(),
             _ => Stdio::inherit(), // Default to inheriting stdin
         };
 
         let stdout_cfg = match self.output {
-            OutputDestination::Stdout => Stdio::piped(),
+            OutputDestination::Stdout | OutputDestination::Writer(_) => Stdio::piped(),
             _ => Stdio::inherit(), // Default to inheriting stdout
         };
 
         let mut cmd = Command::new(&self.handbrake_path);
         cmd.args(args).stdin(stdin_cfg).stdout(stdout_cfg);
+        if self.env_clear {
+            cmd.env_clear();
+        }
+        if let Some(locale) = &self.locale {
+            cmd.env("LC_ALL", locale).env("LANG", locale);
+        }
+        cmd.envs(&self.envs);
+        if let Some(dir) = &self.current_dir {
+            cmd.current_dir(dir);
+        }
         #[cfg(windows)]
         {
-            use windows_sys::Win32::System::Threading::CREATE_NEW_PROCESS_GROUP;
-            cmd.creation_flags(CREATE_NEW_PROCESS_GROUP);
+            use windows_sys::Win32::System::Threading::{CREATE_NEW_PROCESS_GROUP, CREATE_SUSPENDED};
+            // Resumed by `spawn_process()` once it is in its Job Object, so that no process it
+            // starts escapes the Job Object.
+            cmd.creation_flags(CREATE_NEW_PROCESS_GROUP | CREATE_SUSPENDED);
+        }
+        if let Some(cores) = &self.cpu_affinity {
+            crate::affinity::configure(&mut cmd, cores)?;
         }
         Ok(cmd)
     }
 
+    /// Takes the writer of an `OutputDestination::Writer`, before the process is spawned.
+    fn take_output_writer(&self) -> Result<Option<BoxedWriter>, Error> {
+        match &self.output {
+            OutputDestination::Writer(writer) => {
+                writer.take().map(Some).ok_or_else(|| Error::ProcessSpawnFailed {
+                    source: io::Error::other("The output writer was already used by another job"),
+                })
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Spawns the command, then applies the options that require a running process.
+    ///
+    /// The returned resources must be kept alive until the process has exited.
+    fn spawn_process(&self, cmd: &mut Command) -> Result<(Child, ProcessResources), Error> {
+        let input_reader = match &self.input {
+            InputSource::Reader(reader) => Some(reader.take().ok_or_else(|| {
+                Error::ProcessSpawnFailed {
+                    source: io::Error::other("The input reader was already used by another job"),
+                }
+            })?),
+            _ => None,
+        };
+        #[cfg(all(feature = "cgroup", target_os = "linux"))]
+        let cgroup = match &self.cgroup {
+            Some(limits) => {
+                let cgroup = crate::cgroup::TransientCgroup::create(limits)?;
+                cgroup.configure(cmd)?;
+                Some(cgroup)
+            }
+            None => None,
+        };
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| Error::ProcessSpawnFailed { source: e })?;
+        if let Some(cores) = &self.cpu_affinity
+            && let Err(e) = crate::affinity::apply(&child, cores)
+        {
+            let _ = child.start_kill();
+            return Err(e);
+        }
+        // Keeps helper processes from outliving the job, even if this process crashes.
+        #[cfg(windows)]
+        let job_object = match crate::job_object::JobObject::assign(&child)
+            .and_then(|job_object| crate::job_object::resume(&child).map(|()| job_object))
+        {
+            Ok(job_object) => Arc::new(job_object),
+            Err(e) => {
+                let _ = child.start_kill();
+                return Err(Error::ProcessSpawnFailed { source: e });
+            }
+        };
+        // A failed copy ends the input early, which `HandBrakeCLI` reports as a failed encode.
+        if let Some(mut reader) = input_reader
+            && let Some(mut stdin) = child.stdin.take()
+        {
+            tokio::spawn(async move {
+                let _ = tokio::io::copy(&mut reader, &mut stdin).await;
+                let _ = stdin.shutdown().await;
+            });
+        }
+        let resources = ProcessResources {
+            #[cfg(all(feature = "cgroup", target_os = "linux"))]
+            _cgroup: cgroup,
+            #[cfg(windows)]
+            job_object,
+        };
+        crate::telemetry::job_started();
+        Ok((child, resources))
+    }
+
     /// Executes the job and waits for completion, returning only the final `ExitStatus`.
     ///
     /// This is ideal for "fire-and-forget" scenarios where real-time monitoring is not needed.
@@ -389,13 +2344,15 @@ impl JobBuilder {
     ///
     /// Returns an `Error` if the process could not be spawned.
     pub async fn status(self) -> Result<ExitStatus, Error> {
+        let mut cmd = self.create_process()?;
         // For status, we don't need to capture stderr, just let it go to parent process's stderr
-        let stderr_cfg = Stdio::inherit();
-        self.create_process()?
-            .stderr(stderr_cfg)
-            .status()
-            .await
-            .map_err(|e| Error::ProcessSpawnFailed { source: e })
+        cmd.stderr(self.stderr_stdio()?.unwrap_or_else(Stdio::inherit));
+        let output_writer = self.take_output_writer()?;
+        let (mut child, _resources) = self.spawn_process(&mut cmd)?;
+        let pump = pump_output(child.stdout.take().zip(output_writer), self.effective_stdout_buffer_capacity());
+        let (result, ()) = tokio::join!(child.wait(), pump);
+        crate::telemetry::job_finished(matches!(&result, Ok(status) if status.success()));
+        result.map_err(|e| Error::ProcessSpawnFailed { source: e })
     }
 
     /// Starts the job in monitored mode, returning a `JobHandle`.
@@ -407,128 +2364,8 @@ impl JobBuilder {
     ///
     /// Returns an `Error` if the process could not be spawned.
     pub fn start(self) -> Result<JobHandle, Error> {
-        let mut child = self
-            .create_process()?
-            .stdout(Stdio::piped()) // always capture stdout
-            .stderr(Stdio::piped()) // Must pipe stderr for monitoring
-            .spawn()
-            .map_err(|e| Error::ProcessSpawnFailed { source: e })?;
-
-        // Channel for sending events from the background task to the main handle.
-        let (event_tx, event_rx) = mpsc::channel(128);
-
-        // We must take ownership of stderr to read from it.
-        let stderr = child
-            .stderr
-            .take()
-            .expect("BUG: stderr was not captured. This should not happen when piping.");
-
-        let stdout = child.stdout.take().expect("BUG: stdout was not captured.");
-
-        let child = Arc::new(Mutex::new(child));
-        let waiter = Arc::clone(&child);
-
-        // Spawn a background task to read from stderr and stdout and parse events.
-        tokio::spawn(async move {
-            let mut stdout_reader = BufReader::new(stdout);
-            let mut stderr_reader = FramedRead::new(stderr, LinesCodec::default());
-
-            // State for parsing the JSON block
-            let mut job_config_buffer = String::new();
-            let mut in_json_block = false;
-
-            #[derive(PartialEq)]
-            enum EventStreamState {
-                Active,
-                Eof,
-            }
-
-            let mut event_parsing_state = EventStreamState::Active;
-
-            while event_parsing_state == EventStreamState::Active {
-                let mut out_buf: Vec<u8> = Vec::new();
-                let line = select! {
-                    read_status = stdout_reader.read_until(b'\r', &mut out_buf) => {
-                        // propagate the error
-                        if let Ok(bytes_read) = read_status && bytes_read == 0 {
-                            event_parsing_state = EventStreamState::Eof;
-                        }
-
-                        Ok(match PROGRESS_RE.captures(&out_buf) {
-                            Some(caps) => {
-                                let event = JobEvent::Progress(crate::Progress {
-                                    percentage: parse_caps(&caps, "pct").unwrap_or_default(),
-                                    fps: parse_caps(&caps, "fps").unwrap_or_default(),
-                                    avg_fps: parse_caps(&caps, "avg_fps"),
-                                    eta: caps.name("eta").map(|v| parse_eta(&String::from_utf8_lossy(v.as_bytes()))),
-                                });
-                                // remove all occurrences of the progress
-                                out_buf = PROGRESS_RE.replace_all(&out_buf, b"").into();
-
-                                event
-                            },
-                            None => JobEvent::Fragment(out_buf.to_vec()),
-                        })
-                    },
-                    line = stderr_reader.next() => match line {
-                        Some(Ok(v)) => {
-                            if v.ends_with("json job:") {
-                                in_json_block = true;
-                                continue; // Continue to next iteration to buffer more lines
-                            }
-
-                            if in_json_block {
-                                job_config_buffer.push_str(&v);
-                                job_config_buffer.push('\n');
-                                if v == "}" {
-                                    in_json_block = false;
-                                    match serde_json::from_str::<crate::event::Config>(&job_config_buffer) {
-                                        Ok(config) => Ok(JobEvent::Config(config)),
-                                        Err(e) => Ok(JobEvent::Log(Log { message: format!("JSON Parse Error: {}, \n{}", e, job_config_buffer) })),
-                                    }
-                                } else {
-                                    continue; // Continue buffering
-                                }
-                            } else {
-                                Ok(JobEvent::Log(Log { message: v }))
-                            }
-                        },
-                        Some(Err(e)) => Err(std::io::Error::new(io::ErrorKind::InvalidData, e)),
-                        None => continue,
-                    },
-                };
-
-                match line {
-                    Ok(event) => {
-                        let _ = event_tx.send(event).await;
-                        // send the trailing/preceding output buffer
-                        if !out_buf.is_empty() {
-                            let _ = event_tx.send(JobEvent::Fragment(out_buf.to_vec())).await;
-                        }
-                    }
-                    Err(e) => {
-                        let _ = event_tx
-                            .send(JobEvent::Log(Log {
-                                message: format!("Failed to read the line: {:?}", e).to_string(),
-                            }))
-                            .await;
-                    }
-                };
-            }
-            match waiter.lock().await.wait().await {
-                Ok(status) => event_tx.send(JobEvent::Done(Ok(status))).await,
-                Err(e) => {
-                    event_tx
-                        .send(JobEvent::Done(Err(crate::JobFailure {
-                            message: format!("Failed: {}", e),
-                            exit_code: e.raw_os_error(),
-                        })))
-                        .await
-                }
-            }
-        });
-
-        Ok(JobHandle { child, event_rx })
+        let cmd = self.create_process()?;
+        self.spawn_monitored(cmd)
     }
 
     /// Starts the job in monitored mode with custom args, returning a `JobHandle`.
@@ -539,72 +2376,247 @@ impl JobBuilder {
     /// # Errors
     ///
     /// Returns an `Error` if the process could not be spawned.
-    pub fn start_with_args(self, args: Vec<String>) -> Result<JobHandle, Error> {
-        let mut child = self
-            .create_process_with_args(args)?
-            .stdout(Stdio::piped()) // always capture stdout
-            .stderr(Stdio::piped()) // Must pipe stderr for monitoring
-            .spawn()
-            .map_err(|e| Error::ProcessSpawnFailed { source: e })?;
+    pub fn start_with_args(self, args: impl IntoIterator<Item = impl Into<OsString>>) -> Result<JobHandle, Error> {
+        let cmd = self.create_process_with_args(args.into_iter().map(Into::into).collect())?;
+        self.spawn_monitored(cmd)
+    }
 
-        // Channel for sending events from the background task to the main handle.
-        let (event_tx, event_rx) = mpsc::channel(128);
+    /// Resolves a path against the working directory of `HandBrakeCLI`, as it would.
+    fn in_current_dir(&self, path: &Path) -> PathBuf {
+        match &self.current_dir {
+            Some(dir) => dir.join(path),
+            None => path.to_path_buf(),
+        }
+    }
 
-        // We must take ownership of stderr to read from it.
-        let stderr = child
-            .stderr
-            .take()
-            .expect("BUG: stderr was not captured. This should not happen when piping.");
+    /// Opens the file `stderr` is redirected to, if any.
+    fn stderr_stdio(&self) -> Result<Option<Stdio>, Error> {
+        match &self.stderr_file {
+            Some(path) => Ok(Some(Stdio::from(std::fs::File::create(self.in_current_dir(path))?))),
+            None => Ok(None),
+        }
+    }
+
+    /// Spawns the configured command and the background task that turns its output into `JobEvent`s.
+    fn spawn_monitored(self, mut cmd: Command) -> Result<JobHandle, Error> {
+        let started_at = Instant::now();
+        let user_data = self.user_data.clone().map(Arc::new);
+        cmd.stdout(Stdio::piped()) // always capture stdout
+            .stderr(self.stderr_stdio()?.unwrap_or_else(Stdio::piped)); // Must pipe stderr for monitoring
+        let log_file = match &self.log_file {
+            Some((path, level)) => Some((tokio::fs::File::from_std(std::fs::File::create(path)?), *level)),
+            None => None,
+        };
+        let target_writer = self.take_output_writer()?;
+        let command_line = std::iter::once(cmd.as_std().get_program())
+            .chain(cmd.as_std().get_args())
+            .map(OsStr::to_os_string)
+            .collect();
+        let (mut child, resources) = self.spawn_process(&mut cmd)?;
+
+        // Channel for sending events from the background task to the main handle.
+        let (event_tx, event_rx) = mpsc::channel(EVENT_CHANNEL_CAPACITY);
+        // The events dropped as the channel was full.
+        let dropped_events = Arc::new(AtomicU64::new(0));
+        let handle_dropped_events = Arc::clone(&dropped_events);
+        // Channel holding the latest progress, for consumers that don't need every event.
+        let (progress_tx, progress_rx) = watch::channel(None);
+        // Channel carrying the unparsed `stderr` lines, which are dropped if nobody reads them.
+        let (raw_lines_tx, raw_lines) = broadcast::channel(RAW_LINES_CAPACITY);
+
+        // We must take ownership of stderr to read from it, unless it goes to a file.
+        let stderr = child.stderr.take();
 
         let stdout = child.stdout.take().expect("BUG: stdout was not captured.");
 
-        let child = Arc::new(Mutex::new(child));
-        let waiter = Arc::clone(&child);
+        // stdin is only piped when reading the input from it.
+        let input_writer = child.stdin.take();
+
+        // When encoding to stdout, the video data can be read through a dedicated pipe
+        // instead of `Fragment` events, once the caller takes the reader from the handle.
+        // An `OutputDestination::Writer` receives the video data from the start instead.
+        let writes_to_target = target_writer.is_some();
+        let (mut output_writer, output_reader) = match self.output {
+            OutputDestination::Stdout => {
+                let chunk_size = self.stdout_chunk_size.unwrap_or(OUTPUT_PIPE_CAPACITY).max(1);
+                let (writer, reader) = tokio::io::duplex(chunk_size);
+                (Some(Box::pin(writer) as BoxedWriter), Some(reader))
+            }
+            _ => (target_writer, None),
+        };
+        let output_path = match &self.output {
+            // The output is relative to the working directory of `HandBrakeCLI`.
+            OutputDestination::File(path) => Some(self.in_current_dir(path)),
+            OutputDestination::Stdout | OutputDestination::Writer(_) => None,
+        };
+        let output_path_for_task = output_path.clone();
+        let output_taken = Arc::new(AtomicBool::new(writes_to_target));
+        let output_redirected = Arc::clone(&output_taken);
+
+        let pid = child.id();
+        // A dedicated task waits for the process, so control operations never wait for it to exit.
+        let (control, control_rx) = mpsc::unbounded_channel();
+        let waiter = tokio::spawn(crate::handle::wait_for_child(child, control_rx));
+        let state = Arc::new(watch::channel(JobState::Running).0);
+        let controller = JobController {
+            control,
+            progress_rx,
+            state: Arc::clone(&state),
+            pid,
+            killed: Arc::new(AtomicBool::new(false)),
+            #[cfg(windows)]
+            job_object: Arc::clone(&resources.job_object),
+        };
+        if let Some(registry) = &self.registry {
+            let mut jobs = registry.lock().unwrap();
+            jobs.retain(|job| !job.state().is_finished());
+            jobs.push(controller.clone());
+        }
+        let task_controller = controller.clone();
+        let timeout = self.timeout;
+        let disk_space_limits = (self.min_free_space, self.free_space_warnings.clone());
+        let output_size_interval = self.output_size_interval.filter(|interval| !interval.is_zero());
+        let stdout_buffer_capacity = self.effective_stdout_buffer_capacity();
+        let subtitle_scan = self.subtitle_selection == Some(SubtitleSelection::Scan);
+        let json_output = self.json_output;
+        let mut cancel_token = self.cancel_token.clone();
+        let mut notifications = self.notifier.clone().map(|notifier| {
+            crate::notify::Notifications::spawn(notifier, &self.input, &self.output)
+        });
+        if let Some(notifications) = &notifications {
+            notifications.started();
+        }
+
+        let handle_user_data = user_data.clone();
+        let version_warning = self.handbrake_version.as_deref().and_then(crate::compat::untested_warning);
 
         // Spawn a background task to read from stderr and stdout and parse events.
         tokio::spawn(async move {
-            let mut stdout_reader = BufReader::new(stdout);
-            let mut stderr_reader = FramedRead::new(stderr, LinesCodec::default());
+            let mut stdout_reader = BufReader::with_capacity(stdout_buffer_capacity, stdout);
+            let mut stderr_reader = stderr.map(|stderr| FramedRead::new(stderr, LossyLinesCodec::default()));
 
+            // The title listing printed after the scan.
+            let mut scan_listing = ScanListing::default();
+            // The duration of the output, which the progress percentage is a share of.
+            let mut output_duration = None;
             // State for parsing the JSON block
             let mut job_config_buffer = String::new();
             let mut in_json_block = false;
-
-            #[derive(PartialEq)]
-            enum EventStreamState {
-                Active,
-                Eof,
+            // The JSON block being read from `stdout` in `--json` mode.
+            let mut stdout_json_block: Option<String> = None;
+
+            let mut stdout_open = true;
+            let mut stderr_open = stderr_reader.is_some();
+
+            // The job is first cancelled when the timeout elapses or the disk space runs out,
+            // then killed at the deadline after a grace period.
+            let mut deadline = timeout.map(|t| tokio::time::Instant::now() + t);
+            let mut timed_out = false;
+            let mut stopping = false;
+            let mut disk_space = output_path_for_task
+                .as_ref()
+                .and_then(|path| DiskSpaceWatch::new(path, disk_space_limits.0, &disk_space_limits.1));
+            let mut output_size = output_path_for_task
+                .zip(output_size_interval)
+                .map(|(path, interval)| OutputSizeWatch::new(path, interval));
+            // The free space the job was stopped at, if it ran out.
+            let mut out_of_space = None;
+
+            // Kept across iterations, as `read_line_chunk` leaves partially read data in it when
+            // the stderr branch of the `select!` completes first.
+            let mut out_buf: Vec<u8> = Vec::new();
+            // The complete `stdout` chunk being handled, swapped with `out_buf` so that both keep
+            // their capacity and reading doesn't allocate once they have grown.
+            let mut chunk: Vec<u8> = Vec::new();
+            // Whether the last `stdout` chunk was a progress line, whose `\r\n` ending may be
+            // split across two chunks.
+            let mut after_progress = false;
+
+            // This job's share of the summed encoding speed metric.
+            let mut reported_fps = 0.0;
+
+            // Logging stops at the first failed write.
+            let mut log_file = log_file;
+
+            if let Some(message) = version_warning {
+                send_event(&event_tx, &dropped_events, Timestamped::now(JobEvent::Log(Log { message }), started_at, &user_data)).await;
             }
 
-            let mut event_parsing_state = EventStreamState::Active;
-
-            while event_parsing_state == EventStreamState::Active {
-                let mut out_buf: Vec<u8> = Vec::new();
-                let line = select! {
-                    read_status = stdout_reader.read_until(b'\r', &mut out_buf) => {
-                        // propagate the error
-                        if let Ok(bytes_read) = read_status && bytes_read == 0 {
-                            event_parsing_state = EventStreamState::Eof;
+            while stdout_open || stderr_open {
+                chunk.clear();
+                let event = select! {
+                    read_status = read_line_chunk(&mut stdout_reader, &mut out_buf), if stdout_open => {
+                        match read_status {
+                            Ok(0) => {
+                                stdout_open = false;
+                                // A cancelled read may have left unterminated data behind.
+                                if out_buf.is_empty() {
+                                    continue;
+                                }
+                            }
+                            Ok(_) => {}
+                            Err(e) => {
+                                stdout_open = false;
+                                send_event(&event_tx, &dropped_events, Timestamped::now(JobEvent::Log(Log {
+                                    message: format!("Failed to read the line: {:?}", e),
+                                }), started_at, &user_data)).await;
+                                continue;
+                            }
                         }
 
-                        Ok(match PROGRESS_RE.captures(&out_buf) {
-                            Some(caps) => {
-                                let event = JobEvent::Progress(crate::Progress {
-                                    percentage: parse_caps(&caps, "pct").unwrap_or_default(),
-                                    fps: parse_caps(&caps, "fps").unwrap_or_default(),
-                                    avg_fps: parse_caps(&caps, "avg_fps"),
-                                    eta: caps.name("eta").map(|v| parse_eta(&String::from_utf8_lossy(v.as_bytes()))),
-                                });
+                        std::mem::swap(&mut chunk, &mut out_buf);
+                        if json_output {
+                            let text = String::from_utf8_lossy(&chunk);
+                            let line = text.trim_end_matches(['\r', '\n']);
+                            if let Some(block) = &mut stdout_json_block {
+                                block.push_str(line);
+                                block.push('\n');
+                                if line != "}" {
+                                    continue; // Continue buffering
+                                }
+                                let block = stdout_json_block.take().unwrap_or_default();
+                                chunk.clear();
+                                match parse_json_block(&block) {
+                                    Some(event) => Some(event),
+                                    None => continue,
+                                }
+                            } else if line.ends_with(": {") {
+                                stdout_json_block = Some(format!("{}\n", line));
+                                continue;
+                            } else if line.is_empty() {
+                                continue;
+                            } else {
+                                None
+                            }
+                        } else {
+                            if std::mem::take(&mut after_progress) && chunk == b"\n" {
+                                continue;
+                            }
+                            let event = parse_progress(&chunk, subtitle_scan).map(JobEvent::Progress);
+                            after_progress = event.is_some();
+                            if event.is_some() {
                                 // remove all occurrences of the progress
-                                out_buf = PROGRESS_RE.replace_all(&out_buf, b"").into();
-
-                                event
-                            },
-                            None => JobEvent::Fragment(out_buf.to_vec()),
-                        })
+                                strip_progress(&mut chunk);
+                            }
+                            event
+                        }
                     },
-                    line = stderr_reader.next() => match line {
+                    line = next_line(&mut stderr_reader), if stderr_open => match line {
                         Some(Ok(v)) => {
+                            // Nobody can read the lines once the `JobHandle` retaining them from the
+                            // start is consumed, e.g., by `wait()`, and no `raw_lines()` stream is left.
+                            if raw_lines_tx.receiver_count() > 0 {
+                                let _ = raw_lines_tx.send(v.clone());
+                            }
+                            write_log(&mut log_file, &v).await;
+                            // The title listing ends at the first line outside of it.
+                            if !scan_listing.push(&v)
+                                && let Some(summary) = scan_listing.finish()
+                            {
+                                let event = JobEvent::ScanSummary(summary);
+                                output_duration = known_duration(&event).or(output_duration);
+                                send_event(&event_tx, &dropped_events, Timestamped::now(event, started_at, &user_data)).await;
+                            }
                             if v.ends_with("json job:") {
                                 in_json_block = true;
                                 continue; // Continue to next iteration to buffer more lines
@@ -615,106 +2627,344 @@ impl JobBuilder {
                                 job_config_buffer.push('\n');
                                 if v == "}" {
                                     in_json_block = false;
-                                    match serde_json::from_str::<crate::event::Config>(&job_config_buffer) {
-                                        Ok(config) => Ok(JobEvent::Config(config)),
-                                        Err(e) => Ok(JobEvent::Log(Log { message: format!("JSON Parse Error: {}, \n{}", e, job_config_buffer) })),
+                                    match crate::compat::parse_config(&job_config_buffer) {
+                                        Ok(config) => Some(JobEvent::Config(Box::new(config))),
+                                        Err(e) => Some(JobEvent::Log(Log { message: format!("JSON Parse Error: {}, \n{}", e, job_config_buffer) })),
                                     }
                                 } else {
                                     continue; // Continue buffering
                                 }
+                            } else if let Some(progress) = parse_progress(v.as_bytes(), subtitle_scan) {
+                                // Some builds write the progress to `stderr` instead.
+                                Some(JobEvent::Progress(progress))
                             } else {
-                                Ok(JobEvent::Log(Log { message: v }))
+                                Some(crate::diagnostic::classify_line(v))
                             }
                         },
-                        Some(Err(e)) => Err(std::io::Error::new(io::ErrorKind::InvalidData, e)),
-                        None => continue,
+                        Some(Err(e)) => {
+                            let message = format!("Failed to read the line: {:?}", e);
+                            Some(JobEvent::Log(Log { message }))
+                        }
+                        None => {
+                            stderr_open = false;
+                            if let Some(summary) = scan_listing.finish() {
+                                Some(JobEvent::ScanSummary(summary))
+                            } else {
+                                continue;
+                            }
+                        }
+                    },
+                    _ = token_cancelled(cancel_token.clone()) => {
+                        cancel_token = None;
+                        let _ = task_controller.cancel().await;
+                        continue;
+                    }
+                    _ = sleep_until_deadline(deadline) => {
+                        if stopping {
+                            let _ = task_controller.kill().await;
+                            deadline = None;
+                        } else {
+                            timed_out = true;
+                            stopping = true;
+                            let _ = task_controller.cancel().await;
+                            deadline = Some(tokio::time::Instant::now() + TIMEOUT_KILL_GRACE);
+                        }
+                        continue;
+                    }
+                    (bytes, bytes_per_sec) = next_output_size(&mut output_size) => {
+                        Some(JobEvent::OutputSize { bytes, bytes_per_sec })
+                    }
+                    sample = next_disk_space_sample(&mut disk_space) => match sample {
+                        Sample::Warning { free_bytes, threshold } => Some(JobEvent::LowDiskSpace { free_bytes, threshold }),
+                        Sample::Exhausted { free_bytes } => {
+                            disk_space = None;
+                            if !stopping {
+                                out_of_space = Some(free_bytes);
+                                stopping = true;
+                                let _ = task_controller.cancel().await;
+                                deadline = Some(tokio::time::Instant::now() + TIMEOUT_KILL_GRACE);
+                            }
+                            continue;
+                        }
                     },
                 };
 
-                match line {
-                    Ok(event) => {
-                        let _ = event_tx.send(event).await;
-                        // send the trailing/preceding output buffer
-                        if !out_buf.is_empty() {
-                            let _ = event_tx.send(JobEvent::Fragment(out_buf.to_vec())).await;
+                if let Some(mut event) = event {
+                    output_duration = known_duration(&event).or(output_duration);
+                    if let JobEvent::Progress(progress) = &mut event {
+                        progress.position = output_duration.map(|duration| duration.mul_f32(progress.percentage / 100.0));
+                    }
+                    if matches!(&log_file, Some((_, LogLevel::Events))) && matches!(event, JobEvent::Progress(_)) {
+                        write_log(&mut log_file, &format!("[event] {:?}", event)).await;
+                    }
+                    if let JobEvent::Progress(progress) = &event {
+                        progress_tx.send_replace(Some(progress.clone()));
+                        crate::telemetry::fps_changed(reported_fps, progress.fps);
+                        reported_fps = progress.fps;
+                        // The subtitle scan pass has its own percentage, which isn't a job milestone.
+                        if let Some(notifications) = &mut notifications
+                            && progress.phase != Phase::SubtitleScan
+                        {
+                            notifications.progress(progress.percentage);
                         }
                     }
-                    Err(e) => {
-                        let _ = event_tx
-                            .send(JobEvent::Log(Log {
-                                message: format!("Failed to read the line: {:?}", e).to_string(),
-                            }))
-                            .await;
+                    send_event(&event_tx, &dropped_events, Timestamped::now(event, started_at, &user_data)).await;
+                }
+                // send the trailing/preceding output buffer
+                if chunk.is_empty() {
+                    continue;
+                }
+                if output_redirected.load(Ordering::Acquire) {
+                    // A failed write means the reader was dropped, so the output is discarded.
+                    if let Some(writer) = output_writer.as_mut()
+                        && let Err(e) = writer.write_all(&chunk).await
+                    {
+                        output_writer = None;
+                        if writes_to_target {
+                            let message = format!("Failed to write the output: {e}");
+                            send_event(&event_tx, &dropped_events, Timestamped::now(JobEvent::Log(Log { message }), started_at, &user_data)).await;
+                        }
                     }
-                };
-            }
-            match waiter.lock().await.wait().await {
-                Ok(status) => event_tx.send(JobEvent::Done(Ok(status))).await,
-                Err(e) => {
-                    event_tx
-                        .send(JobEvent::Done(Err(crate::JobFailure {
-                            message: format!("Failed: {}", e),
-                            exit_code: e.raw_os_error(),
-                        })))
-                        .await
+                } else {
+                    send_event(&event_tx, &dropped_events, Timestamped::now(JobEvent::Fragment(std::mem::take(&mut chunk)), started_at, &user_data)).await;
                 }
             }
+            // Signal EOF to the output reader before reporting completion.
+            if let Some(mut writer) = output_writer {
+                let _ = writer.shutdown().await;
+            }
+            let result = waiter.await.unwrap_or_else(|e| Err(io::Error::other(e)));
+            resources.release();
+            let cancelled = *state.borrow() == JobState::Cancelling;
+            let killed = task_controller.killed.load(Ordering::Acquire);
+            state.send_replace(match &result {
+                Ok(status) => JobState::Exited(*status),
+                Err(_) => JobState::Failed,
+            });
+            crate::telemetry::fps_changed(reported_fps, 0.0);
+            crate::telemetry::job_finished(!stopping && matches!(&result, Ok(status) if status.success()));
+            let done = match result {
+                Ok(status) if timed_out => Err(crate::JobFailure {
+                    message: format!("Job timed out after {:?}", timeout.unwrap_or_default()),
+                    exit_code: status.code(),
+                    kind: FailureKind::Timeout,
+                }),
+                Ok(status) if out_of_space.is_some() => Err(crate::JobFailure {
+                    message: format!(
+                        "Job was stopped with {} bytes free on the output volume",
+                        out_of_space.unwrap_or_default()
+                    ),
+                    exit_code: status.code(),
+                    kind: FailureKind::DiskSpace,
+                }),
+                Ok(status) if killed && !status.success() => Err(crate::JobFailure {
+                    message: "Job was killed".to_string(),
+                    exit_code: status.code(),
+                    kind: FailureKind::Killed,
+                }),
+                Ok(status) if cancelled && !status.success() => Err(crate::JobFailure {
+                    message: "Job was cancelled".to_string(),
+                    exit_code: status.code(),
+                    kind: FailureKind::Cancelled,
+                }),
+                Ok(status) => Ok(status),
+                Err(e) => Err(crate::JobFailure {
+                    message: format!("Failed: {}", e),
+                    exit_code: e.raw_os_error(),
+                    kind: FailureKind::Process,
+                }),
+            };
+            if let Some(notifications) = &notifications {
+                notifications.completed(&done);
+            }
+            if matches!(&log_file, Some((_, LogLevel::Events))) {
+                write_log(&mut log_file, &format!("[event] Done({:?})", done)).await;
+            }
+            if let Some((file, _)) = &mut log_file {
+                let _ = file.flush().await;
+            }
+            // Progress watchers see the job end even if nobody reads the events.
+            drop(progress_tx);
+            // Only `Done` waits for room in the channel, as it is the last event.
+            let _ = event_tx.send(Timestamped::now(JobEvent::Done(done), started_at, &user_data)).await;
         });
 
-        Ok(JobHandle { child, event_rx })
+        Ok(JobHandle {
+            controller,
+            event_rx: Some(event_rx),
+            input_writer,
+            output_reader,
+            output_taken,
+            output_path,
+            started_at,
+            raw_lines,
+            raw_lines_taken: false,
+            user_data: handle_user_data,
+            command_line,
+            dropped_events: handle_dropped_events,
+        })
     }
 
+    /// The audio tracks with a per-track option, in the order of their numbers.
+    fn audio_tracks(&self) -> Vec<u32> {
+        let tracks: BTreeSet<u32> = self
+            .audio_codecs
+            .keys()
+            .chain(self.audio_mixdowns.keys())
+            .chain(self.normalize_mix.keys())
+            .chain(self.audio_qualities.keys())
+            .copied()
+            .collect();
+        tracks.into_iter().collect()
+    }
 
     /// Builds the final list of command-line arguments based on the configured options.
-    pub fn build_args(&self) -> Vec<String> {
-        let mut args: Vec<String> = Vec::new();
+    ///
+    /// Paths are passed as they are, so file names that are not valid UTF-8 reach `HandBrakeCLI`
+    /// unchanged.
+    pub fn build_args(&self) -> Vec<OsString> {
+        let mut args: Vec<OsString> = Vec::new();
 
         if self.import_gui_presets {
             args.push("--preset-import-gui".into());
         }
 
+        if let Some(level) = &self.verbosity {
+            args.push(format!("--verbose={}", level).into());
+        }
+
+        if self.json_output {
+            args.push("--json".into());
+        }
+
         // Input argument
         match &self.input {
-            InputSource::File(path) => args.extend(["-i".into(), path.display().to_string()]),
-            InputSource::Stdin => args.extend(["-i".into(), "pipe:0".into()]),
+            InputSource::File(path) => args.extend(["-i".into(), path.into()]),
+            InputSource::Stdin | InputSource::Reader(_) => {
+                args.extend(["-i".into(), "pipe:0".into()])
+            }
+            InputSource::Device(path) => args.extend(["-i".into(), path.into()]),
         }
 
         // Output argument
         match &self.output {
             OutputDestination::File(path) => {
-                args.extend(["-o".to_string(), path.display().to_string()])
+                args.extend(["-o".into(), path.into()])
+            }
+            OutputDestination::Stdout | OutputDestination::Writer(_) => {
+                args.extend(["-o".into(), "pipe:1".into()])
             }
-            OutputDestination::Stdout => args.extend(["-o".into(), "pipe:1".into()]),
         }
 
         // Optional arguments
         if let Some(p) = &self.preset {
-            args.extend(["--preset".into(), p.clone()]);
+            args.extend(["--preset".into(), p.into()]);
         }
         if let Some(vc) = &self.video_codec {
-            args.extend(["--encoder".into(), vc.clone()]);
+            args.extend(["--encoder".into(), vc.into()]);
+        }
+        if let Some(encopts) = &self.advanced_encoder_options {
+            args.extend(["--encopts".into(), encopts.into()]);
         }
         if let Some(w) = &self.width {
-            args.extend(["--width".into(), w.to_string()]);   
+            args.extend(["--width".into(), w.to_string().into()]);   
         }
         if let Some(h) = &self.height {
-            args.extend(["--height".into(), h.to_string()]);
+            args.extend(["--height".into(), h.to_string().into()]);
+        }
+        if let Some(w) = &self.max_width {
+            args.extend(["--maxWidth".into(), w.to_string().into()]);
+        }
+        if let Some(h) = &self.max_height {
+            args.extend(["--maxHeight".into(), h.to_string().into()]);
+        }
+        match self.keep_display_aspect {
+            Some(true) => args.push("--keep-display-aspect".into()),
+            Some(false) => args.push("--no-keep-display-aspect".into()),
+            None => {}
+        }
+        if let Some(m) = &self.modulus {
+            args.extend(["--modulus".into(), m.to_string().into()]);
         }
-        if let Some(phdrmd) = &self.preserve_hdr_metadata {
-            args.extend(["--hdr-dynamic-metadata".into(), phdrmd.to_string()]);
+        if let Some(w) = &self.display_width {
+            args.extend(["--display-width".into(), w.to_string().into()]);
+        }
+        if let Some((x, y)) = &self.pixel_aspect {
+            args.extend(["--pixel-aspect".into(), format!("{}:{}", x, y).into()]);
+        }
+        if self.itu_par {
+            args.push("--itu-par".into());
+        }
+        if let Some(matrix) = &self.color_matrix {
+            args.extend(["--color-matrix".into(), matrix.to_string().into()]);
+        }
+        let colorspace: Vec<String> = [
+            self.color_range.map(|r| format!("range={}", r)),
+            self.color_primaries.map(|p| format!("primaries={}", p)),
+            self.color_transfer.map(|t| format!("transfer={}", t)),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        if !colorspace.is_empty() {
+            args.push(format!("--colorspace={}", colorspace.join(":")).into());
+        }
+        match &self.preserve_hdr_metadata {
+            Some(PreserveHdrMetadata::Disabled) => args.push("--no-hdr-dynamic-metadata".into()),
+            Some(phdrmd) => args.extend(["--hdr-dynamic-metadata".into(), phdrmd.to_string().into()]),
+            None => {}
+        }
+        match &self.comb_detect {
+            Some(CombDetect::Default) => args.push("--comb-detect".into()),
+            Some(comb_detect) => args.push(format!("--comb-detect={}", comb_detect).into()),
+            None => {}
+        }
+        for filter in &self.disabled_filters {
+            args.push(format!("--no-{}", filter).into());
         }
         // Audio codecs
         // Sort by track number for consistent argument order, though not strictly necessary for HBCLI
         let mut sorted_audio_codecs: Vec<(&u32, &String)> = self.audio_codecs.iter().collect();
         sorted_audio_codecs.sort_by_key(|&(track, _)| track);
         for (track, codec) in sorted_audio_codecs {
-            args.extend(["--audio".into(), format!("{},{}", track, codec)]);
+            args.extend(["--audio".into(), format!("{},{}", track, codec).into()]);
         }
-        if let Some(q) = &self.quality {
-            args.extend(["--quality".into(), q.to_string()]);
+        let audio_tracks = self.audio_tracks();
+        let mixdowns: HashMap<u32, String> =
+            self.audio_mixdowns.iter().map(|(&track, mixdown)| (track, mixdown.to_string())).collect();
+        if let Some(mixdowns) = per_track_list(&audio_tracks, &mixdowns, String::new()) {
+            args.extend(["--mixdown".into(), mixdowns.into()]);
+        }
+        let normalize_mix: HashMap<u32, u8> =
+            self.normalize_mix.iter().map(|(&track, &normalize)| (track, normalize as u8)).collect();
+        if let Some(normalize_mix) = per_track_list(&audio_tracks, &normalize_mix, 0) {
+            args.extend(["--normalize-mix".into(), normalize_mix.into()]);
+        }
+        let audio_qualities: HashMap<u32, String> =
+            self.audio_qualities.iter().map(|(&track, quality)| (track, quality.to_string())).collect();
+        if let Some(audio_qualities) = per_track_list(&audio_tracks, &audio_qualities, String::new()) {
+            args.extend(["--aq".into(), audio_qualities.into()]);
+        }
+        match &self.rate_control {
+            Some(RateControl::ConstantQuality(q)) => {
+                args.extend(["--quality".into(), q.to_string().into()]);
+            }
+            Some(RateControl::Bitrate { kbps, two_pass, turbo }) => {
+                args.extend(["--vb".into(), kbps.to_string().into()]);
+                if *two_pass {
+                    args.push("--two-pass".into());
+                    if *turbo {
+                        args.push("--turbo".into());
+                    }
+                }
+            }
+            None => {}
+        }
+        if self.no_two_pass {
+            args.push("--no-two-pass".into());
         }
         if let Some(f) = &self.format {
-            args.extend(["--format".into(), f.to_string()]);
+            args.extend(["--format".into(), f.into()]);
         }
 
         if let Some(selection) = &self.subtitle_selection {
@@ -726,11 +2976,11 @@ impl JobBuilder {
                     .join(","),
                 SubtitleSelection::Scan => "scan".to_string(),
             };
-            args.extend(["--subtitle".into(), value]);
+            args.extend(["--subtitle".into(), value.into()]);
         }
 
         if !self.subtitle_langs.is_empty() {
-            args.extend(["--subtitle-lang-list".into(), self.subtitle_langs.join(",")]);
+            args.extend(["--subtitle-lang-list".into(), self.subtitle_langs.join(",").into()]);
         }
 
         if let Some(mode) = &self.subtitle_burned {
@@ -738,11 +2988,11 @@ impl JobBuilder {
                 SubtitleBurnMode::Native => "native".to_string(),
                 SubtitleBurnMode::None => "none".to_string(),
             };
-            args.extend(["--subtitle-burned".into(), value]);
+            args.extend(["--subtitle-burned".into(), value.into()]);
         }
 
         if let Some(track) = &self.subtitle_forced {
-            args.extend(["--subtitle-forced".into(), track.to_string()]);
+            args.extend(["--subtitle-forced".into(), track.to_string().into()]);
         }
 
         if let Some(mode) = &self.subtitle_default {
@@ -750,61 +3000,147 @@ impl JobBuilder {
                 SubtitleDefaultMode::Track(t) => t.to_string(),
                 SubtitleDefaultMode::None => "none".to_string(),
             };
-            args.extend(["--subtitle-default".into(), value]);
+            args.extend(["--subtitle-default".into(), value.into()]);
+        }
+
+        if let Some(lang) = &self.native_language {
+            args.extend(["--native-language".into(), lang.into()]);
+        }
+
+        if self.native_dub {
+            args.push("--native-dub".into());
         }
 
         if let Some(srt_file) = &self.srt_file {
-            args.extend(["--srt-file".into(), srt_file.clone()]);
+            args.extend(["--srt-file".into(), srt_file.into()]);
         }
 
         if let Some(ssa_file) = &self.ssa_file {
-            args.extend(["--ssa-file".into(), ssa_file.clone()]);
+            args.extend(["--ssa-file".into(), ssa_file.into()]);
         }
 
+        if let Some((first, last)) = &self.chapters {
+            let value = if first == last {
+                first.to_string()
+            } else {
+                format!("{}-{}", first, last)
+            };
+            args.extend(["--chapters".into(), value.into()]);
+        }
+
+        if let Some(angle) = &self.angle {
+            args.extend(["--angle".into(), angle.to_string().into()]);
+        }
+
+        if self.no_dvdnav {
+            args.push("--no-dvdnav".into());
+        }
+
+        if let Some((start, duration)) = &self.time_range {
+            args.extend(["--start-at".into(), format!("duration:{}", start.as_secs_f64()).into()]);
+            args.extend(["--stop-at".into(), format!("duration:{}", duration.as_secs_f64()).into()]);
+        }
+
+        if let Some(duration) = &self.sample {
+            args.extend(["--start-at-preview".into(), SAMPLE_PREVIEW.to_string().into()]);
+            args.extend(["--stop-at".into(), format!("duration:{}", duration.as_secs_f64()).into()]);
+        }
+
+        args.extend(self.extra_args.iter().map(OsString::from));
+
         args
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::job::PROGRESS_RE;
+/// Renders the command line that runs the job, quoted so it can be pasted into a shell,
+/// e.g., for audit logs and bug reports.
+///
+/// The environment and working directory of the process are not part of it, and arguments that
+/// are not valid UTF-8 are rendered lossily.
+impl std::fmt::Display for JobBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&shell_quote(&self.handbrake_path.to_string_lossy()))?;
+        for arg in self.build_args() {
+            write!(f, " {}", shell_quote(&arg.to_string_lossy()))?;
+        }
+        Ok(())
+    }
+}
 
-    #[test]
-    fn test_progress_re_full_match() {
-        let line = "Encoding: task 1 of 1, 12.34 % (120.00 fps, avg 110.00 fps, ETA 00h01m30s)";
-        let caps = PROGRESS_RE.captures(line.as_bytes()).unwrap();
+/// Joins the per-track values of an audio option in the order of the tracks, e.g., `"1,0"`.
+///
+/// The list stops at the last track with a value, as `HandBrakeCLI` uses its own defaults for the
+/// remaining tracks, while earlier gaps are filled with `gap`.
+fn per_track_list<T: std::fmt::Display>(tracks: &[u32], values: &HashMap<u32, T>, gap: T) -> Option<String> {
+    let last = tracks.iter().rposition(|track| values.contains_key(track))?;
+    let values: Vec<String> = tracks[..=last]
+        .iter()
+        .map(|track| values.get(track).unwrap_or(&gap).to_string())
+        .collect();
+    Some(values.join(","))
+}
 
-        assert_eq!(&caps["pct"], b"12.34");
-        assert_eq!(&caps["fps"], b"120.00");
-        assert_eq!(&caps["avg_fps"], b"110.00");
-        assert_eq!(&caps["eta"], b"00h01m30s");
+/// Quotes an argument for a POSIX shell, unless it only contains characters that need no quoting.
+#[cfg(not(windows))]
+fn shell_quote(arg: &str) -> std::borrow::Cow<'_, str> {
+    if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "_-./:=,+@%".contains(c)) {
+        return arg.into();
     }
+    format!("'{}'", arg.replace('\'', r"'\''")).into()
+}
 
-    #[test]
-    fn test_progress_re_pct_only() {
-        let line = "Encoding: task 1 of 1, 56.78 %";
-        let caps = PROGRESS_RE.captures(line.as_bytes()).unwrap();
-
-        assert_eq!(&caps["pct"], b"56.78");
-        assert!(caps.name("fps").is_none());
-        assert!(caps.name("avg_fps").is_none());
-        assert!(caps.name("eta").is_none());
+/// Quotes an argument the way `CommandLineToArgvW` splits it, unless it needs no quoting.
+#[cfg(windows)]
+fn shell_quote(arg: &str) -> std::borrow::Cow<'_, str> {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+        return arg.into();
+    }
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                // Backslashes before a quote are escaped, as is the quote itself.
+                quoted.extend(std::iter::repeat_n('\\', backslashes * 2 + 1));
+                backslashes = 0;
+            }
+            _ => {
+                quoted.extend(std::iter::repeat_n('\\', backslashes));
+                backslashes = 0;
+            }
+        }
+        if c != '\\' {
+            quoted.push(c);
+        }
     }
+    // Backslashes before the closing quote are escaped.
+    quoted.extend(std::iter::repeat_n('\\', backslashes * 2));
+    quoted.push('"');
+    quoted.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::job::{parse_version, strip_progress};
 
     #[test]
-    fn test_progress_re_no_match() {
-        let line = "Some other output that does not match";
-        assert!(PROGRESS_RE.captures(line.as_bytes()).is_none());
+    fn test_parse_version() {
+        assert_eq!(parse_version("HandBrake 1.7.2\n"), Some((1, 7, 2)));
+        assert_eq!(parse_version("HandBrake 20240101000000-abc-master"), None);
     }
 
     #[test]
-    fn test_progress_re_another_full_match() {
-        let line = "Encoding: task 2 of 5, 99.99 % (30.00 fps, avg 25.50 fps, ETA 01h23m45s)";
-        let caps = PROGRESS_RE.captures(line.as_bytes()).unwrap();
-
-        assert_eq!(&caps["pct"], b"99.99");
-        assert_eq!(&caps["fps"], b"30.00");
-        assert_eq!(&caps["avg_fps"], b"25.50");
-        assert_eq!(&caps["eta"], b"01h23m45s");
+    fn test_strip_progress_in_place() {
+        let mut data = b"abc\rEncoding: task 1 of 1, 12.34 %\rdefMuxing: this may take awhile...\nghi".to_vec();
+        let capacity = data.capacity();
+        strip_progress(&mut data);
+
+        assert_eq!(data, b"abc\rdefghi");
+        assert_eq!(data.capacity(), capacity);
+
+        let mut data = b"no progress here".to_vec();
+        strip_progress(&mut data);
+        assert_eq!(data, b"no progress here");
     }
 }
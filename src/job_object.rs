@@ -0,0 +1,118 @@
+//! Windows Job Objects tying the lifetime of the `HandBrakeCLI` process tree to its handle.
+
+use std::io;
+use std::mem::size_of;
+use std::ptr;
+
+use tokio::process::Child;
+use windows_sys::Win32::Foundation::{CloseHandle, HANDLE, INVALID_HANDLE_VALUE};
+use windows_sys::Win32::System::Diagnostics::ToolHelp::{
+    CreateToolhelp32Snapshot, TH32CS_SNAPTHREAD, THREADENTRY32, Thread32First, Thread32Next,
+};
+use windows_sys::Win32::System::JobObjects::{
+    AssignProcessToJobObject, CreateJobObjectW, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JobObjectExtendedLimitInformation,
+    SetInformationJobObject, TerminateJobObject,
+};
+use windows_sys::Win32::System::Threading::{OpenThread, ResumeThread, THREAD_SUSPEND_RESUME};
+
+/// An anonymous Job Object that kills all of its processes once its last handle is closed,
+/// including when the parent process crashes.
+#[derive(Debug)]
+pub(crate) struct JobObject {
+    handle: HANDLE,
+}
+
+// SAFETY: the handle is owned exclusively by this struct, and Job Object handles can be used
+// from any thread.
+unsafe impl Send for JobObject {}
+unsafe impl Sync for JobObject {}
+
+impl JobObject {
+    /// Creates a Job Object and assigns the spawned process to it.
+    ///
+    /// Processes started by `HandBrakeCLI` afterwards are part of the same Job Object. The
+    /// process is spawned with `CREATE_SUSPENDED` and only `resume()`d once assigned, as it
+    /// could otherwise start a process before being assigned.
+    pub(crate) fn assign(child: &Child) -> io::Result<Self> {
+        let process = child
+            .raw_handle()
+            .ok_or_else(|| io::Error::other("Process already exited"))?;
+
+        let handle = unsafe { CreateJobObjectW(ptr::null(), ptr::null()) };
+        if handle.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        let job_object = JobObject { handle };
+
+        let mut info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+        info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+        let result = unsafe {
+            SetInformationJobObject(
+                job_object.handle,
+                JobObjectExtendedLimitInformation,
+                &info as *const _ as *const _,
+                size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+            )
+        };
+        if result == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        if unsafe { AssignProcessToJobObject(job_object.handle, process) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(job_object)
+    }
+
+    /// Terminates all processes in the Job Object.
+    pub(crate) fn terminate(&self) -> io::Result<()> {
+        if unsafe { TerminateJobObject(self.handle, 1) } == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for JobObject {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.handle);
+        }
+    }
+}
+
+/// Resumes the main thread of a process spawned with `CREATE_SUSPENDED`.
+///
+/// The thread handle of the process isn't kept by `Child`, so the thread is looked up by the
+/// process ID. A suspended process has no other threads yet.
+pub(crate) fn resume(child: &Child) -> io::Result<()> {
+    let process_id = child.id().ok_or_else(|| io::Error::other("Process already exited"))?;
+
+    let snapshot = unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPTHREAD, 0) };
+    if snapshot == INVALID_HANDLE_VALUE {
+        return Err(io::Error::last_os_error());
+    }
+    let mut entry = THREADENTRY32 {
+        dwSize: size_of::<THREADENTRY32>() as u32,
+        ..Default::default()
+    };
+    let mut resumed = false;
+    let mut found = unsafe { Thread32First(snapshot, &mut entry) } != 0;
+    while found {
+        if entry.th32OwnerProcessID == process_id {
+            let thread = unsafe { OpenThread(THREAD_SUSPEND_RESUME, 0, entry.th32ThreadID) };
+            if !thread.is_null() {
+                resumed |= unsafe { ResumeThread(thread) } != u32::MAX;
+                unsafe { CloseHandle(thread) };
+            }
+        }
+        found = unsafe { Thread32Next(snapshot, &mut entry) } != 0;
+    }
+    unsafe { CloseHandle(snapshot) };
+
+    if !resumed {
+        return Err(io::Error::other("The main thread of the process could not be resumed"));
+    }
+    Ok(())
+}
@@ -0,0 +1,194 @@
+//! Encoding one input into several renditions, e.g., for adaptive streaming.
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::ExitStatus;
+
+use crate::batch::JobTemplate;
+use crate::error::Error;
+use crate::handle::JobHandle;
+use crate::job::{JobBuilder, RateControl};
+
+/// A rendition of a `Ladder`, with its own resolution and quality or bitrate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rung {
+    /// The output width.
+    pub width: u32,
+    /// The output height.
+    pub height: u32,
+    /// The quality or bitrate of the rendition.
+    pub rate: RateControl,
+}
+
+impl Rung {
+    /// Creates a rung encoded with a constant quality.
+    pub fn quality(width: u32, height: u32, quality: f32) -> Self {
+        Rung {
+            width,
+            height,
+            rate: RateControl::ConstantQuality(quality),
+        }
+    }
+
+    /// Creates a rung encoded to an average bitrate in kbit/s.
+    pub fn bitrate(width: u32, height: u32, kbps: u32) -> Self {
+        Rung {
+            width,
+            height,
+            rate: RateControl::Bitrate {
+                kbps,
+                two_pass: false,
+                turbo: false,
+            },
+        }
+    }
+
+    /// The suffix of the output file name, e.g., `-720p-3000k` or `-1080p-q22`.
+    fn suffix(&self) -> String {
+        match self.rate {
+            RateControl::ConstantQuality(quality) => format!("-{}p-q{}", self.height, quality),
+            RateControl::Bitrate { kbps, .. } => format!("-{}p-{}k", self.height, kbps),
+        }
+    }
+}
+
+/// A builder for the renditions of one input, one job per `Rung`.
+///
+/// The output of each job is named after the input and its rung, e.g., `movie.mkv` is encoded to
+/// `<output_dir>/movie-720p-3000k.mp4`. Jobs are always returned in the order of the rungs.
+///
+/// Every job scans the source on its own, as `HandBrakeCLI` can't share a scan between
+/// processes.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use handbrake::{HandBrake, Rung};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let hb = HandBrake::new().await?;
+/// let results = hb
+///     .ladder("movie.mkv")
+///     .rung(Rung::bitrate(1920, 1080, 6000))
+///     .rung(Rung::bitrate(1280, 720, 3000))
+///     .rung(Rung::bitrate(640, 360, 800))
+///     .video_codec("x264")
+///     .output_dir("renditions")
+///     .status()
+///     .await;
+/// # Ok(())
+/// # }
+/// ```
+pub struct Ladder {
+    input: PathBuf,
+    rungs: Vec<Rung>,
+    template: JobTemplate,
+}
+
+impl Ladder {
+    /// Creates a new `Ladder` instance without rungs.
+    ///
+    /// This is typically called via `HandBrake::ladder()`.
+    pub fn new(handbrake_path: PathBuf, input: impl Into<PathBuf>) -> Self {
+        Ladder {
+            input: input.into(),
+            rungs: Vec::new(),
+            template: JobTemplate::new(handbrake_path),
+        }
+    }
+
+    /// Adds a rendition.
+    pub fn rung(mut self, rung: Rung) -> Self {
+        self.rungs.push(rung);
+        self
+    }
+
+    /// Adds several renditions.
+    pub fn rungs(mut self, rungs: impl IntoIterator<Item = Rung>) -> Self {
+        self.rungs.extend(rungs);
+        self
+    }
+
+    /// Sets the directory the outputs are written to.
+    ///
+    /// Defaults to the directory of the input.
+    pub fn output_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.template.output_dir(dir.into());
+        self
+    }
+
+    /// Sets the file extension of the outputs.
+    ///
+    /// Defaults to the container set with `format()`, or `"mp4"`.
+    pub fn extension(mut self, extension: impl Into<String>) -> Self {
+        self.template.extension(extension.into());
+        self
+    }
+
+    /// Applies arbitrary `JobBuilder` options to every rendition.
+    ///
+    /// The closure is called once per rung, before the rung's resolution and rate are applied.
+    /// Options are applied in the order they were set on the ladder.
+    pub fn configure(
+        mut self,
+        f: impl Fn(JobBuilder) -> JobBuilder + Send + Sync + 'static,
+    ) -> Self {
+        self.template.configure(f);
+        self
+    }
+
+    /// Sets the preset for every rendition. See `JobBuilder::preset()`.
+    pub fn preset(self, preset: impl Into<String>) -> Self {
+        let preset = preset.into();
+        self.configure(move |job| job.preset(preset.clone()))
+    }
+
+    /// Sets the video codec for every rendition. See `JobBuilder::video_codec()`.
+    pub fn video_codec(self, codec: impl Into<String>) -> Self {
+        let codec = codec.into();
+        self.configure(move |job| job.video_codec(codec.clone()))
+    }
+
+    /// Sets the output container format for every rendition. See `JobBuilder::format()`.
+    pub fn format(mut self, format: impl Into<String>) -> Self {
+        self.template.format(format.into());
+        self
+    }
+
+    /// Builds one `JobBuilder` per rung, in the order of the rungs.
+    pub fn jobs(self) -> Vec<JobBuilder> {
+        self.rungs
+            .iter()
+            .map(|rung| {
+                // Named after the input and the rung.
+                let mut name = OsString::from(self.input.file_stem().unwrap_or_default());
+                name.push(rung.suffix());
+                let output = self.template.output_path(&self.input, &name);
+                self.template
+                    .job(&self.input, output)
+                    .width(rung.width)
+                    .height(rung.height)
+                    .rate_control(rung.rate)
+            })
+            .collect()
+    }
+
+    /// Starts all renditions at once in monitored mode, returning a `JobHandle` per rung.
+    ///
+    /// Every rendition runs its own `HandBrakeCLI` process concurrently. Use `status()` to
+    /// encode them one after another.
+    pub fn start(self) -> Vec<Result<JobHandle, Error>> {
+        self.jobs().into_iter().map(JobBuilder::start).collect()
+    }
+
+    /// Executes the renditions one after another, returning the final `ExitStatus` per rung.
+    ///
+    /// A job that fails to spawn doesn't stop the remaining jobs.
+    pub async fn status(self) -> Vec<Result<ExitStatus, Error>> {
+        let mut results = Vec::with_capacity(self.rungs.len());
+        for job in self.jobs() {
+            results.push(job.status().await);
+        }
+        results
+    }
+}
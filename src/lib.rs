@@ -56,17 +56,53 @@
 //! ```
 
 use std::env;
+use std::ops::RangeInclusive;
 use std::path::PathBuf;
+use std::time::Duration;
 #[cfg(not(test))]
 use tokio::process::Command;
 
+mod affinity;
+mod batch;
+pub mod blocking;
+mod chain;
+mod chapter_split;
+mod cli_args;
+mod codec;
+mod compat;
+mod diagnostic;
+#[cfg(feature = "compare")]
+mod compare;
+#[cfg(all(feature = "cgroup", target_os = "linux"))]
+mod cgroup;
+mod disk_space;
 mod error;
+mod estimate;
 mod event;
+mod gui_queue;
 mod handle;
+mod telemetry;
+#[cfg(windows)]
+mod job_object;
+mod ladder;
 pub mod job;
+pub mod notify;
+mod output_size;
+#[cfg(feature = "segmented")]
+mod segmented;
+mod profile;
+#[cfg(feature = "indicatif")]
+pub mod progress_bar;
+mod progress_line;
+#[cfg(feature = "remote")]
+pub mod remote;
+mod scan_listing;
+mod sniff;
+#[cfg(feature = "status-server")]
+mod status_server;
 
-#[cfg(test)]
-mod testing;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
 
 #[cfg(test)]
 use testing::mock_command::MockCommand as Command;
@@ -135,13 +171,33 @@ fn find_executable_in_path(path_env: &std::ffi::OsStr) -> Result<PathBuf, Error>
     })
 }
 
+pub use batch::BatchBuilder;
+pub use chain::{ChainFailure, JobChain};
+pub use chapter_split::ChapterSplit;
+pub use compat::Support;
+#[cfg(feature = "compare")]
+pub use compare::{Metric, QualityComparison, QualityScore};
 pub use error::Error;
 pub use event::{
-    AudioConfig, AudioTrackConfig, Config, DestinationConfig, JobEvent, JobFailure, Log, Progress,
-    SourceConfig, VideoConfig,
+    AudioConfig, AudioTrackConfig, ChapterConfig, Config, DestinationConfig, Diagnostic,
+    DiagnosticKind, FailureKind, FilterConfig, FiltersConfig, JobEvent, JobFailure, JobSummary,
+    Log, Phase, PixelAspectRatio, Progress, ScanSummary, ScannedTitle, ScannedTrack, SourceConfig,
+    SubtitleConfig, SubtitleSearchConfig, SubtitleTrackConfig, Timestamped, VideoConfig,
+};
+#[cfg(all(feature = "cgroup", target_os = "linux"))]
+pub use cgroup::CgroupLimits;
+pub use handle::{EventStream, JobController, JobHandle, JobState, TimestampedEventStream};
+use handle::JobRegistry;
+pub use job::{
+    InputReader, InputSource, JobBuilder, JobSpec, LogLevel, OutputDestination, OutputWriter,
+    RateControl,
 };
-pub use handle::JobHandle;
-pub use job::{InputSource, JobBuilder, OutputDestination};
+pub use ladder::{Ladder, Rung};
+pub use profile::Profile;
+#[cfg(feature = "segmented")]
+pub use segmented::{Segment, SegmentedEncode};
+#[cfg(feature = "status-server")]
+pub use status_server::StatusServer;
 
 /// The main entry point for the `handbrake-rs` crate.
 ///
@@ -151,6 +207,8 @@ pub use job::{InputSource, JobBuilder, OutputDestination};
 pub struct HandBrake {
     executable_path: PathBuf,
     version: String,
+    /// The controllers of the monitored jobs created by this instance, for `shutdown()`.
+    jobs: JobRegistry,
 }
 
 impl HandBrake {
@@ -168,6 +226,7 @@ impl HandBrake {
         Ok(Self {
             executable_path,
             version,
+            jobs: JobRegistry::default(),
         })
     }
 
@@ -183,6 +242,7 @@ impl HandBrake {
         Ok(Self {
             executable_path,
             version,
+            jobs: JobRegistry::default(),
         })
     }
 
@@ -193,6 +253,14 @@ impl HandBrake {
         &self.version
     }
 
+    /// Returns how well the version of `HandBrakeCLI` is supported.
+    ///
+    /// Releases from 1.5 to 1.9 and nightly builds are supported. Jobs of other versions still
+    /// run, but their events may be incomplete, and a warning is logged at their start.
+    pub fn support(&self) -> Support {
+        Support::of(&self.version)
+    }
+
     /// Creates a new `JobBuilder` to configure an encoding job.
     ///
     /// # Arguments
@@ -200,7 +268,105 @@ impl HandBrake {
     /// * `input` - The source for the encoding job (e.g., a file path or stdin).
     /// * `output` - The destination for the encoded file (e.g., a file path or stdout).
     pub fn job(&self, input: InputSource, output: OutputDestination) -> JobBuilder {
-        JobBuilder::new(self.executable_path.clone(), input, output)
+        self.own(JobBuilder::new(self.executable_path.clone(), input, output))
+    }
+
+    /// Creates a new `JobBuilder` from a serializable `JobSpec`.
+    ///
+    /// # Arguments
+    ///
+    /// * `spec` - The job configuration, e.g., loaded from a configuration file.
+    pub fn job_from_spec(&self, spec: JobSpec) -> JobBuilder {
+        self.own(JobBuilder::from_spec(self.executable_path.clone(), spec))
+    }
+
+    /// Creates a new `JobBuilder` from a `HandBrakeCLI` argument list.
+    ///
+    /// See `JobBuilder::from_args()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the arguments lack an input or output, or if an argument kept as
+    /// `extra_args()` is not valid UTF-8.
+    pub fn job_from_args(&self, args: &[impl AsRef<std::ffi::OsStr>]) -> Result<JobBuilder, Error> {
+        JobBuilder::from_args(self.executable_path.clone(), args).map(|job| self.own(job))
+    }
+
+    /// Creates a `JobBuilder` for every entry of a queue file exported by the HandBrake GUI.
+    ///
+    /// See `JobBuilder::from_gui_queue()`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the queue is not valid JSON in the GUI queue format.
+    pub fn import_gui_queue(&self, json: &str) -> Result<Vec<JobBuilder>, Error> {
+        JobBuilder::from_gui_queue(self.executable_path.clone(), json)
+            .map(|jobs| jobs.into_iter().map(|job| self.own(job)).collect())
+    }
+
+    /// Cancels the running jobs created by this instance, and kills the ones still running after
+    /// `grace`.
+    ///
+    /// This is meant for a Ctrl-C handler, so that no `HandBrakeCLI` process outlives the
+    /// application. Only monitored jobs created by this instance are tracked, including those of
+    /// `batch()`, `ladder()`, and `split_chapters()`, and the segments and samples run by the
+    /// `segmented()` and `compare_qualities()` of its jobs.
+    pub async fn shutdown(&self, grace: Duration) {
+        let jobs = std::mem::take(&mut *self.jobs.lock().unwrap());
+        let running: Vec<JobController> = jobs
+            .into_iter()
+            .filter(|job| !job.state().is_finished())
+            .collect();
+        for job in &running {
+            let _ = job.cancel().await;
+        }
+        let exited = futures::future::join_all(running.iter().map(JobController::exited));
+        if tokio::time::timeout(grace, exited).await.is_err() {
+            for job in running.iter().filter(|job| !job.state().is_finished()) {
+                let _ = job.kill().await;
+            }
+        }
+    }
+
+    /// Records the version and the job registry of this instance in a job.
+    fn own(&self, job: JobBuilder) -> JobBuilder {
+        self.owner()(job)
+    }
+
+    /// Returns `own()` as a closure, for the builders that create their jobs later.
+    fn owner(&self) -> impl Fn(JobBuilder) -> JobBuilder + Send + Sync + 'static {
+        let version = self.version.clone();
+        let jobs = std::sync::Arc::clone(&self.jobs);
+        move |job| job.with_version(&version).tracked_by(&jobs)
+    }
+
+    /// Creates a new `BatchBuilder` to encode many input files with one configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - The input files, each encoded by its own job.
+    pub fn batch(&self, inputs: impl IntoIterator<Item = impl Into<PathBuf>>) -> BatchBuilder {
+        // The first options applied to every job, so they are owned before being configured.
+        BatchBuilder::new(self.executable_path.clone(), inputs).configure(self.owner())
+    }
+
+    /// Creates a new `Ladder` to encode one input into several renditions.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input file, encoded once per rung.
+    pub fn ladder(&self, input: impl Into<PathBuf>) -> Ladder {
+        Ladder::new(self.executable_path.clone(), input).configure(self.owner())
+    }
+
+    /// Creates a new `ChapterSplit` to encode every chapter of an input into its own file.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - The input file or disc.
+    /// * `chapters` - The chapters to encode, e.g., `1..=12`.
+    pub fn split_chapters(&self, input: impl Into<PathBuf>, chapters: RangeInclusive<u32>) -> ChapterSplit {
+        ChapterSplit::new(self.executable_path.clone(), input, chapters).configure(self.owner())
     }
 }
 
@@ -0,0 +1,173 @@
+//! Notifications about the lifecycle of monitored jobs, e.g., for automation webhooks.
+//!
+//! A `Notifier` set with `JobBuilder::notifier()` is called when the job starts, whenever its
+//! progress passes another multiple of 10 percent, and when it completes. Notifications are
+//! delivered in order from a separate task, so a slow notifier never delays the job's events.
+
+use std::path::PathBuf;
+use std::process::ExitStatus;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::Serialize;
+use tokio::sync::mpsc;
+
+use crate::event::JobFailure;
+use crate::job::{InputSource, OutputDestination};
+
+/// The progress interval, in percent, at which `NotificationKind::Progress` is sent.
+const PROGRESS_MILESTONE_STEP: u32 = 10;
+
+/// A notification about a job, serialized as a flat JSON object with an `event` field.
+///
+/// e.g., `{"input":"movie.mkv","output":"movie.mp4","event":"progress","percentage":50}`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Notification {
+    /// The input file of the job, or `None` if it reads from `stdin`.
+    pub input: Option<PathBuf>,
+    /// The output file of the job, or `None` if it writes to `stdout`.
+    pub output: Option<PathBuf>,
+    /// What happened.
+    #[serde(flatten)]
+    pub kind: NotificationKind,
+}
+
+/// The lifecycle event a `Notification` reports.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// The `HandBrakeCLI` process was spawned.
+    Started,
+    /// The job reached a progress milestone, a multiple of 10 percent.
+    Progress {
+        /// The milestone that was reached.
+        percentage: u32,
+    },
+    /// The job completed, successfully or not.
+    Completed {
+        /// Whether the job completed successfully.
+        success: bool,
+        /// The exit code of the `HandBrakeCLI` process, if available.
+        exit_code: Option<i32>,
+        /// A message describing the failure, if the job failed.
+        message: Option<String>,
+    },
+}
+
+/// Receives notifications about the lifecycle of a job.
+///
+/// Implementations are responsible for handling their own errors, which don't affect the job.
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Handles a single notification.
+    async fn notify(&self, notification: &Notification);
+}
+
+/// A `Notifier` that POSTs every notification as JSON to an HTTP endpoint.
+///
+/// Failed requests are ignored. Requires the `webhook` feature.
+#[cfg(feature = "webhook")]
+#[derive(Debug, Clone)]
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+    url: String,
+}
+
+#[cfg(feature = "webhook")]
+impl WebhookNotifier {
+    /// Creates a notifier posting to the given URL.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::with_client(reqwest::Client::new(), url)
+    }
+
+    /// Creates a notifier posting to the given URL with a preconfigured client, e.g., one with
+    /// authentication headers or a request timeout.
+    pub fn with_client(client: reqwest::Client, url: impl Into<String>) -> Self {
+        WebhookNotifier {
+            client,
+            url: url.into(),
+        }
+    }
+}
+
+#[cfg(feature = "webhook")]
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn notify(&self, notification: &Notification) {
+        let _ = self.client.post(&self.url).json(notification).send().await;
+    }
+}
+
+/// Sends the notifications of a single job to a task that delivers them in order.
+pub(crate) struct Notifications {
+    tx: mpsc::UnboundedSender<Notification>,
+    input: Option<PathBuf>,
+    output: Option<PathBuf>,
+    last_milestone: u32,
+}
+
+impl Notifications {
+    /// Spawns the delivery task for a job with the given input and output.
+    pub(crate) fn spawn(
+        notifier: Arc<dyn Notifier>,
+        input: &InputSource,
+        output: &OutputDestination,
+    ) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel::<Notification>();
+        tokio::spawn(async move {
+            while let Some(notification) = rx.recv().await {
+                notifier.notify(&notification).await;
+            }
+        });
+        Notifications {
+            tx,
+            input: match input {
+                InputSource::File(path) | InputSource::Device(path) => Some(path.clone()),
+                InputSource::Stdin | InputSource::Reader(_) => None,
+            },
+            output: match output {
+                OutputDestination::File(path) => Some(path.clone()),
+                OutputDestination::Stdout | OutputDestination::Writer(_) => None,
+            },
+            last_milestone: 0,
+        }
+    }
+
+    fn send(&self, kind: NotificationKind) {
+        let _ = self.tx.send(Notification {
+            input: self.input.clone(),
+            output: self.output.clone(),
+            kind,
+        });
+    }
+
+    pub(crate) fn started(&self) {
+        self.send(NotificationKind::Started);
+    }
+
+    /// Sends a milestone notification if the percentage passed a new milestone.
+    pub(crate) fn progress(&mut self, percentage: f32) {
+        let milestone = (percentage as u32 / PROGRESS_MILESTONE_STEP) * PROGRESS_MILESTONE_STEP;
+        if milestone > self.last_milestone {
+            self.last_milestone = milestone;
+            self.send(NotificationKind::Progress {
+                percentage: milestone,
+            });
+        }
+    }
+
+    pub(crate) fn completed(&self, result: &Result<ExitStatus, JobFailure>) {
+        self.send(match result {
+            Ok(status) => NotificationKind::Completed {
+                success: status.success(),
+                exit_code: status.code(),
+                message: None,
+            },
+            Err(failure) => NotificationKind::Completed {
+                success: false,
+                exit_code: failure.exit_code,
+                message: Some(failure.message.clone()),
+            },
+        });
+    }
+}
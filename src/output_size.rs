@@ -0,0 +1,71 @@
+//! Sampling the size of the output file while it is written, see
+//! `JobBuilder::output_size_interval()`.
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use tokio::time::{Instant, Interval, MissedTickBehavior};
+
+/// Samples the size of the output file of a running job.
+pub(crate) struct OutputSizeWatch {
+    path: PathBuf,
+    interval: Interval,
+    /// The previous size, and when it was sampled.
+    last: (u64, Instant),
+}
+
+impl OutputSizeWatch {
+    /// Must be called within a Tokio runtime.
+    pub(crate) fn new(path: PathBuf, every: Duration) -> Self {
+        let start = Instant::now();
+        // The first tick completes immediately, when there is no output yet.
+        let mut interval = tokio::time::interval_at(start + every, every);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        OutputSizeWatch {
+            path,
+            interval,
+            last: (0, start),
+        }
+    }
+
+    /// Waits for the next sample, returning the size in bytes and its growth in bytes per second
+    /// since the previous sample.
+    ///
+    /// Samples are skipped until `HandBrakeCLI` creates the output.
+    pub(crate) async fn next(&mut self) -> (u64, f64) {
+        loop {
+            let now = self.interval.tick().await;
+            let Ok(metadata) = tokio::fs::metadata(&self.path).await else {
+                continue;
+            };
+            return self.sampled(metadata.len(), now);
+        }
+    }
+
+    fn sampled(&mut self, bytes: u64, at: Instant) -> (u64, f64) {
+        let (last_bytes, last_at) = std::mem::replace(&mut self.last, (bytes, at));
+        let elapsed = at.duration_since(last_at).as_secs_f64();
+        let bytes_per_sec = if elapsed > 0.0 {
+            // A shrinking output, e.g., when it is rewritten, grows at a negative rate.
+            (bytes as f64 - last_bytes as f64) / elapsed
+        } else {
+            0.0
+        };
+        (bytes, bytes_per_sec)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_growth_rate() {
+        let mut watch = OutputSizeWatch::new(PathBuf::from("out.mkv"), Duration::from_secs(1));
+        let start = watch.last.1;
+
+        assert_eq!(watch.sampled(1000, start + Duration::from_secs(2)), (1000, 500.0));
+        assert_eq!(watch.sampled(4000, start + Duration::from_secs(3)), (4000, 3000.0));
+        assert_eq!(watch.sampled(3000, start + Duration::from_secs(4)), (3000, -1000.0));
+    }
+}
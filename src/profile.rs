@@ -0,0 +1,87 @@
+//! Named bundles of job settings, layered over HandBrake presets.
+
+use serde::{Deserialize, Serialize};
+
+use crate::job::JobBuilder;
+
+/// A named, serializable bundle of job settings, e.g., to keep the encode settings of a team
+/// under version control separately from HandBrake's preset files.
+///
+/// The settings are stored as `HandBrakeCLI` arguments, so a profile file reads like the
+/// command line it stands for, and are applied to a job with `JobBuilder::profile()`:
+///
+/// - The `preset` of the profile, if any, replaces the preset of the job. `HandBrakeCLI`
+///   applies the preset first, and every other option overrides the value of the preset.
+/// - The `args` of the profile override the options set on the job before `profile()`, while
+///   options set after it override the profile. Options the profile doesn't set are kept.
+/// - Arguments without a `JobBuilder` equivalent are kept as `extra_args()`, as with
+///   `JobBuilder::from_args()`.
+///
+/// # Example
+///
+/// ```rust
+/// # use std::path::PathBuf;
+/// # use handbrake::{JobBuilder, Profile};
+/// let profile: Profile = serde_json::from_str(r#"{
+///     "name": "archive",
+///     "preset": "HQ 1080p30 Surround",
+///     "args": ["--encoder", "x265", "--quality", "20"]
+/// }"#).unwrap();
+///
+/// let job = JobBuilder::new(PathBuf::from("HandBrakeCLI"), "input.mkv".into(), "output.mkv".into())
+///     .profile(&profile)
+///     .quality(18.0);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    /// The name of the profile.
+    pub name: String,
+    /// The HandBrake preset the settings are applied on top of.
+    #[serde(default)]
+    pub preset: Option<String>,
+    /// The settings, as `HandBrakeCLI` arguments without the input and output.
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+impl Profile {
+    /// Creates a profile without settings.
+    pub fn new(name: impl Into<String>) -> Self {
+        Profile {
+            name: name.into(),
+            preset: None,
+            args: Vec::new(),
+        }
+    }
+
+    /// Creates a profile with the settings of a job, except its input and output.
+    ///
+    /// Only options passed to `HandBrakeCLI` are kept, not execution options such as
+    /// `JobBuilder::timeout()`.
+    pub fn from_job(name: impl Into<String>, job: &JobBuilder) -> Self {
+        let mut profile = Profile::new(name);
+        let mut args = job.build_args().into_iter();
+        while let Some(arg) = args.next() {
+            match arg.to_str() {
+                Some("-i" | "-o") => {
+                    args.next();
+                }
+                Some("--preset") => profile.preset = args.next().map(|p| p.to_string_lossy().into_owned()),
+                _ => profile.args.push(arg.to_string_lossy().into_owned()),
+            }
+        }
+        profile
+    }
+
+    /// Sets the HandBrake preset the settings are applied on top of.
+    pub fn preset(mut self, preset: impl Into<String>) -> Self {
+        self.preset = Some(preset.into());
+        self
+    }
+
+    /// Appends `HandBrakeCLI` arguments to the settings.
+    pub fn args(mut self, args: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+}
@@ -0,0 +1,84 @@
+//! Driving an `indicatif` progress bar from a monitored job.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! # use handbrake::{HandBrake, InputSource, OutputDestination};
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let hb = HandBrake::new().await?;
+//! let job_handle = hb
+//!     .job(InputSource::from("input.mkv"), OutputDestination::from("output.mp4"))
+//!     .preset("Fast 1080p30")
+//!     .start()?;
+//! let bar = handbrake::progress_bar::attach(&job_handle);
+//! let summary = job_handle.wait().await;
+//! bar.finish_with_message(if summary.is_ok() { "done" } else { "failed" });
+//! # Ok(())
+//! # }
+//! ```
+
+use indicatif::{ProgressBar, ProgressStyle};
+use tokio::sync::watch;
+
+use crate::event::{Phase, Progress};
+use crate::handle::JobHandle;
+
+/// The number of progress bar steps per percent, so fractional percentages still move the bar.
+const STEPS_PER_PERCENT: u64 = 100;
+
+/// Returns the style used by `attach()`, showing the percentage, speed, and `HandBrakeCLI`'s ETA.
+pub fn style() -> ProgressStyle {
+    ProgressStyle::with_template("[{elapsed_precise}] [{wide_bar}] {percent:>3}% {msg}")
+        .expect("BUG: Invalid progress bar template")
+        .progress_chars("=> ")
+}
+
+/// Creates a progress bar for the job, updated from a background task until the job exits.
+///
+/// Must be called from within a `tokio` runtime.
+pub fn attach(handle: &JobHandle) -> ProgressBar {
+    let bar = ProgressBar::new(100 * STEPS_PER_PERCENT).with_style(style());
+    tokio::spawn(drive(handle.progress_watch(), bar.clone()));
+    bar
+}
+
+/// Updates the progress bar with every `Progress` of the job until it exits.
+///
+/// The bar's message shows the current and average FPS and the ETA. The bar is expected to have
+/// a length of `10000`, as created by `attach()`; it is not finished when the job exits, so the
+/// caller can report the outcome.
+pub async fn drive(mut progress: watch::Receiver<Option<Progress>>, bar: ProgressBar) {
+    loop {
+        if let Some(p) = progress.borrow_and_update().as_ref() {
+            bar.set_position((p.percentage * STEPS_PER_PERCENT as f32) as u64);
+            bar.set_message(message(p));
+        }
+        if progress.changed().await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Formats the speed and ETA of a progress update.
+fn message(progress: &Progress) -> String {
+    match progress.phase {
+        Phase::Encoding => {}
+        Phase::SubtitleScan => return "scanning subtitles".to_string(),
+        Phase::Muxing => return "muxing".to_string(),
+    }
+    let mut message = format!("{:.2} fps", progress.fps);
+    if let Some(avg_fps) = progress.avg_fps {
+        message.push_str(&format!(" (avg {:.2})", avg_fps));
+    }
+    if let Some(eta) = progress.eta {
+        let secs = eta.as_secs();
+        message.push_str(&format!(
+            ", ETA {:02}:{:02}:{:02}",
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        ));
+    }
+    message
+}
@@ -0,0 +1,209 @@
+//! Matching of the progress lines and muxing statuses `HandBrakeCLI` writes to `stdout`.
+//!
+//! When encoding to `stdout`, these lines are interleaved with the video data, so every chunk of
+//! it is searched. A substring search for the fixed prefix skips data without progress, and
+//! only the lines following a prefix are parsed.
+
+use std::ops::Range;
+use std::time::Duration;
+
+use memchr::memmem::Finder;
+use once_cell::sync::Lazy;
+
+static ENCODING: Lazy<Finder<'static>> = Lazy::new(|| Finder::new(b"Encoding: task "));
+
+static MUXING: Lazy<Finder<'static>> = Lazy::new(|| Finder::new(b"Muxing:"));
+
+/// A progress line, e.g., `Encoding: task 1 of 1, 12.34 % (120.00 fps, avg 110.00 fps, ETA 00h01m30s)`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ProgressLine {
+    /// Where the line is in the searched data, including its line ending.
+    pub(crate) range: Range<usize>,
+    pub(crate) task: u32,
+    pub(crate) task_count: u32,
+    pub(crate) percentage: f32,
+    /// The current and average frame rate, and the estimated remaining time, which are
+    /// omitted while HandBrake is still measuring them.
+    pub(crate) rate: Option<(f32, f32, Duration)>,
+}
+
+/// Finds the first progress line in `data`.
+pub(crate) fn find_progress_line(data: &[u8]) -> Option<ProgressLine> {
+    let mut start = 0;
+    while let Some(offset) = ENCODING.find(&data[start..]) {
+        let at = start + offset;
+        if let Some(line) = parse_progress_line(data, at) {
+            return Some(line);
+        }
+        start = at + 1;
+    }
+    None
+}
+
+/// Finds the first muxing status in `data`, e.g., `Muxing: this may take awhile...`.
+///
+/// The status extends up to and including the next line ending.
+pub(crate) fn find_muxing_line(data: &[u8]) -> Option<Range<usize>> {
+    let at = MUXING.find(data)?;
+    let mut cursor = Cursor { data, pos: at };
+    cursor.pos += data[at..]
+        .iter()
+        .position(|&b| b == b'\r' || b == b'\n')
+        .unwrap_or(data.len() - at);
+    cursor.line_ending();
+    Some(at..cursor.pos)
+}
+
+/// Parses the progress line starting at `at`, which is known to start with the prefix.
+fn parse_progress_line(data: &[u8], at: usize) -> Option<ProgressLine> {
+    let mut cursor = Cursor { data, pos: at };
+    cursor.tag(b"Encoding: task ")?;
+    let task = cursor.integer()?;
+    cursor.tag(b" of ")?;
+    let task_count = cursor.integer()?;
+    cursor.tag(b", ")?;
+    let percentage = cursor.decimal(1, 2)?;
+    cursor.tag(b" %")?;
+
+    // An incomplete rate leaves it out, like a line without one.
+    let before_rate = cursor.pos;
+    let rate = cursor.rate();
+    if rate.is_none() {
+        cursor.pos = before_rate;
+    }
+    cursor.line_ending();
+
+    Some(ProgressLine {
+        range: at..cursor.pos,
+        task,
+        task_count,
+        percentage,
+        rate,
+    })
+}
+
+/// A position in the data being parsed, only advanced past matching input.
+struct Cursor<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn tag(&mut self, tag: &[u8]) -> Option<()> {
+        if self.data[self.pos..].starts_with(tag) {
+            self.pos += tag.len();
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    /// Consumes between `min` and `max` ASCII digits.
+    fn digits(&mut self, min: usize, max: usize) -> Option<&'a [u8]> {
+        let rest = &self.data[self.pos..];
+        let len = rest.iter().take(max).take_while(|b| b.is_ascii_digit()).count();
+        if len < min {
+            return None;
+        }
+        self.pos += len;
+        Some(&rest[..len])
+    }
+
+    fn integer<T: std::str::FromStr>(&mut self) -> Option<T> {
+        parse(self.digits(1, usize::MAX)?)
+    }
+
+    /// Consumes a number with `min` to `max` integer digits and two decimal places.
+    fn decimal(&mut self, min: usize, max: usize) -> Option<f32> {
+        let start = self.pos;
+        self.digits(min, max)?;
+        self.tag(b".")?;
+        self.digits(2, 2)?;
+        parse(&self.data[start..self.pos])
+    }
+
+    /// Consumes ` (120.00 fps, avg 110.00 fps, ETA 00h01m30s)`.
+    fn rate(&mut self) -> Option<(f32, f32, Duration)> {
+        self.tag(b" (")?;
+        let fps = self.decimal(1, usize::MAX)?;
+        self.tag(b" fps, avg ")?;
+        let avg_fps = self.decimal(1, usize::MAX)?;
+        self.tag(b" fps, ETA ")?;
+        let hours: u64 = parse(self.digits(2, 2)?)?;
+        self.tag(b"h")?;
+        let minutes: u64 = parse(self.digits(2, 2)?)?;
+        self.tag(b"m")?;
+        let seconds: u64 = parse(self.digits(2, 2)?)?;
+        self.tag(b"s)")?;
+        Some((fps, avg_fps, Duration::from_secs(hours * 3600 + minutes * 60 + seconds)))
+    }
+
+    /// Consumes an optional `\r\n`, `\r` or `\n`.
+    fn line_ending(&mut self) {
+        if self.tag(b"\r\n").is_none() && self.tag(b"\r").is_none() {
+            let _ = self.tag(b"\n");
+        }
+    }
+}
+
+fn parse<T: std::str::FromStr>(digits: &[u8]) -> Option<T> {
+    std::str::from_utf8(digits).ok()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_line_full_match() {
+        let line = "Encoding: task 1 of 1, 12.34 % (120.00 fps, avg 110.00 fps, ETA 00h01m30s)";
+        let progress = find_progress_line(line.as_bytes()).unwrap();
+
+        assert_eq!(progress.percentage, 12.34);
+        assert_eq!(progress.rate, Some((120.0, 110.0, Duration::from_secs(90))));
+        assert_eq!(progress.range, 0..line.len());
+    }
+
+    #[test]
+    fn test_progress_line_pct_only() {
+        let line = "Encoding: task 1 of 1, 56.78 %";
+        let progress = find_progress_line(line.as_bytes()).unwrap();
+
+        assert_eq!(progress.percentage, 56.78);
+        assert!(progress.rate.is_none());
+    }
+
+    #[test]
+    fn test_progress_line_no_match() {
+        let line = "Some other output that does not match";
+        assert!(find_progress_line(line.as_bytes()).is_none());
+    }
+
+    #[test]
+    fn test_progress_line_another_full_match() {
+        let line = "Encoding: task 2 of 5, 99.99 % (30.00 fps, avg 25.50 fps, ETA 01h23m45s)";
+        let progress = find_progress_line(line.as_bytes()).unwrap();
+
+        assert_eq!((progress.task, progress.task_count), (2, 5));
+        assert_eq!(progress.percentage, 99.99);
+        assert_eq!(progress.rate, Some((30.0, 25.5, Duration::from_secs(5025))));
+    }
+
+    #[test]
+    fn test_progress_line_within_data() {
+        let data = b"\x00Encoding: task 1\xffEncoding: task 1 of 2, 5.00 % (1.00 fps\r\nrest";
+        let progress = find_progress_line(data).unwrap();
+
+        // The line ends before the incomplete rate.
+        assert_eq!(progress.range, 18..47);
+        assert_eq!(progress.percentage, 5.0);
+        assert!(progress.rate.is_none());
+    }
+
+    #[test]
+    fn test_muxing_line() {
+        assert_eq!(find_muxing_line(b"abcMuxing: this may take awhile...\r\ndef"), Some(3..36));
+        assert_eq!(find_muxing_line(b"Muxing: this may take awhile..."), Some(0..31));
+        assert_eq!(find_muxing_line(b"Encoding"), None);
+    }
+}
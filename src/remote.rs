@@ -0,0 +1,336 @@
+//! Running jobs on other machines, on workers that receive `JobSpec`s over TCP.
+//!
+//! The protocol is length-delimited JSON: the coordinator sends one `JobSpec` per connection,
+//! and the worker answers with the `Timestamped` `JobEvent`s of the job, up to `JobEvent::Done`.
+//! Closing the connection early cancels the job on the worker.
+//!
+//! Paths in the spec are resolved on the worker, so inputs and outputs are typically on shared
+//! storage.
+//!
+//! # Security
+//!
+//! The protocol is unauthenticated and unencrypted, and is meant for trusted networks only. Any
+//! peer that can connect to a `Worker` runs `HandBrakeCLI` as the worker's user, with the
+//! `extra_args()` and output paths of its choice, which lets it overwrite any file the worker
+//! can write. Bind workers to a loopback or private interface, and use `Worker::check_spec()`
+//! to reject the specs the worker must not run.
+
+use std::pin::Pin;
+use std::process::ExitStatus;
+use std::fmt;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant, SystemTime};
+
+use futures::{SinkExt, Stream, StreamExt};
+use serde::Deserialize;
+use tokio::net::{TcpListener, TcpStream, ToSocketAddrs};
+use tokio::select;
+use tokio::sync::mpsc;
+use tokio_util::codec::{Framed, LengthDelimitedCodec};
+
+use crate::HandBrake;
+use crate::error::Error;
+use crate::event::{
+    Config, Diagnostic, FailureKind, JobEvent, JobFailure, Log, Progress, ScanSummary, Timestamped,
+};
+use crate::job::JobSpec;
+
+/// Runs the jobs sent by coordinators with its `HandBrake` instance.
+///
+/// Requires the `remote` feature.
+///
+/// # Security
+///
+/// Any peer that can connect runs jobs on the worker, see the [module documentation](self).
+/// Only listen on trusted networks.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use handbrake::{HandBrake, remote::Worker};
+/// # use tokio::net::TcpListener;
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let worker = Worker::new(HandBrake::new().await?)
+///     .check_spec(|spec| match spec.extra_args.is_empty() {
+///         true => Ok(()),
+///         false => Err("extra_args are not allowed".to_string()),
+///     });
+/// worker.serve(TcpListener::bind("127.0.0.1:7800").await?).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Worker {
+    handbrake: Arc<HandBrake>,
+    check_spec: Option<Arc<CheckSpec>>,
+}
+
+type CheckSpec = dyn Fn(&JobSpec) -> Result<(), String> + Send + Sync;
+
+impl fmt::Debug for Worker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Worker")
+            .field("handbrake", &self.handbrake)
+            .field("check_spec", &self.check_spec.is_some())
+            .finish()
+    }
+}
+
+impl Worker {
+    /// Creates a worker running jobs with the given `HandBrakeCLI`.
+    pub fn new(handbrake: HandBrake) -> Self {
+        Worker {
+            handbrake: Arc::new(handbrake),
+            check_spec: None,
+        }
+    }
+
+    /// Checks every received `JobSpec` before running it, e.g., to only allow outputs in a
+    /// certain directory or to reject `extra_args`.
+    ///
+    /// A rejected job is not started, and its coordinator receives a `JobEvent::Done` with a
+    /// `JobFailure` of kind `FailureKind::Process` carrying the returned reason.
+    pub fn check_spec(mut self, check: impl Fn(&JobSpec) -> Result<(), String> + Send + Sync + 'static) -> Self {
+        self.check_spec = Some(Arc::new(check));
+        self
+    }
+
+    /// Accepts connections until the listener fails, running one job per connection.
+    ///
+    /// Jobs run concurrently, so coordinators are expected to limit the jobs they send.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if a connection can't be accepted. Failures of single jobs are reported
+    /// to their coordinator instead.
+    pub async fn serve(&self, listener: TcpListener) -> Result<(), Error> {
+        loop {
+            let (stream, _) = listener.accept().await.map_err(remote_error)?;
+            let worker = self.clone();
+            tokio::spawn(async move {
+                let _ = serve_connection(&worker, stream).await;
+            });
+        }
+    }
+}
+
+/// Runs the job received on a connection, sending its events back.
+async fn serve_connection(worker: &Worker, stream: TcpStream) -> Result<(), Error> {
+    let (mut sink, mut source) = Framed::new(stream, LengthDelimitedCodec::new()).split();
+    let frame = match source.next().await {
+        Some(frame) => frame.map_err(remote_error)?,
+        None => return Ok(()),
+    };
+
+    let started_at = Instant::now();
+    let started = serde_json::from_slice::<JobSpec>(&frame)
+        .map_err(remote_error)
+        .and_then(|spec| match &worker.check_spec {
+            Some(check) => check(&spec).map(|()| spec).map_err(|reason| Error::RemoteFailed {
+                reason: format!("The worker rejected the job: {}", reason),
+            }),
+            None => Ok(spec),
+        })
+        .and_then(|spec| worker.handbrake.job_from_spec(spec).start());
+    let mut handle = match started {
+        Ok(handle) => handle,
+        Err(e) => {
+            let done = JobEvent::Done(Err(JobFailure {
+                message: e.to_string(),
+                exit_code: None,
+                kind: FailureKind::Process,
+            }));
+            let event = Timestamped::now(done, started_at, &None);
+            let frame = serde_json::to_vec(&event).map_err(remote_error)?;
+            return sink.send(frame.into()).await.map_err(remote_error);
+        }
+    };
+
+    let controller = handle.controller();
+    let mut events = handle.timestamped_events();
+    loop {
+        select! {
+            event = events.next() => {
+                let Some(event) = event else {
+                    return Ok(());
+                };
+                let done = matches!(event.event, JobEvent::Done(_));
+                let frame = serde_json::to_vec(&event).map_err(remote_error)?;
+                if let Err(e) = sink.send(frame.into()).await {
+                    let _ = controller.cancel().await;
+                    return Err(remote_error(e));
+                }
+                if done {
+                    return Ok(());
+                }
+            }
+            frame = source.next() => {
+                // The coordinator only closes the connection, which cancels the job.
+                if !matches!(frame, Some(Ok(_))) {
+                    let _ = controller.cancel().await;
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// A job running on a `Worker`, yielding its `Timestamped` `JobEvent`s.
+///
+/// The stream ends after `JobEvent::Done`. A lost connection ends the job with a `JobFailure`,
+/// and dropping the stream cancels the job on the worker.
+///
+/// Requires the `remote` feature.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use futures::StreamExt;
+/// # use handbrake::{HandBrake, JobEvent, remote::RemoteJob};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let hb = HandBrake::new().await?;
+/// let spec = hb.job("/mnt/media/movie.mkv".into(), "/mnt/media/movie.mp4".into()).to_spec();
+/// let mut job = RemoteJob::start("encoder-1:7800", &spec).await?;
+/// while let Some(event) = job.next().await {
+///     if let JobEvent::Progress(progress) = event.event {
+///         println!("{:.1}%", progress.percentage);
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct RemoteJob {
+    event_rx: mpsc::Receiver<Timestamped<JobEvent>>,
+}
+
+impl RemoteJob {
+    /// Sends a job to the worker at the given address.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the worker can't be reached. A job that fails to start on the worker
+    /// is reported through `JobEvent::Done`.
+    pub async fn start(addr: impl ToSocketAddrs, spec: &JobSpec) -> Result<Self, Error> {
+        let stream = TcpStream::connect(addr).await.map_err(remote_error)?;
+        let mut framed = Framed::new(stream, LengthDelimitedCodec::new());
+        let frame = serde_json::to_vec(spec).map_err(remote_error)?;
+        framed.send(frame.into()).await.map_err(remote_error)?;
+
+        let started_at = Instant::now();
+        let (event_tx, event_rx) = mpsc::channel(128);
+        tokio::spawn(async move {
+            loop {
+                let event = match framed.next().await {
+                    Some(Ok(frame)) => match serde_json::from_slice::<WireTimestamped>(&frame) {
+                        Ok(event) => event.into_timestamped(),
+                        Err(e) => lost(format!("Invalid event from the worker: {e}"), started_at),
+                    },
+                    Some(Err(e)) => lost(format!("Lost the connection to the worker: {e}"), started_at),
+                    None => lost("The worker closed the connection".to_string(), started_at),
+                };
+                let done = matches!(event.event, JobEvent::Done(_));
+                // A dropped receiver closes the connection, which cancels the job.
+                if event_tx.send(event).await.is_err() || done {
+                    return;
+                }
+            }
+        });
+        Ok(RemoteJob { event_rx })
+    }
+}
+
+impl Stream for RemoteJob {
+    type Item = Timestamped<JobEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.event_rx.poll_recv(cx)
+    }
+}
+
+/// A `Timestamped` `JobEvent` as serialized by the worker.
+#[derive(Deserialize)]
+struct WireTimestamped {
+    event: WireEvent,
+    since_start: Duration,
+    wall_clock: SystemTime,
+    user_data: Option<Arc<serde_json::Value>>,
+}
+
+/// A `JobEvent` as serialized by the worker, with the exit status of `Done` as a code.
+#[derive(Deserialize)]
+enum WireEvent {
+    Config(Box<Config>),
+    ScanSummary(ScanSummary),
+    Progress(Progress),
+    Log(Log),
+    Warning(Diagnostic),
+    Error(Diagnostic),
+    Fragment(Vec<u8>),
+    LowDiskSpace { free_bytes: u64, threshold: u64 },
+    OutputSize { bytes: u64, bytes_per_sec: f64 },
+    Done(Result<WireStatus, JobFailure>),
+}
+
+#[derive(Deserialize)]
+struct WireStatus {
+    exit_code: Option<i32>,
+}
+
+impl WireTimestamped {
+    /// Rebuilds the event, timing it on the monotonic clock when it was received.
+    fn into_timestamped(self) -> Timestamped<JobEvent> {
+        let event = match self.event {
+            WireEvent::Config(config) => JobEvent::Config(config),
+            WireEvent::ScanSummary(summary) => JobEvent::ScanSummary(summary),
+            WireEvent::Progress(progress) => JobEvent::Progress(progress),
+            WireEvent::Log(log) => JobEvent::Log(log),
+            WireEvent::Warning(diagnostic) => JobEvent::Warning(diagnostic),
+            WireEvent::Error(diagnostic) => JobEvent::Error(diagnostic),
+            WireEvent::Fragment(data) => JobEvent::Fragment(data),
+            WireEvent::LowDiskSpace { free_bytes, threshold } => JobEvent::LowDiskSpace { free_bytes, threshold },
+            WireEvent::OutputSize { bytes, bytes_per_sec } => JobEvent::OutputSize { bytes, bytes_per_sec },
+            WireEvent::Done(result) => {
+                JobEvent::Done(result.map(|status| exit_status(status.exit_code.unwrap_or(0))))
+            }
+        };
+        Timestamped {
+            event,
+            at: Instant::now(),
+            since_start: self.since_start,
+            wall_clock: self.wall_clock,
+            user_data: self.user_data,
+        }
+    }
+}
+
+/// Ends a remote job whose events can no longer be received.
+fn lost(message: String, started_at: Instant) -> Timestamped<JobEvent> {
+    let failure = JobFailure {
+        message,
+        exit_code: None,
+        kind: FailureKind::Process,
+    };
+    Timestamped::now(JobEvent::Done(Err(failure)), started_at, &None)
+}
+
+/// Rebuilds the exit status of a successful job from its exit code.
+fn exit_status(code: i32) -> ExitStatus {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        ExitStatus::from_raw(code << 8)
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::ExitStatusExt;
+        ExitStatus::from_raw(code as u32)
+    }
+}
+
+fn remote_error(e: impl std::fmt::Display) -> Error {
+    Error::RemoteFailed { reason: e.to_string() }
+}
@@ -0,0 +1,200 @@
+//! Parsing of the title listing `HandBrakeCLI` writes to `stderr` after scanning the source:
+//!
+//! ```text
+//! + title 1:
+//!   + stream: in.mkv
+//!   + duration: 00:01:30
+//!   + size: 1920x1080, pixel aspect: 1/1, display aspect: 1.78, 23.976 fps
+//!   + chapters:
+//!     + 1: duration 00:01:30
+//!   + audio tracks:
+//!     + 1, English (AC3) (5.1 ch) (iso639-2: eng), 48000Hz, 448000bps
+//!   + subtitle tracks:
+//!     + 1, English [PGS]
+//! ```
+
+use std::time::Duration;
+
+use crate::event::{ScanSummary, ScannedTitle, ScannedTrack};
+
+/// The part of a title the following items belong to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Section {
+    Title,
+    Chapters,
+    Audio,
+    Subtitles,
+}
+
+/// Collects the listing line by line, until a line outside of it ends it.
+#[derive(Debug)]
+pub(crate) struct ScanListing {
+    titles: Vec<ScannedTitle>,
+    section: Section,
+}
+
+impl Default for ScanListing {
+    fn default() -> Self {
+        ScanListing {
+            titles: Vec::new(),
+            section: Section::Title,
+        }
+    }
+}
+
+impl ScanListing {
+    /// Reads a `stderr` line, returning whether it belongs to the listing.
+    pub(crate) fn push(&mut self, line: &str) -> bool {
+        let item = line.trim_start();
+        let indent = line.len() - item.len();
+        if indent == 0 {
+            let Some(number) = item.strip_prefix("+ title ").and_then(|rest| rest.strip_suffix(':')) else {
+                return false;
+            };
+            self.titles.push(ScannedTitle {
+                number: number.parse().unwrap_or_default(),
+                ..ScannedTitle::default()
+            });
+            self.section = Section::Title;
+            return true;
+        }
+        let (Some(title), Some(item)) = (self.titles.last_mut(), item.strip_prefix("+ ")) else {
+            return false;
+        };
+        if indent < 4 {
+            self.section = match item {
+                "chapters:" => Section::Chapters,
+                "audio tracks:" => Section::Audio,
+                "subtitle tracks:" => Section::Subtitles,
+                _ => {
+                    if let Some(duration) = item.strip_prefix("duration: ") {
+                        title.duration = parse_duration(duration);
+                    } else if let Some(size) = item.strip_prefix("size: ") {
+                        parse_size(size, title);
+                    }
+                    Section::Title
+                }
+            };
+            return true;
+        }
+        match self.section {
+            Section::Audio => title.audio_tracks.extend(parse_track(item)),
+            Section::Subtitles => title.subtitle_tracks.extend(parse_track(item)),
+            Section::Title | Section::Chapters => {}
+        }
+        true
+    }
+
+    /// Takes the listing read so far, if there is one.
+    pub(crate) fn finish(&mut self) -> Option<ScanSummary> {
+        if self.titles.is_empty() {
+            return None;
+        }
+        self.section = Section::Title;
+        Some(ScanSummary {
+            titles: std::mem::take(&mut self.titles),
+        })
+    }
+}
+
+/// Parses `HH:MM:SS`.
+fn parse_duration(text: &str) -> Option<Duration> {
+    let mut parts = text.trim().splitn(3, ':').map(|part| part.parse::<u64>().ok());
+    let (hours, minutes, seconds) = (parts.next()??, parts.next()??, parts.next()??);
+    Some(Duration::from_secs(hours * 3600 + minutes * 60 + seconds))
+}
+
+/// Parses `1920x1080, pixel aspect: 1/1, display aspect: 1.78, 23.976 fps`.
+fn parse_size(text: &str, title: &mut ScannedTitle) {
+    let mut fields = text.split(", ");
+    if let Some((width, height)) = fields.next().and_then(|size| size.split_once('x')) {
+        title.size = width.parse().ok().zip(height.parse().ok());
+    }
+    title.frame_rate = fields.find_map(|field| field.strip_suffix(" fps")?.parse().ok());
+}
+
+/// Parses `1, English (AC3) (5.1 ch) (iso639-2: eng), 48000Hz, 448000bps` or `1, English [PGS]`.
+fn parse_track(text: &str) -> Option<ScannedTrack> {
+    let (number, description) = text.split_once(", ")?;
+    let language = description
+        .split_once("(iso639-2: ")
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .map(|(language, _)| language.to_string());
+    // Subtitles name their format in brackets, audio tracks their codec in the first parentheses.
+    let codec = enclosed(description, '[', ']').or_else(|| {
+        description
+            .match_indices('(')
+            .filter_map(|(at, _)| enclosed(&description[at..], '(', ')'))
+            .find(|group| !group.starts_with("iso639-2:"))
+    });
+    Some(ScannedTrack {
+        number: number.trim().parse().ok()?,
+        description: description.to_string(),
+        language,
+        codec: codec.map(str::to_string),
+    })
+}
+
+/// Returns the text between the first `open` and the following `close`.
+fn enclosed(text: &str, open: char, close: char) -> Option<&str> {
+    let (_, rest) = text.split_once(open)?;
+    rest.split_once(close).map(|(inner, _)| inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LISTING: &str = "\
++ title 1:
+  + stream: in.mkv
+  + duration: 01:02:03
+  + size: 1920x1080, pixel aspect: 1/1, display aspect: 1.78, 23.976 fps
+  + autocrop: 0/0/0/0
+  + chapters:
+    + 1: duration 01:02:03
+  + audio tracks:
+    + 1, English (AC3) (5.1 ch) (iso639-2: eng), 48000Hz, 448000bps
+    + 2, Japanese (AAC LC) (2.0 ch) (iso639-2: jpn), 48000Hz, 128000bps
+  + subtitle tracks:
+    + 1, English [PGS]
+    + 2, Deutsch (iso639-2: deu) (Text)(SRT)";
+
+    #[test]
+    fn test_scan_listing() {
+        let mut listing = ScanListing::default();
+        assert!(!listing.push("[12:00:00] scan: DVD has 1 title(s)"));
+        assert!(LISTING.lines().all(|line| listing.push(line)));
+        assert!(!listing.push("HandBrake has exited."));
+
+        let summary = listing.finish().unwrap();
+        assert!(listing.finish().is_none());
+        let title = &summary.titles[0];
+        assert_eq!(title.number, 1);
+        assert_eq!(title.duration, Some(Duration::from_secs(3723)));
+        assert_eq!(title.size, Some((1920, 1080)));
+        assert_eq!(title.frame_rate, Some(23.976));
+
+        let audio: Vec<_> = title
+            .audio_tracks
+            .iter()
+            .map(|track| (track.number, track.language.as_deref(), track.codec.as_deref()))
+            .collect();
+        assert_eq!(audio, [(1, Some("eng"), Some("AC3")), (2, Some("jpn"), Some("AAC LC"))]);
+        let subtitles: Vec<_> = title
+            .subtitle_tracks
+            .iter()
+            .map(|track| (track.number, track.language.as_deref(), track.codec.as_deref()))
+            .collect();
+        assert_eq!(subtitles, [(1, None, Some("PGS")), (2, Some("deu"), Some("Text"))]);
+        assert_eq!(title.subtitle_tracks[0].description, "English [PGS]");
+    }
+
+    #[test]
+    fn test_scan_listing_ignores_other_lines() {
+        let mut listing = ScanListing::default();
+        assert!(!listing.push("  + duration: 00:00:10"));
+        assert!(!listing.push("+ titles: 3"));
+        assert!(listing.finish().is_none());
+    }
+}
@@ -0,0 +1,291 @@
+//! Encoding segments of one source in parallel, and concatenating them with `ffmpeg`.
+//!
+//! This is experimental: the segments are encoded independently, so rate control restarts at
+//! every boundary, and the concatenation relies on `ffmpeg` joining the outputs without
+//! re-encoding them.
+//!
+//! Every completed segment is recorded next to its file, so an interrupted encode can be resumed
+//! with `SegmentedEncode::resume()`, only encoding the remaining segments.
+
+use std::ffi::OsString;
+use std::io;
+use std::ops::RangeInclusive;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use futures::StreamExt;
+use tokio::process::Command;
+
+use crate::error::Error;
+use crate::event::JobSummary;
+use crate::job::{InputSource, JobBuilder, OutputDestination};
+
+/// A part of the source encoded by its own `HandBrakeCLI` process.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    /// A range of chapters. See `JobBuilder::chapters()`.
+    Chapters(RangeInclusive<u32>),
+    /// A length of the source, starting at an offset. See `JobBuilder::time_range()`.
+    Time {
+        /// The offset of the segment.
+        start: Duration,
+        /// The length of the segment.
+        duration: Duration,
+    },
+}
+
+/// An encode split into segments that run in parallel, then are concatenated into the output.
+///
+/// Single `HandBrakeCLI` processes often leave cores idle, e.g., with AV1 encoders. Each segment
+/// is encoded into `<output>.segments/`, which is removed once the segments were concatenated.
+///
+/// Requires the `segmented` feature and `ffmpeg` to concatenate the segments.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use handbrake::{HandBrake, InputSource, OutputDestination, Segment};
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let hb = HandBrake::new().await?;
+/// let summaries = hb
+///     .job(InputSource::from("movie.mkv"), OutputDestination::from("movie-av1.mkv"))
+///     .video_codec("svt_av1")
+///     .segmented()
+///     .segments([Segment::Chapters(1..=6), Segment::Chapters(7..=12), Segment::Chapters(13..=18)])
+///     .parallelism(3)
+///     .run()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct SegmentedEncode {
+    job: JobBuilder,
+    segments: Vec<Segment>,
+    parallelism: usize,
+    ffmpeg_path: PathBuf,
+    resume: bool,
+}
+
+impl SegmentedEncode {
+    /// Creates a segmented encode of the given job, without segments.
+    ///
+    /// This is typically called via `JobBuilder::segmented()`.
+    pub fn new(job: JobBuilder) -> Self {
+        SegmentedEncode {
+            job,
+            segments: Vec::new(),
+            parallelism: 2,
+            ffmpeg_path: PathBuf::from("ffmpeg"),
+            resume: false,
+        }
+    }
+
+    /// Adds a segment. Segments are concatenated in the order they were added.
+    pub fn segment(mut self, segment: Segment) -> Self {
+        self.segments.push(segment);
+        self
+    }
+
+    /// Adds several segments.
+    pub fn segments(mut self, segments: impl IntoIterator<Item = Segment>) -> Self {
+        self.segments.extend(segments);
+        self
+    }
+
+    /// Adds one segment per group of chapters, e.g., `chapter_segments(1..=10, 3)` adds the
+    /// chapters 1-3, 4-6, 7-9, and 10.
+    ///
+    /// Segments at chapter boundaries are best suited to resuming an interrupted encode.
+    pub fn chapter_segments(mut self, chapters: RangeInclusive<u32>, per_segment: u32) -> Self {
+        let per_segment = per_segment.max(1);
+        let (first, last) = chapters.into_inner();
+        let mut start = first;
+        while start <= last {
+            let end = start.saturating_add(per_segment - 1).min(last);
+            self.segments.push(Segment::Chapters(start..=end));
+            match end.checked_add(1) {
+                Some(next) => start = next,
+                None => break,
+            }
+        }
+        self
+    }
+
+    /// Skips the segments a previous, interrupted run already completed.
+    ///
+    /// A segment counts as completed if its file exists and it was encoded with the same
+    /// arguments, so changing the job's options encodes it again. Defaults to `false`.
+    pub fn resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    /// Sets how many segments are encoded at once.
+    ///
+    /// Defaults to 2.
+    pub fn parallelism(mut self, parallelism: usize) -> Self {
+        self.parallelism = parallelism.max(1);
+        self
+    }
+
+    /// Sets the `ffmpeg` executable used to concatenate the segments.
+    ///
+    /// Defaults to `ffmpeg` in the system `PATH`.
+    pub fn ffmpeg_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.ffmpeg_path = path.into();
+        self
+    }
+
+    /// Builds one `JobBuilder` per segment, in the order of the segments.
+    ///
+    /// Options of the job that are not part of its `JobSpec`, such as `cancel_token()`, are not
+    /// carried over.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the input is not a file or device, or the output is not a file.
+    pub fn jobs(&self) -> Result<Vec<JobBuilder>, Error> {
+        let (dir, extension) = self.segment_dir()?;
+        Ok(self
+            .segments
+            .iter()
+            .enumerate()
+            .map(|(index, segment)| {
+                let mut spec = self.job.to_spec();
+                spec.output = OutputDestination::File(dir.join(segment_name(index, &extension)));
+                let job = self.job.derive_from_spec(spec);
+                match segment {
+                    Segment::Chapters(chapters) => job.chapters(chapters.clone()),
+                    Segment::Time { start, duration } => job.time_range(*start, *duration),
+                }
+            })
+            .collect())
+    }
+
+    /// Encodes the segments in monitored mode, then concatenates them into the output.
+    ///
+    /// Returns the `JobSummary` of every segment encoded by this run, in the order of the segments,
+    /// without the segments skipped by `resume()`. If a segment fails, the other segments still
+    /// complete, and the encoded segments are kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if a segment or the concatenation failed.
+    pub async fn run(self) -> Result<Vec<JobSummary>, Error> {
+        let jobs = self.jobs()?;
+        let (dir, extension) = self.segment_dir()?;
+        tokio::fs::create_dir_all(&dir).await.map_err(file_error)?;
+
+        let resume = self.resume;
+        let results: Vec<_> = futures::stream::iter(jobs.into_iter().enumerate())
+            .map(|(index, job)| {
+                let segment = dir.join(segment_name(index, &extension));
+                async move { encode_segment(job, segment, resume).await.map_err(|reason| (index, reason)) }
+            })
+            .buffered(self.parallelism)
+            .collect()
+            .await;
+        let mut summaries = Vec::with_capacity(results.len());
+        for result in results {
+            match result {
+                Ok(Some(summary)) => summaries.push(summary),
+                Ok(None) => {}
+                Err((index, reason)) => {
+                    return Err(Error::SegmentedEncodeFailed {
+                        reason: format!("Segment {} failed: {}", index + 1, reason),
+                    });
+                }
+            }
+        }
+
+        // The concat demuxer resolves the file names relative to the list.
+        let list = dir.join("segments.txt");
+        let contents: String = (0..self.segments.len())
+            .map(|index| format!("file '{}'\n", segment_name(index, &extension)))
+            .collect();
+        tokio::fs::write(&list, contents).await.map_err(file_error)?;
+
+        let OutputDestination::File(output) = &self.job.output else {
+            unreachable!("BUG: the output was checked by segment_dir()");
+        };
+        let status = Command::new(&self.ffmpeg_path)
+            .args(["-y", "-loglevel", "error", "-f", "concat", "-safe", "0", "-i"])
+            .arg(&list)
+            .args(["-c", "copy"])
+            .arg(output)
+            .status()
+            .await
+            .map_err(|e| Error::ProcessSpawnFailed { source: e })?;
+        if !status.success() {
+            return Err(Error::SegmentedEncodeFailed {
+                reason: format!("ffmpeg exited unsuccessfully: {}", status),
+            });
+        }
+        tokio::fs::remove_dir_all(&dir).await.map_err(file_error)?;
+        Ok(summaries)
+    }
+
+    /// Returns the directory the segments are encoded into, and their file extension.
+    fn segment_dir(&self) -> Result<(PathBuf, String), Error> {
+        if !matches!(self.job.input, InputSource::File(_) | InputSource::Device(_)) {
+            return Err(Error::SegmentedEncodeFailed {
+                reason: "The input must be a file or device, as it is read once per segment"
+                    .to_string(),
+            });
+        }
+        let OutputDestination::File(output) = &self.job.output else {
+            return Err(Error::SegmentedEncodeFailed {
+                reason: "The output must be a file".to_string(),
+            });
+        };
+        let mut dir = OsString::from(output.as_os_str());
+        dir.push(".segments");
+        let extension = output
+            .extension()
+            .map(|e| e.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "mkv".to_string());
+        Ok((PathBuf::from(dir), extension))
+    }
+}
+
+/// Encodes a segment, unless resuming and it was completed with the same arguments before.
+///
+/// Returns `None` if the segment was skipped.
+async fn encode_segment(job: JobBuilder, segment: PathBuf, resume: bool) -> Result<Option<JobSummary>, String> {
+    let mut marker = OsString::from(segment.as_os_str());
+    marker.push(".done");
+    let marker = PathBuf::from(marker);
+    let args = serde_json::to_string(&job.build_args()).map_err(|e| e.to_string())?;
+
+    if resume
+        && tokio::fs::try_exists(&segment).await.unwrap_or(false)
+        && tokio::fs::read_to_string(&marker).await.is_ok_and(|recorded| recorded == args)
+    {
+        return Ok(None);
+    }
+    // The segment is only recorded once it is complete again.
+    match tokio::fs::remove_file(&marker).await {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.to_string()),
+        _ => {}
+    }
+    let summary = job
+        .start()
+        .map_err(|e| e.to_string())?
+        .wait()
+        .await
+        .map_err(|failure| failure.message)?;
+    tokio::fs::write(&marker, args).await.map_err(|e| e.to_string())?;
+    Ok(Some(summary))
+}
+
+fn segment_name(index: usize, extension: &str) -> String {
+    format!("segment-{:03}.{}", index + 1, extension)
+}
+
+/// Describes a failure to manage the segment files.
+fn file_error(e: io::Error) -> Error {
+    Error::SegmentedEncodeFailed {
+        reason: format!("Failed to manage the segment files: {}", e),
+    }
+}
@@ -0,0 +1,101 @@
+//! Recognizing inputs `HandBrakeCLI` can't read from their first bytes, see
+//! `JobBuilder::check_input()`.
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::error::Error;
+
+/// How many bytes are read from the start of an input.
+const SNIFF_LEN: u64 = 512;
+
+/// Formats `HandBrakeCLI` can't read, by the magic bytes at the start of their files.
+///
+/// None of them collides with the start of a container `HandBrakeCLI` reads.
+const UNSUPPORTED: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "a PNG image"),
+    (b"\xff\xd8\xff", "a JPEG image"),
+    (b"GIF87a", "a GIF image"),
+    (b"GIF89a", "a GIF image"),
+    (b"II*\0", "a TIFF image"),
+    (b"MM\0*", "a TIFF image"),
+    (b"%PDF-", "a PDF document"),
+    (b"PK\x03\x04", "a ZIP archive"),
+    (b"\x1f\x8b", "a gzip archive"),
+    (b"7z\xbc\xaf\x27\x1c", "a 7-Zip archive"),
+    (b"Rar!\x1a\x07", "a RAR archive"),
+    (b"\x7fELF", "an executable"),
+];
+
+/// Fails if the file at `path` is recognized as a format `HandBrakeCLI` can't read.
+pub(crate) fn check_input(path: &Path) -> Result<(), Error> {
+    // Errors are left to `HandBrakeCLI`, which reports them in its own words.
+    let Ok(file) = File::open(path) else {
+        return Ok(());
+    };
+    if file.metadata().is_ok_and(|metadata| metadata.is_dir()) {
+        return Ok(());
+    }
+    let mut head = Vec::new();
+    if file.take(SNIFF_LEN).read_to_end(&mut head).is_err() {
+        return Ok(());
+    }
+    match sniff(&head) {
+        Some(reason) => Err(Error::UnsupportedInput {
+            path: path.to_path_buf(),
+            reason: reason.to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Returns what the start of a file is recognized as, if it is no video.
+fn sniff(head: &[u8]) -> Option<&'static str> {
+    if head.is_empty() {
+        return Some("the file is empty");
+    }
+    if let Some((_, reason)) = UNSUPPORTED.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return Some(reason);
+    }
+    if head.starts_with(b"RIFF") && head.get(8..12) == Some(b"WEBP") {
+        return Some("a WebP image");
+    }
+    is_text(head).then_some("a text file")
+}
+
+/// Whether the data is UTF-8 text, which may be cut off within the last character.
+fn is_text(data: &[u8]) -> bool {
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => {
+            std::str::from_utf8(&data[..e.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return false,
+    };
+    !text.is_empty() && text.chars().all(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r' | '\x0c'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff_rejects_non_video() {
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), Some("a PNG image"));
+        assert_eq!(sniff(b"RIFF\x10\0\0\0WEBPVP8 "), Some("a WebP image"));
+        assert_eq!(sniff(b"1\n00:00:01,000 --> 00:00:02,000\nHello\n"), Some("a text file"));
+        assert_eq!(sniff(&"Caf\u{e9}".as_bytes()[..4]), Some("a text file"));
+        assert_eq!(sniff(b""), Some("the file is empty"));
+    }
+
+    #[test]
+    fn test_sniff_accepts_video() {
+        // Matroska, MP4, AVI, MPEG-PS, and MPEG-TS.
+        assert_eq!(sniff(b"\x1a\x45\xdf\xa3\x9f\x42\x86\x81\x01"), None);
+        assert_eq!(sniff(b"\0\0\0\x20ftypisom\0\0\x02\0"), None);
+        assert_eq!(sniff(b"RIFF\x10\0\0\0AVI LIST"), None);
+        assert_eq!(sniff(b"\0\0\x01\xba\x44\0\x04\0\x04\x01"), None);
+        assert_eq!(sniff(b"\x47\x40\0\x10\0\0\xb0\x0d"), None);
+    }
+}
@@ -0,0 +1,216 @@
+//! Serving the status and progress of jobs over HTTP, e.g., for monitoring dashboards.
+
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use bytes::Bytes;
+use futures::StreamExt;
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, Full, StreamBody};
+use hyper::body::{Frame, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde::Serialize;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+
+use crate::error::Error;
+use crate::event::Progress;
+use crate::handle::{JobController, JobState};
+
+/// The number of progress messages kept for slow event stream clients before they skip ahead.
+const MESSAGES_CAPACITY: usize = 256;
+
+/// An HTTP endpoint serving the status of the jobs tracked with `StatusServer::track()`.
+///
+/// - `GET /jobs` returns the status of every job as a JSON array.
+/// - `GET /events` streams the progress of every job as server-sent events, with a `progress`
+///   event per update and a `done` event once a job exited.
+///
+/// The status is read from the `JobController` of a job, so tracking a job doesn't consume its
+/// events. Requires the `status-server` feature.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// # use handbrake::{HandBrake, StatusServer};
+/// # use tokio::net::TcpListener;
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// # let hb = HandBrake::new().await?;
+/// let server = StatusServer::new();
+/// let listener = TcpListener::bind("127.0.0.1:8080").await?;
+/// tokio::spawn({
+///     let server = server.clone();
+///     async move { server.serve(listener).await }
+/// });
+///
+/// let handle = hb.job("movie.mkv".into(), "movie.mp4".into()).start()?;
+/// server.track("movie", &handle.controller());
+/// let result = handle.wait().await;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct StatusServer {
+    jobs: Arc<Mutex<BTreeMap<u64, TrackedJob>>>,
+    next_id: Arc<AtomicU64>,
+    messages: broadcast::Sender<Bytes>,
+}
+
+#[derive(Debug)]
+struct TrackedJob {
+    name: String,
+    controller: JobController,
+}
+
+/// The status of a job, as served by `GET /jobs`.
+#[derive(Serialize)]
+struct JobStatus<'a> {
+    id: u64,
+    name: &'a str,
+    state: &'static str,
+    exit_code: Option<i32>,
+    progress: Option<Progress>,
+}
+
+impl StatusServer {
+    /// Creates a server without jobs.
+    pub fn new() -> Self {
+        StatusServer {
+            jobs: Arc::new(Mutex::new(BTreeMap::new())),
+            next_id: Arc::new(AtomicU64::new(1)),
+            messages: broadcast::channel(MESSAGES_CAPACITY).0,
+        }
+    }
+
+    /// Adds a job to the served status, returning its ID.
+    ///
+    /// Jobs are kept after they exited, until removed with `untrack()`.
+    pub fn track(&self, name: impl Into<String>, controller: &JobController) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.jobs.lock().unwrap().insert(
+            id,
+            TrackedJob {
+                name: name.into(),
+                controller: controller.clone(),
+            },
+        );
+
+        let mut progress = controller.progress_watch();
+        let controller = controller.clone();
+        let messages = self.messages.clone();
+        tokio::spawn(async move {
+            // The watch closes once the job exited.
+            while progress.changed().await.is_ok() {
+                let update = progress.borrow_and_update().clone();
+                let data = serde_json::json!({ "id": id, "progress": update });
+                let _ = messages.send(format!("event: progress\ndata: {}\n\n", data).into());
+            }
+            let (state, exit_code) = state(controller.state());
+            let data = serde_json::json!({ "id": id, "state": state, "exit_code": exit_code });
+            let _ = messages.send(format!("event: done\ndata: {}\n\n", data).into());
+        });
+        id
+    }
+
+    /// Removes a job from the served status.
+    pub fn untrack(&self, id: u64) {
+        self.jobs.lock().unwrap().remove(&id);
+    }
+
+    /// Serves the status on the listener until accepting a connection fails.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if a connection can't be accepted.
+    pub async fn serve(&self, listener: TcpListener) -> Result<(), Error> {
+        loop {
+            let (stream, _) = listener.accept().await.map_err(|source| Error::AcceptFailed { source })?;
+            let server = self.clone();
+            tokio::spawn(async move {
+                let service = service_fn(move |request| {
+                    let server = server.clone();
+                    async move { Ok::<_, Infallible>(server.respond(request)) }
+                });
+                let _ = http1::Builder::new().serve_connection(TokioIo::new(stream), service).await;
+            });
+        }
+    }
+
+    fn respond(&self, request: Request<Incoming>) -> Response<BoxBody<Bytes, Infallible>> {
+        match (request.method(), request.uri().path()) {
+            (&Method::GET, "/jobs") => {
+                let jobs = self.jobs.lock().unwrap();
+                let statuses: Vec<JobStatus> = jobs
+                    .iter()
+                    .map(|(&id, job)| {
+                        let (state, exit_code) = state(job.controller.state());
+                        JobStatus {
+                            id,
+                            name: &job.name,
+                            state,
+                            exit_code,
+                            progress: job.controller.progress_watch().borrow().clone(),
+                        }
+                    })
+                    .collect();
+                let body = serde_json::to_vec(&statuses).unwrap_or_default();
+                response(StatusCode::OK, "application/json", Full::new(body.into()).boxed())
+            }
+            (&Method::GET, "/events") => {
+                // Messages missed by a slow client are skipped.
+                let messages = messages(self.messages.subscribe())
+                    .map(|message| Ok::<_, Infallible>(Frame::data(message)));
+                response(StatusCode::OK, "text/event-stream", BodyExt::boxed(StreamBody::new(messages)))
+            }
+            _ => response(StatusCode::NOT_FOUND, "text/plain", Full::new("Not found".into()).boxed()),
+        }
+    }
+}
+
+impl Default for StatusServer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The messages of a broadcast channel, skipping the ones a slow receiver missed.
+fn messages(mut rx: broadcast::Receiver<Bytes>) -> impl futures::Stream<Item = Bytes> + Send {
+    async_stream::stream! {
+        loop {
+            match rx.recv().await {
+                Ok(message) => yield message,
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+}
+
+fn response(
+    status: StatusCode,
+    content_type: &str,
+    body: BoxBody<Bytes, Infallible>,
+) -> Response<BoxBody<Bytes, Infallible>> {
+    let mut response = Response::new(body);
+    *response.status_mut() = status;
+    if let Ok(value) = content_type.parse() {
+        response.headers_mut().insert(hyper::header::CONTENT_TYPE, value);
+    }
+    response
+}
+
+/// The name of a state, and the exit code once the job exited.
+fn state(state: JobState) -> (&'static str, Option<i32>) {
+    match state {
+        JobState::Running => ("running", None),
+        JobState::Cancelling => ("cancelling", None),
+        JobState::Exited(status) => ("exited", status.code()),
+        JobState::Failed => ("failed", None),
+    }
+}
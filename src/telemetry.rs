@@ -0,0 +1,46 @@
+//! Job metrics, reported through the `metrics` facade when the `metrics` feature is enabled.
+//!
+//! Without the feature, these functions compile to nothing.
+
+/// Counter of spawned `HandBrakeCLI` processes.
+#[cfg(feature = "metrics")]
+const JOBS_STARTED: &str = "handbrake_jobs_started_total";
+/// Counter of jobs that did not complete successfully, including timeouts and cancellations.
+#[cfg(feature = "metrics")]
+const JOBS_FAILED: &str = "handbrake_jobs_failed_total";
+/// Gauge of `HandBrakeCLI` processes that have not exited yet.
+#[cfg(feature = "metrics")]
+const JOBS_RUNNING: &str = "handbrake_jobs_running";
+/// Gauge of the encoding speed summed over all monitored jobs, in frames per second.
+#[cfg(feature = "metrics")]
+const ENCODE_FPS: &str = "handbrake_encode_fps";
+
+/// Records that a `HandBrakeCLI` process was spawned.
+pub(crate) fn job_started() {
+    #[cfg(feature = "metrics")]
+    {
+        ::metrics::counter!(JOBS_STARTED).increment(1);
+        ::metrics::gauge!(JOBS_RUNNING).increment(1.0);
+    }
+}
+
+/// Records that a `HandBrakeCLI` process exited, or could no longer be waited on.
+pub(crate) fn job_finished(succeeded: bool) {
+    #[cfg(feature = "metrics")]
+    {
+        ::metrics::gauge!(JOBS_RUNNING).decrement(1.0);
+        if !succeeded {
+            ::metrics::counter!(JOBS_FAILED).increment(1);
+        }
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = succeeded;
+}
+
+/// Replaces a job's previous contribution to the summed encoding speed with its current one.
+pub(crate) fn fps_changed(previous: f32, current: f32) {
+    #[cfg(feature = "metrics")]
+    ::metrics::gauge!(ENCODE_FPS).increment(f64::from(current - previous));
+    #[cfg(not(feature = "metrics"))]
+    let _ = (previous, current);
+}
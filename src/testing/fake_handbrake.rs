@@ -0,0 +1,189 @@
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::handle::JobHandle;
+use crate::job::{InputSource, JobBuilder, OutputDestination};
+
+/// A scripted stand-in for `HandBrakeCLI`, installed as an executable shell script.
+///
+/// The script answers `--version` like `HandBrakeCLI`, and otherwise replays its steps in order:
+/// progress lines on `stdout`, log lines on `stderr`, and delays, before exiting with the
+/// configured exit code. Its output goes through the same parsing as a real encode, so a job
+/// started from the installed path produces realistic `JobEvent`s.
+///
+/// Only available on Unix with the `test-util` feature enabled.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use handbrake::testing::FakeHandBrake;
+/// use handbrake::{HandBrake, InputSource, OutputDestination};
+/// use std::time::Duration;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let dir = std::env::temp_dir();
+/// let path = FakeHandBrake::new()
+///     .progress(50.0)
+///     .delay(Duration::from_millis(100))
+///     .log("Encode failed")
+///     .exit_code(3)
+///     .install(&dir)?;
+///
+/// let hb = HandBrake::new_with_path(path).await?;
+/// let job = hb.job(InputSource::from("in.mkv"), OutputDestination::from("out.mp4"));
+/// assert!(job.start()?.wait().await.is_err());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct FakeHandBrake {
+    version: String,
+    steps: Vec<Step>,
+    exit_code: i32,
+}
+
+#[derive(Debug, Clone)]
+enum Step {
+    Stdout(String),
+    Stderr(String),
+    Delay(Duration),
+}
+
+impl Default for FakeHandBrake {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeHandBrake {
+    /// Creates a fake that reports version `HandBrake 1.8.0` and exits successfully.
+    pub fn new() -> Self {
+        FakeHandBrake {
+            version: "HandBrake 1.8.0".to_string(),
+            steps: Vec::new(),
+            exit_code: 0,
+        }
+    }
+
+    /// Sets the output of `--version`.
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    /// Reports a progress percentage, without speed or ETA.
+    pub fn progress(mut self, percentage: f32) -> Self {
+        self.steps.push(Step::Stdout(format!(
+            "Encoding: task 1 of 1, {:.2} %\r",
+            percentage
+        )));
+        self
+    }
+
+    /// Reports a progress percentage with the current and average FPS and the ETA.
+    pub fn progress_with_rate(mut self, percentage: f32, fps: f32, avg_fps: f32, eta: Duration) -> Self {
+        let secs = eta.as_secs();
+        self.steps.push(Step::Stdout(format!(
+            "Encoding: task 1 of 1, {:.2} % ({:.2} fps, avg {:.2} fps, ETA {:02}h{:02}m{:02}s)\r",
+            percentage,
+            fps,
+            avg_fps,
+            secs / 3600,
+            secs / 60 % 60,
+            secs % 60
+        )));
+        self
+    }
+
+    /// Writes a log line to `stderr`.
+    pub fn log(mut self, message: impl Into<String>) -> Self {
+        self.steps.push(Step::Stderr(format!("{}\n", message.into())));
+        self
+    }
+
+    /// Writes raw data to `stderr`, e.g., a JSON job configuration block.
+    pub fn stderr(mut self, data: impl Into<String>) -> Self {
+        self.steps.push(Step::Stderr(data.into()));
+        self
+    }
+
+    /// Writes raw data to `stdout`, e.g., to simulate encoded video when writing to `stdout`.
+    pub fn stdout(mut self, data: impl Into<String>) -> Self {
+        self.steps.push(Step::Stdout(data.into()));
+        self
+    }
+
+    /// Pauses before the next step.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.steps.push(Step::Delay(delay));
+        self
+    }
+
+    /// Sets the exit code once all steps are done.
+    pub fn exit_code(mut self, exit_code: i32) -> Self {
+        self.exit_code = exit_code;
+        self
+    }
+
+    /// Returns the shell script implementing the fake.
+    pub fn script(&self) -> String {
+        let mut script = String::from("#!/bin/sh\n");
+        script.push_str(&format!(
+            "if [ \"$1\" = \"--version\" ]; then printf '%s\\n' {}; exit 0; fi\n",
+            quote(&self.version)
+        ));
+        for step in &self.steps {
+            match step {
+                Step::Stdout(data) => script.push_str(&format!("printf '%s' {}\n", quote(data))),
+                Step::Stderr(data) => {
+                    script.push_str(&format!("printf '%s' {} >&2\n", quote(data)))
+                }
+                Step::Delay(delay) => {
+                    script.push_str(&format!("sleep {}\n", delay.as_secs_f64()))
+                }
+            }
+        }
+        script.push_str(&format!("exit {}\n", self.exit_code));
+        script
+    }
+
+    /// Writes the script as an executable file named `HandBrakeCLI` into `dir`, returning its path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file could not be written.
+    pub fn install(&self, dir: &Path) -> io::Result<PathBuf> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join("HandBrakeCLI");
+        std::fs::write(&path, self.script())?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+        Ok(path)
+    }
+
+    /// Installs the fake into `dir` and starts a monitored job with it.
+    ///
+    /// This skips the `--version` check of `HandBrake::new_with_path()`. The job's input and
+    /// output are placeholder paths, which the fake never touches.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the fake could not be installed or spawned.
+    pub fn start(&self, dir: &Path) -> Result<JobHandle, Error> {
+        let path = self.install(dir)?;
+        JobBuilder::new(
+            path,
+            InputSource::File(dir.join("input.mkv")),
+            OutputDestination::File(dir.join("output.mp4")),
+        )
+        .start()
+    }
+}
+
+/// Quotes a string for the shell.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
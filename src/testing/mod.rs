@@ -1,2 +1,15 @@
+//! Utilities for testing code built on this crate without a real `HandBrakeCLI`.
+//!
+//! Enable the `test-util` feature to use them from downstream crates.
+
+#[cfg(all(feature = "test-util", unix))]
+mod fake_handbrake;
 #[cfg(test)]
 pub mod mock_command;
+#[cfg(all(feature = "test-util", unix))]
+mod transcript;
+
+#[cfg(all(feature = "test-util", unix))]
+pub use fake_handbrake::FakeHandBrake;
+#[cfg(all(feature = "test-util", unix))]
+pub use transcript::{Transcript, TranscriptEntry, TranscriptStream};
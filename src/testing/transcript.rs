@@ -0,0 +1,86 @@
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::FakeHandBrake;
+use crate::error::Error;
+use crate::handle::JobHandle;
+
+/// A recording of the output of a `HandBrakeCLI` run, with the time each chunk was written.
+///
+/// Transcripts are plain data, so they can be stored as JSON and replayed later:
+///
+/// ```json
+/// {
+///   "exit_code": 0,
+///   "entries": [
+///     { "offset_ms": 0, "stream": "stderr", "data": "Starting work\n" },
+///     { "offset_ms": 1000, "stream": "stdout", "data": "Encoding: task 1 of 1, 42.00 %\r" }
+///   ]
+/// }
+/// ```
+///
+/// Only available on Unix with the `test-util` feature enabled.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Transcript {
+    /// The exit code of the recorded process.
+    #[serde(default)]
+    pub exit_code: i32,
+    /// The recorded output, ordered by offset.
+    pub entries: Vec<TranscriptEntry>,
+}
+
+/// A chunk of output in a `Transcript`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptEntry {
+    /// When the chunk was written, in milliseconds since the process started.
+    pub offset_ms: u64,
+    /// The stream the chunk was written to.
+    pub stream: TranscriptStream,
+    /// The chunk, written verbatim. Progress lines end with `\r`, log lines with `\n`.
+    pub data: String,
+}
+
+/// The output stream of a `TranscriptEntry`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptStream {
+    /// Standard output, carrying progress and, when writing to `stdout`, the encoded video.
+    Stdout,
+    /// Standard error, carrying the JSON job configuration and log messages.
+    Stderr,
+}
+
+impl Transcript {
+    /// Converts the transcript into a `FakeHandBrake` that writes the same output with the same
+    /// timing.
+    pub fn to_fake(&self) -> FakeHandBrake {
+        let mut fake = FakeHandBrake::new().exit_code(self.exit_code);
+        let mut offset_ms = 0;
+        for entry in &self.entries {
+            if entry.offset_ms > offset_ms {
+                fake = fake.delay(Duration::from_millis(entry.offset_ms - offset_ms));
+                offset_ms = entry.offset_ms;
+            }
+            fake = match entry.stream {
+                TranscriptStream::Stdout => fake.stdout(entry.data.clone()),
+                TranscriptStream::Stderr => fake.stderr(entry.data.clone()),
+            };
+        }
+        fake
+    }
+
+    /// Replays the transcript through a monitored job, returning its `JobHandle`.
+    ///
+    /// The fake executable is installed into `dir`. The events of the returned handle are parsed
+    /// from the replayed output exactly as for a real encode, and the job can be controlled as
+    /// usual.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `Error` if the fake executable could not be installed or spawned.
+    pub fn replay(&self, dir: &Path) -> Result<JobHandle, Error> {
+        self.to_fake().start(dir)
+    }
+}
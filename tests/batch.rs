@@ -0,0 +1,53 @@
+use handbrake::BatchBuilder;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+fn batch(inputs: &[&str]) -> BatchBuilder {
+    BatchBuilder::new(PathBuf::from("/usr/bin/HandBrakeCLI"), inputs.iter().copied())
+}
+
+#[test]
+fn test_batch_applies_configuration_to_every_input() {
+    let jobs = batch(&["/videos/a.mkv", "/videos/b.mkv"])
+        .preset("Fast 1080p30")
+        .quality(22.0)
+        .output_dir("/encoded")
+        .jobs();
+
+    let args: Vec<Vec<OsString>> = jobs.iter().map(|job| job.build_args()).collect();
+    assert_eq!(
+        args,
+        vec![
+            vec!["-i", "/videos/a.mkv", "-o", "/encoded/a.mp4", "--preset", "Fast 1080p30", "--quality", "22"],
+            vec!["-i", "/videos/b.mkv", "-o", "/encoded/b.mp4", "--preset", "Fast 1080p30", "--quality", "22"],
+        ]
+    );
+}
+
+#[test]
+fn test_batch_extension_follows_format() {
+    let jobs = batch(&["/videos/a.part1.mkv"]).format("mkv").output_dir("/encoded").jobs();
+    assert_eq!(jobs[0].build_args()[3], "/encoded/a.part1.mkv");
+
+    let jobs = batch(&["/videos/a.mkv"]).format("mkv").extension("m4v").output_dir("/encoded").jobs();
+    assert_eq!(jobs[0].build_args()[3], "/encoded/a.m4v");
+}
+
+#[test]
+fn test_batch_does_not_overwrite_inputs() {
+    let jobs = batch(&["/videos/a.mp4", "/videos/b.mkv"]).jobs();
+    assert_eq!(jobs[0].build_args()[3], "/videos/a-encoded.mp4");
+    assert_eq!(jobs[1].build_args()[3], "/videos/b.mp4");
+}
+
+#[test]
+fn test_batch_options_apply_in_call_order() {
+    let jobs = batch(&["a.mkv"])
+        .configure(|job| job.preset("HQ 1080p30 Surround"))
+        .preset("Fast 1080p30")
+        .jobs();
+    assert_eq!(
+        jobs[0].build_args(),
+        vec!["-i", "a.mkv", "-o", "a.mp4", "--preset", "Fast 1080p30"]
+    );
+}
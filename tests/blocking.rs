@@ -0,0 +1,59 @@
+//! Tests for the blocking API.
+//!
+//! These tests use a shell script as a stand-in for `HandBrakeCLI`, so they only run on Unix.
+#![cfg(unix)]
+
+use handbrake::blocking::HandBrake;
+use handbrake::{InputSource, JobEvent, OutputDestination};
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+/// Writes a fake `HandBrakeCLI` that reports its version and some progress.
+fn fake_handbrake(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("handbrake-rs-{}-{}", name, std::process::id()));
+    std::fs::write(
+        &path,
+        "#!/bin/sh\n\
+         if [ \"$1\" = \"--version\" ]; then echo 'HandBrake 1.8.0'; exit 0; fi\n\
+         printf 'Encoding: task 1 of 1, 50.00 %%\\r'\n\
+         echo 'Finished work' >&2\n",
+    )
+    .unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+#[test]
+fn test_blocking_run_reports_events() {
+    let path = fake_handbrake("run");
+    let hb = HandBrake::new_with_path(&path).expect("Fake HandBrakeCLI should validate");
+    assert_eq!(hb.version(), "HandBrake 1.8.0");
+
+    let job = hb.job(InputSource::from("in.mkv"), OutputDestination::from("out.mp4"));
+    let mut percentages = Vec::new();
+    let mut logs = Vec::new();
+    let summary = hb
+        .run(job, |event| match event {
+            JobEvent::Progress(p) => percentages.push(p.percentage),
+            JobEvent::Log(log) => logs.push(log.message.clone()),
+            _ => {}
+        })
+        .expect("Job should succeed");
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(summary.status.success());
+    assert_eq!(summary.output, Some(PathBuf::from("out.mp4")));
+    assert_eq!(percentages, vec![50.0]);
+    assert_eq!(logs, vec!["Finished work".to_string()]);
+}
+
+#[test]
+fn test_blocking_status() {
+    let path = fake_handbrake("status");
+    let hb = HandBrake::new_with_path(&path).expect("Fake HandBrakeCLI should validate");
+
+    let job = hb.job(InputSource::from("in.mkv"), OutputDestination::from("out.mp4"));
+    let status = hb.status(job).expect("Job should spawn");
+    std::fs::remove_file(&path).unwrap();
+    assert!(status.success());
+}
@@ -0,0 +1,45 @@
+use handbrake::ChapterSplit;
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+fn split(input: &str, chapters: std::ops::RangeInclusive<u32>) -> ChapterSplit {
+    ChapterSplit::new(PathBuf::from("/usr/bin/HandBrakeCLI"), input, chapters)
+}
+
+#[test]
+fn test_chapter_split_builds_one_job_per_chapter() {
+    let jobs = split("/discs/concert.iso", 2..=3)
+        .preset("Fast 1080p30")
+        .output_dir("/tracks")
+        .jobs();
+
+    let args: Vec<Vec<OsString>> = jobs.iter().map(|job| job.build_args()).collect();
+    assert_eq!(
+        args,
+        vec![
+            vec!["-i", "/discs/concert.iso", "-o", "/tracks/concert-02.mp4", "--preset", "Fast 1080p30", "--chapters", "2"],
+            vec!["-i", "/discs/concert.iso", "-o", "/tracks/concert-03.mp4", "--preset", "Fast 1080p30", "--chapters", "3"],
+        ]
+    );
+}
+
+#[test]
+fn test_chapter_split_name_template() {
+    let jobs = split("show.mkv", 9..=120)
+        .name_template("{name} - Episode {chapter}")
+        .format("av_mkv")
+        .jobs();
+    assert_eq!(jobs.len(), 112);
+    assert_eq!(jobs[0].build_args()[3], "show - Episode 009.mkv");
+    assert_eq!(jobs[111].build_args()[3], "show - Episode 120.mkv");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_chapter_split_keeps_non_utf8_names() {
+    use std::os::unix::ffi::OsStrExt;
+
+    let input = PathBuf::from(std::ffi::OsStr::from_bytes(b"/discs/caf\xe9.iso"));
+    let jobs = ChapterSplit::new(PathBuf::from("/usr/bin/HandBrakeCLI"), input, 1..=1).jobs();
+    assert_eq!(jobs[0].build_args()[3].as_bytes(), b"/discs/caf\xe9-01.mp4");
+}
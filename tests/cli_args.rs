@@ -0,0 +1,127 @@
+use handbrake::{Error, InputSource, JobBuilder, OutputDestination, RateControl};
+use std::path::PathBuf;
+use std::time::Duration;
+
+fn parse(args: &[&str]) -> Result<JobBuilder, Error> {
+    let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+    JobBuilder::from_args(PathBuf::from("/usr/bin/HandBrakeCLI"), &args)
+}
+
+#[test]
+fn test_from_args_maps_known_options() {
+    let builder = parse(&[
+        "-i", "movie.mkv", "--output=movie.mp4", "-Z", "Fast 1080p30", "-e", "x265", "-x",
+        "tune=grain", "-q", "20",
+        "--width", "1280", "-l", "720", "--audio", "1,av_aac", "-s", "1,2", "--subtitle-burned",
+        "native", "-c", "3-5", "--verbose=3", "--json",
+    ])
+    .unwrap();
+    let spec = builder.to_spec();
+
+    assert_eq!(spec.input, InputSource::File("movie.mkv".into()));
+    assert_eq!(spec.output, OutputDestination::File("movie.mp4".into()));
+    assert_eq!(spec.preset.as_deref(), Some("Fast 1080p30"));
+    assert_eq!(spec.video_codec.as_deref(), Some("x265"));
+    assert_eq!(spec.advanced_encoder_options.as_deref(), Some("tune=grain"));
+    assert_eq!(spec.rate_control, Some(RateControl::ConstantQuality(20.0)));
+    assert_eq!((spec.width, spec.height), (Some(1280), Some(720)));
+    assert_eq!(spec.chapters, Some((3, 5)));
+    assert_eq!(spec.verbosity, Some(3));
+    assert!(spec.json_output);
+    assert!(spec.extra_args.is_empty());
+}
+
+#[test]
+fn test_from_args_keeps_unknown_options() {
+    let builder = parse(&[
+        "--comb-detect", "-i", "pipe:0", "--deinterlace=bob", "-o", "pipe:1", "-q22", "--audio", "1,2",
+    ])
+    .unwrap();
+    let spec = builder.to_spec();
+
+    assert_eq!(spec.input, InputSource::Stdin);
+    assert_eq!(spec.output, OutputDestination::Stdout);
+    assert_eq!(spec.extra_args, vec!["--comb-detect", "--deinterlace=bob", "-q22", "--audio", "1,2"]);
+    assert_eq!(
+        builder.build_args(),
+        vec!["-i", "pipe:0", "-o", "pipe:1", "--comb-detect", "--deinterlace=bob", "-q22", "--audio", "1,2"]
+    );
+}
+
+#[test]
+fn test_from_args_ranges() {
+    let spec = parse(&["-i", "in.mkv", "-o", "out.mp4", "--start-at", "duration:30", "--stop-at", "duration:90"])
+        .unwrap()
+        .to_spec();
+    assert_eq!(spec.time_range, Some((Duration::from_secs(30), Duration::from_secs(90))));
+
+    let spec = parse(&["-i", "in.mkv", "-o", "out.mp4", "--start-at-preview", "5", "--stop-at", "duration:10"])
+        .unwrap()
+        .to_spec();
+    assert_eq!(spec.sample, Some(Duration::from_secs(10)));
+
+    // Ranges in frames have no `JobBuilder` equivalent.
+    let spec = parse(&["-i", "in.mkv", "-o", "out.mp4", "--start-at", "frame:100", "--stop-at", "duration:10"])
+        .unwrap()
+        .to_spec();
+    assert_eq!(spec.time_range, None);
+    assert_eq!(spec.extra_args, vec!["--start-at", "frame:100", "--stop-at", "duration:10"]);
+}
+
+#[test]
+fn test_from_args_multi_pass() {
+    // The bitrate may come after the passes.
+    let spec = parse(&["-i", "in.mkv", "-o", "out.mp4", "-2", "-T", "-b", "6000"]).unwrap().to_spec();
+    assert_eq!(
+        spec.rate_control,
+        Some(RateControl::Bitrate {
+            kbps: 6000,
+            two_pass: true,
+            turbo: true,
+        })
+    );
+    assert!(spec.extra_args.is_empty());
+
+    // Passes don't apply to a constant quality, so they are kept as they are.
+    let spec = parse(&["-i", "in.mkv", "-o", "out.mp4", "-q", "20", "--two-pass"]).unwrap().to_spec();
+    assert_eq!(spec.rate_control, Some(RateControl::ConstantQuality(20.0)));
+    assert_eq!(spec.extra_args, vec!["--two-pass"]);
+
+    let spec = parse(&["-i", "in.mkv", "-o", "out.mp4", "-b", "6000", "-2", "--no-two-pass", "--no-decomb"])
+        .unwrap()
+        .to_spec();
+    assert!(spec.no_two_pass);
+    assert!(matches!(spec.rate_control, Some(RateControl::Bitrate { two_pass: false, .. })));
+    assert_eq!(spec.disabled_filters.len(), 1);
+    assert!(spec.extra_args.is_empty());
+}
+
+#[test]
+fn test_from_args_round_trip() {
+    let builder = JobBuilder::new("hb".into(), "in.mkv".into(), "out.mkv".into())
+        .preset("HQ 1080p30 Surround")
+        .rate_control(RateControl::Bitrate {
+            kbps: 4000,
+            two_pass: true,
+            turbo: true,
+        })
+        .subtitle_scan()
+        .time_range(Duration::from_secs(60), Duration::from_secs(30))
+        .max_width(1280)
+        .max_height(720)
+        .keep_display_aspect(true)
+        .modulus(2)
+        .pixel_aspect(64, 45)
+        .itu_par(true)
+        .extra_args(["--decomb"]);
+    let args = builder.build_args();
+    let parsed = JobBuilder::from_args("hb".into(), &args).unwrap();
+
+    assert_eq!(parsed.build_args(), args);
+}
+
+#[test]
+fn test_from_args_requires_input_and_output() {
+    assert!(matches!(parse(&["-i", "in.mkv"]), Err(Error::InvalidArgs { .. })));
+    assert!(matches!(parse(&["-o", "out.mp4"]), Err(Error::InvalidArgs { .. })));
+}
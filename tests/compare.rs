@@ -0,0 +1,54 @@
+//! Tests for scoring sample encodes with a fake `ffmpeg`.
+#![cfg(all(feature = "compare", feature = "test-util", unix))]
+
+mod common;
+
+use common::TempDir;
+use handbrake::testing::FakeHandBrake;
+use handbrake::{HandBrake, InputSource, Metric, OutputDestination};
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+fn install_ffmpeg(dir: &std::path::Path, stderr: &str) -> PathBuf {
+    let path = dir.join("ffmpeg");
+    std::fs::write(&path, format!("#!/bin/sh\nprintf '{}' >&2\n", stderr)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+#[tokio::test]
+async fn test_compare_qualities_scores_every_sample() {
+    let dir = TempDir::new("compare-scores");
+    let hb = HandBrake::new_with_path(FakeHandBrake::new().install(&dir).unwrap()).await.unwrap();
+    let ffmpeg = install_ffmpeg(&dir, "[Parsed_ssim_4 @ 0x1] SSIM Y:0.99 (20.1) All:0.975 (16.0)\\n");
+    let work_dir = dir.join("samples");
+
+    let scores = hb
+        .job(InputSource::from("in.mkv"), OutputDestination::from("out.mp4"))
+        .compare_qualities([20.0, 26.0])
+        .metric(Metric::Ssim)
+        .ffmpeg_path(ffmpeg)
+        .work_dir(&work_dir)
+        .run()
+        .await
+        .unwrap();
+    let scored: Vec<(f32, f64)> = scores.iter().map(|s| (s.quality, s.score)).collect();
+    assert_eq!(scored, vec![(20.0, 0.975), (26.0, 0.975)]);
+    assert!(!work_dir.exists(), "Samples should be removed");
+}
+
+#[tokio::test]
+async fn test_compare_qualities_requires_score() {
+    let dir = TempDir::new("compare-missing-score");
+    let hb = HandBrake::new_with_path(FakeHandBrake::new().install(&dir).unwrap()).await.unwrap();
+    let ffmpeg = install_ffmpeg(&dir, "no score\\n");
+
+    let result = hb
+        .job(InputSource::from("in.mkv"), OutputDestination::from("out.mp4"))
+        .compare_qualities([20.0])
+        .ffmpeg_path(ffmpeg)
+        .work_dir(dir.join("samples"))
+        .run()
+        .await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,156 @@
+//! Tests for parsing the JSON job configuration printed by `HandBrakeCLI`.
+
+use handbrake::{Config, PixelAspectRatio};
+use std::time::Duration;
+
+const JOB_JSON: &str = r#"{
+    "Audio": {
+        "AudioList": [
+            {
+                "Bitrate": 160,
+                "CompressionLevel": -1.0,
+                "DRC": 0.0,
+                "Encoder": "av_aac",
+                "Gain": 0.0,
+                "Mixdown": "stereo",
+                "Name": "Stereo",
+                "PresetEncoder": "aac",
+                "Quality": -3.0,
+                "Samplerate": 48000,
+                "Track": 0
+            }
+        ],
+        "FallbackEncoder": "av_aac"
+    },
+    "Destination": {
+        "ChapterList": [
+            {
+                "Duration": { "Hours": 0, "Minutes": 0, "Seconds": 2, "Ticks": 180000 },
+                "Name": "Chapter 1"
+            }
+        ],
+        "ChapterMarkers": true,
+        "File": "out.mp4",
+        "Mux": "av_mp4"
+    },
+    "Filters": {
+        "FilterList": [
+            { "ID": 4, "Settings": { "mode": 2 } },
+            { "ID": 11, "Settings": { "crop-top": 0, "height": 720, "width": 1280 } }
+        ]
+    },
+    "PAR": { "Den": 1, "Num": 1 },
+    "Source": { "Path": "in.mkv", "Title": 1 },
+    "Subtitle": {
+        "Search": { "Burn": true, "Default": false, "Enable": true, "Forced": true },
+        "SubtitleList": [
+            { "Burn": false, "Default": true, "Forced": false, "Offset": 0, "Track": 1 }
+        ]
+    },
+    "Video": {
+        "ColorMatrix": 1,
+        "ColorPrimaries": 1,
+        "ColorRange": 1,
+        "ColorTransfer": 1,
+        "ChromaLocation": 1,
+        "Encoder": "x264",
+        "Level": "4.0",
+        "MultiPass": false,
+        "Options": "",
+        "Preset": "fast",
+        "Profile": "main",
+        "Quality": 22.0,
+        "Tune": ""
+    }
+}"#;
+
+#[test]
+fn test_parse_full_config() {
+    let config: Config = serde_json::from_str(JOB_JSON).unwrap();
+
+    assert_eq!(config.dimensions(), Some((1280, 720)));
+    assert_eq!(config.par, Some(PixelAspectRatio { num: 1, den: 1 }));
+
+    let chapters = &config.destination.chapter_list;
+    assert_eq!(chapters.len(), 1);
+    assert_eq!(chapters[0].name.as_deref(), Some("Chapter 1"));
+    assert_eq!(chapters[0].duration, Some(Duration::from_secs(2)));
+    assert_eq!(config.chapter_at(Duration::from_secs(1)).map(|(index, _)| index), Some(0));
+    assert!(config.chapter_at(Duration::from_secs(2)).is_none());
+
+    let filters = config.filters.as_ref().unwrap();
+    assert_eq!(filters.filter_list.iter().map(|f| f.id).collect::<Vec<_>>(), vec![4, 11]);
+
+    let subtitle = config.subtitle.as_ref().unwrap();
+    assert!(subtitle.search.as_ref().unwrap().enable);
+    assert_eq!(subtitle.subtitle_list[0].track, 1);
+    assert!(subtitle.subtitle_list[0].default);
+
+    let audio = &config.audio_config.audio_list[0];
+    assert_eq!(audio.encoder_name, "aac");
+    assert_eq!(audio.encoder.as_deref(), Some("av_aac"));
+    assert_eq!(audio.mixdown.as_deref(), Some("stereo"));
+    assert_eq!(audio.samplerate, Some(48000));
+
+    assert_eq!(config.video.profile.as_deref(), Some("main"));
+    assert_eq!(config.video.color_primaries, Some(1));
+}
+
+#[test]
+fn test_parse_minimal_config() {
+    let config: Config = serde_json::from_str(
+        r#"{
+            "Audio": { "AudioList": [] },
+            "Destination": { "File": "out.mp4", "Mux": "av_mp4" },
+            "Source": { "Path": "in.mkv", "Title": 1 },
+            "Video": { "Encoder": "x264", "Quality": 22.0 }
+        }"#,
+    )
+    .unwrap();
+
+    assert!(config.subtitle.is_none());
+    assert!(config.destination.chapter_list.is_empty());
+    assert!(config.chapter_at(Duration::ZERO).is_none());
+    assert_eq!(config.dimensions(), None);
+}
+
+#[test]
+fn test_parse_config_from_other_versions() {
+    let config: Config = serde_json::from_str(
+        r#"{
+            "Destination": { "File": "out.mkv", "NewOption": true },
+            "Source": { "Path": "in.mkv" },
+            "Video": { "Encoder": "svt_av1" },
+            "FutureSection": { "Enabled": true }
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.destination.mux, "");
+    assert_eq!(config.source.title, 0);
+    assert_eq!(config.video.encoder, "svt_av1");
+    assert_eq!(config.video.quality, 0.0);
+    assert!(config.audio_config.audio_list.is_empty());
+    assert_eq!(config.raw["FutureSection"]["Enabled"], true);
+}
+
+#[test]
+fn test_chapter_at_position() {
+    let config: Config = serde_json::from_str(
+        r#"{
+            "Destination": { "ChapterList": [
+                { "Duration": { "Ticks": 900000 }, "Name": "Intro" },
+                { "Duration": { "Ticks": 2700000 }, "Name": "Act 1" },
+                { "Name": "Credits" }
+            ] }
+        }"#,
+    )
+    .unwrap();
+
+    let name = |secs| config.chapter_at(Duration::from_secs(secs)).and_then(|(_, c)| c.name.as_deref());
+    assert_eq!(name(0), Some("Intro"));
+    assert_eq!(name(10), Some("Act 1"));
+    assert_eq!(name(39), Some("Act 1"));
+    // The last chapter has no duration, so its end is unknown.
+    assert_eq!(name(40), None);
+}
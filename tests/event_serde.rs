@@ -0,0 +1,69 @@
+//! Tests for the `Serialize` implementations of the event types.
+#![cfg(feature = "serde")]
+
+use handbrake::{FailureKind, JobEvent, JobFailure, Log, Phase, Progress};
+use serde_json::json;
+use std::time::Duration;
+
+#[test]
+fn test_serialize_progress_event() {
+    let event = JobEvent::Progress(Progress {
+        percentage: 42.5,
+        fps: 30.0,
+        avg_fps: None,
+        eta: Some(Duration::from_secs(90)),
+        position: Some(Duration::from_secs(30)),
+        phase: Phase::Encoding,
+    });
+    assert_eq!(
+        serde_json::to_value(&event).unwrap(),
+        json!({
+            "Progress": {
+                "percentage": 42.5,
+                "fps": 30.0,
+                "avg_fps": null,
+                "eta": { "secs": 90, "nanos": 0 },
+                "position": { "secs": 30, "nanos": 0 },
+                "phase": "Encoding"
+            }
+        })
+    );
+}
+
+#[test]
+fn test_serialize_log_event() {
+    let event = JobEvent::Log(Log {
+        message: "Starting work".to_string(),
+    });
+    assert_eq!(
+        serde_json::to_value(&event).unwrap(),
+        json!({ "Log": { "message": "Starting work" } })
+    );
+}
+
+#[test]
+fn test_serialize_done_failure() {
+    let event = JobEvent::Done(Err(JobFailure {
+        message: "Job timed out".to_string(),
+        exit_code: Some(1),
+        kind: FailureKind::Timeout,
+    }));
+    assert_eq!(
+        serde_json::to_value(&event).unwrap(),
+        json!({
+            "Done": { "Err": { "message": "Job timed out", "exit_code": 1, "kind": "Timeout" } }
+        })
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_serialize_done_success() {
+    use std::os::unix::process::ExitStatusExt;
+
+    let event = JobEvent::Done(Ok(std::process::ExitStatus::from_raw(0)));
+    assert_eq!(
+        serde_json::to_value(&event).unwrap(),
+        json!({ "Done": { "Ok": { "success": true, "exit_code": 0 } } })
+    );
+}
@@ -0,0 +1,354 @@
+//! Tests for the scriptable fake `HandBrakeCLI`.
+#![cfg(all(feature = "test-util", unix))]
+
+mod common;
+
+use common::TempDir;
+use futures::StreamExt;
+use handbrake::job::PreserveHdrMetadata;
+use handbrake::testing::{FakeHandBrake, Transcript};
+use handbrake::{Error, HandBrake, InputSource, JobEvent, LogLevel, OutputDestination, Support};
+use std::time::Duration;
+
+
+#[tokio::test]
+async fn test_fake_handbrake_replays_steps() {
+    let dir = TempDir::new("fake-steps");
+    let path = FakeHandBrake::new()
+        .version("HandBrake 1.7.3")
+        .progress(10.0)
+        .progress_with_rate(55.5, 30.0, 29.5, Duration::from_secs(61))
+        .log("It's working")
+        .exit_code(0)
+        .install(&dir)
+        .unwrap();
+
+    let hb = HandBrake::new_with_path(&path).await.expect("Fake should validate");
+    assert_eq!(hb.version(), "HandBrake 1.7.3");
+
+    let mut handle = hb
+        .job(InputSource::from("in.mkv"), OutputDestination::from("out.mp4"))
+        .start()
+        .unwrap();
+    let mut progress = Vec::new();
+    let mut logs = Vec::new();
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::Progress(p) => progress.push((p.percentage, p.avg_fps, p.eta)),
+            JobEvent::Log(log) => logs.push(log.message),
+            JobEvent::Done(result) => {
+                assert!(result.unwrap().success());
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    assert_eq!(
+        progress,
+        vec![
+            (10.0, None, None),
+            (55.5, Some(29.5), Some(Duration::from_secs(61))),
+        ]
+    );
+    assert_eq!(logs, vec!["It's working".to_string()]);
+}
+
+#[tokio::test]
+async fn test_fake_handbrake_exit_code() {
+    let dir = TempDir::new("fake-exit");
+    let path = FakeHandBrake::new().exit_code(3).install(&dir).unwrap();
+
+    let hb = HandBrake::new_with_path(&path).await.unwrap();
+    let failure = hb
+        .job(InputSource::from("in.mkv"), OutputDestination::from("out.mp4"))
+        .start()
+        .unwrap()
+        .wait()
+        .await
+        .expect_err("Job should fail");
+    assert_eq!(failure.exit_code, Some(3));
+}
+
+#[tokio::test]
+async fn test_transcript_replay() {
+    let transcript: Transcript = serde_json::from_str(
+        r#"{
+            "exit_code": 0,
+            "entries": [
+                { "offset_ms": 0, "stream": "stderr", "data": "Starting work\n" },
+                { "offset_ms": 50, "stream": "stdout", "data": "Encoding: task 1 of 1, 42.00 %\r" },
+                { "offset_ms": 100, "stream": "stdout", "data": "Encoding: task 1 of 1, 99.00 %\r" },
+                { "offset_ms": 150, "stream": "stderr", "data": "Encode done!\n" }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let dir = TempDir::new("replay");
+    let mut handle = transcript.replay(&dir).expect("Replay should start");
+    let mut seen = Vec::new();
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::Progress(p) => seen.push(format!("{:.2}", p.percentage)),
+            JobEvent::Log(log) => seen.push(log.message),
+            JobEvent::Done(result) => {
+                assert!(result.unwrap().success());
+                break;
+            }
+            _ => {}
+        }
+    }
+
+    assert_eq!(seen, vec!["Starting work", "42.00", "99.00", "Encode done!"]);
+}
+
+#[tokio::test]
+async fn test_raw_lines() {
+    let dir = TempDir::new("raw-lines");
+    let mut handle = FakeHandBrake::new()
+        .log("Starting work")
+        .log("json job:")
+        .log("{")
+        .log("}")
+        .progress(50.0)
+        .log("Encode done!")
+        .start(&dir)
+        .expect("Fake should start");
+
+    let lines = handle.raw_lines();
+    let (lines, summary) = tokio::join!(lines.collect::<Vec<_>>(), handle.wait());
+
+    assert!(summary.is_ok());
+    assert_eq!(lines, vec!["Starting work", "json job:", "{", "}", "Encode done!"]);
+}
+
+#[tokio::test]
+async fn test_log_file() {
+    let dir = TempDir::new("log-file");
+    let path = FakeHandBrake::new()
+        .log("Starting work")
+        .delay(Duration::from_millis(50))
+        .progress(50.0)
+        .delay(Duration::from_millis(50))
+        .log("Encode done!")
+        .install(&dir)
+        .unwrap();
+    let log_path = dir.join("job.log");
+
+    let hb = HandBrake::new_with_path(&path).await.unwrap();
+    let summary = hb
+        .job(InputSource::from("in.mkv"), OutputDestination::from("out.mp4"))
+        .log_file(&log_path, LogLevel::Events)
+        .start()
+        .unwrap()
+        .wait()
+        .await;
+    let log = std::fs::read_to_string(&log_path).unwrap();
+
+    assert!(summary.is_ok());
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], "Starting work");
+    assert!(lines[1].starts_with("[event] Progress(Progress { percentage: 50.0"));
+    assert_eq!(lines[2], "Encode done!");
+    assert!(lines[3].starts_with("[event] Done(Ok("));
+}
+
+#[tokio::test]
+async fn test_timestamped_events() {
+    let dir = TempDir::new("timestamps");
+    let mut handle = FakeHandBrake::new()
+        .log("Starting work")
+        .delay(Duration::from_millis(200))
+        .log("Encode done!")
+        .start(&dir)
+        .expect("Fake should start");
+
+    let events: Vec<_> = handle.timestamped_events().collect().await;
+
+    assert_eq!(events.len(), 3);
+    assert!(matches!(events[2].event, JobEvent::Done(_)));
+    assert!(events.windows(2).all(|w| w[0].at <= w[1].at));
+    assert!(events[1].since_start - events[0].since_start >= Duration::from_millis(150));
+}
+
+#[tokio::test]
+async fn test_estimate_duration() {
+    let dir = TempDir::new("estimate-duration");
+    let path = FakeHandBrake::new()
+        .log("+ title 1:")
+        .log("  + duration: 00:10:00")
+        .log("  + size: 1920x1080, pixel aspect: 1/1, display aspect: 1.78, 25.000 fps")
+        .progress_with_rate(50.0, 48.0, 50.0, Duration::from_secs(30))
+        .install(&dir)
+        .unwrap();
+    let hb = HandBrake::new_with_path(&path).await.unwrap();
+
+    let job = hb.job(InputSource::from("in.mkv"), OutputDestination::from("out.mp4"));
+    // 10 minutes at 25 fps are 15000 frames, encoded at 50 fps.
+    assert_eq!(job.estimate_duration().await.unwrap(), Duration::from_secs(300));
+
+    let piped = hb.job(InputSource::Stdin, OutputDestination::from("out.mp4"));
+    assert!(piped.estimate_duration().await.is_err());
+}
+
+#[tokio::test]
+async fn test_estimate_size() {
+    let dir = TempDir::new("estimate-size");
+    let path = FakeHandBrake::new()
+        .log("  + duration: 00:10:00")
+        .stdout("0123456789")
+        .install(&dir)
+        .unwrap();
+    let hb = HandBrake::new_with_path(&path).await.unwrap();
+
+    let job = hb.job(InputSource::from("in.mkv"), OutputDestination::from("out.mp4"));
+    // The one-minute sample is a tenth of the source.
+    assert_eq!(job.estimate_size().await.unwrap(), 100);
+}
+
+#[tokio::test]
+async fn test_estimate_size_of_selected_part() {
+    let dir = TempDir::new("estimate-size-selected");
+    let path = FakeHandBrake::new()
+        .log("  + duration: 00:10:00")
+        .log("  + chapters:")
+        .log("    + 1: duration 00:04:00")
+        .log("    + 2: duration 00:03:00")
+        .log("    + 3: duration 00:03:00")
+        .stdout("0123456789")
+        .install(&dir)
+        .unwrap();
+    let hb = HandBrake::new_with_path(&path).await.unwrap();
+
+    let job = hb.job(InputSource::from("in.mkv"), OutputDestination::from("out.mp4"));
+    let range = job.clone().time_range(Duration::from_secs(60), Duration::from_secs(120));
+    assert_eq!(range.estimate_size().await.unwrap(), 20);
+    // The range is cut off at the end of the title.
+    let range = job.clone().time_range(Duration::from_secs(570), Duration::from_secs(120));
+    assert_eq!(range.estimate_size().await.unwrap(), 5);
+    let chapters = job.clone().chapters(2..=3);
+    assert_eq!(chapters.estimate_size().await.unwrap(), 60);
+    let missing = job.chapters(4..=4);
+    assert!(matches!(missing.estimate_size().await, Err(Error::EstimationFailed { .. })));
+}
+
+#[tokio::test]
+async fn test_target_size_sets_quality() {
+    let dir = TempDir::new("target-size");
+    let path = FakeHandBrake::new()
+        .log("  + duration: 00:10:00")
+        .stdout("0123456789")
+        .install(&dir)
+        .unwrap();
+    let hb = HandBrake::new_with_path(&path).await.unwrap();
+
+    let job = hb
+        .job(InputSource::from("in.mkv"), OutputDestination::from("out.mp4"))
+        .target_size(100)
+        .await
+        .unwrap();
+    assert_eq!(job.build_args()[4..], ["--quality", "18"]);
+}
+
+#[tokio::test]
+async fn test_hdr_metadata_requires_recent_version() {
+    let dir = TempDir::new("hdr-version");
+    let path = FakeHandBrake::new().version("HandBrake 1.6.1").install(&dir).unwrap();
+    let hb = HandBrake::new_with_path(&path).await.unwrap();
+
+    let job = hb
+        .job(InputSource::from("in.mkv"), OutputDestination::from("out.mp4"))
+        .preserve_hdr_metadata(Some(PreserveHdrMetadata::DolbyVision));
+    let err = job.start().unwrap_err();
+    assert!(matches!(err, Error::UnsupportedOption { ref required, .. } if required == "1.7.0"));
+
+    let job = hb.job(InputSource::from("in.mkv"), OutputDestination::from("out.mp4"));
+    assert!(job.start().unwrap().wait().await.is_ok());
+}
+
+#[tokio::test]
+async fn test_untested_version_warning() {
+    let dir = TempDir::new("untested-version");
+    let path = FakeHandBrake::new().version("HandBrake 1.4.2").log("It's working").install(&dir).unwrap();
+    let hb = HandBrake::new_with_path(&path).await.unwrap();
+    assert_eq!(hb.support(), Support::Untested);
+
+    let mut handle = hb
+        .job(InputSource::from("in.mkv"), OutputDestination::from("out.mp4"))
+        .start()
+        .unwrap();
+    let mut logs = Vec::new();
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::Log(log) => logs.push(log.message),
+            JobEvent::Done(_) => break,
+            _ => {}
+        }
+    }
+    assert_eq!(logs.len(), 2);
+    assert!(logs[0].starts_with("HandBrake 1.4.2 is untested"));
+    assert_eq!(logs[1], "It's working");
+}
+
+#[tokio::test]
+async fn test_shutdown_cancels_running_jobs() {
+    let dir = TempDir::new("shutdown");
+    let path = FakeHandBrake::new().install(&dir).unwrap();
+    let hb = HandBrake::new_with_path(&path).await.unwrap();
+    // Replace the installed fake with one that keeps running until interrupted.
+    std::fs::write(&path, "#!/bin/sh\nexec sleep 5\n").unwrap();
+
+    let first = hb.job(InputSource::from("a.mkv"), OutputDestination::from("a.mp4")).start().unwrap();
+    let second = hb.job(InputSource::from("b.mkv"), OutputDestination::from("b.mp4")).start().unwrap();
+
+    let started = std::time::Instant::now();
+    hb.shutdown(Duration::from_secs(2)).await;
+    assert!(started.elapsed() < Duration::from_secs(2));
+
+    assert!(first.wait().await.is_err());
+    assert!(second.wait().await.is_err());
+}
+
+#[tokio::test]
+async fn test_shutdown_cancels_batch_jobs() {
+    let dir = TempDir::new("shutdown-batch");
+    let path = FakeHandBrake::new().install(&dir).unwrap();
+    let hb = HandBrake::new_with_path(&path).await.unwrap();
+    std::fs::write(&path, "#!/bin/sh\nexec sleep 5\n").unwrap();
+
+    let handles = hb.batch(["a.mkv", "b.mkv"]).output_dir(&*dir).start();
+
+    let started = std::time::Instant::now();
+    hb.shutdown(Duration::from_secs(2)).await;
+    assert!(started.elapsed() < Duration::from_secs(2));
+
+    for handle in handles {
+        assert!(handle.unwrap().wait().await.is_err());
+    }
+}
+
+#[cfg(feature = "segmented")]
+#[tokio::test]
+async fn test_shutdown_cancels_segmented_jobs() {
+    let dir = TempDir::new("shutdown-segmented");
+    let path = FakeHandBrake::new().install(&dir).unwrap();
+    let hb = HandBrake::new_with_path(&path).await.unwrap();
+    std::fs::write(&path, "#!/bin/sh\nexec sleep 5\n").unwrap();
+
+    let encode = hb
+        .job(InputSource::from("in.mkv"), OutputDestination::from(dir.join("movie.mkv")))
+        .segmented()
+        .chapter_segments(1..=2, 1)
+        .ffmpeg_path("true")
+        .run();
+    let encode = tokio::spawn(encode);
+    // Give the segments time to start.
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let started = std::time::Instant::now();
+    hb.shutdown(Duration::from_secs(2)).await;
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert!(encode.await.unwrap().is_err());
+}
@@ -0,0 +1,112 @@
+use handbrake::{Error, JobBuilder};
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+fn import(json: &str) -> Result<Vec<JobBuilder>, Error> {
+    JobBuilder::from_gui_queue(PathBuf::from("/usr/bin/HandBrakeCLI"), json)
+}
+
+#[test]
+fn test_import_gui_queue_maps_job_settings() {
+    let json = r#"[
+        {
+            "Job": {
+                "Source": { "Path": "/videos/movie.mkv", "Title": 1 },
+                "Destination": { "File": "/encoded/movie.mkv", "Mux": "av_mkv" },
+                "Video": { "Encoder": "x265", "Quality": 20.0, "Options": "" },
+                "Audio": { "AudioList": [{ "Track": 0, "Encoder": "av_aac", "Bitrate": 160 }] },
+                "Subtitle": {
+                    "SubtitleList": [{ "Track": 1, "Burn": false, "Default": true, "Forced": true }]
+                },
+                "Filters": {
+                    "FilterList": [
+                        { "ID": 4, "Settings": { "mode": 2 } },
+                        { "ID": 11, "Settings": { "width": 1920, "height": 1080 } }
+                    ]
+                }
+            },
+            "uiSettings": {}
+        }
+    ]"#;
+
+    let jobs = import(json).expect("Queue should parse");
+    assert_eq!(jobs.len(), 1);
+    assert_eq!(
+        jobs[0].build_args(),
+        vec![
+            "-i", "/videos/movie.mkv", "-o", "/encoded/movie.mkv",
+            "--encoder", "x265",
+            "--width", "1920", "--height", "1080",
+            "--audio", "1,av_aac",
+            "--quality", "20",
+            "--format", "av_mkv",
+            "--subtitle", "2",
+            "--subtitle-forced", "2",
+            "--subtitle-default", "2",
+        ]
+    );
+}
+
+#[test]
+fn test_import_gui_queue_bare_entries() {
+    let json = r#"[
+        {
+            "Source": { "Path": "a.mkv" },
+            "Destination": { "File": "a.mp4", "Mux": 2 },
+            "Video": { "Encoder": "x264", "Quality": -1.0 },
+            "Subtitle": { "SubtitleList": [{ "Track": -1, "Burn": true }] }
+        },
+        { "Source": { "Path": "b.mkv" }, "Destination": { "File": "b.mp4" } }
+    ]"#;
+
+    let jobs = import(json).expect("Queue should parse");
+    let args: Vec<Vec<OsString>> = jobs.iter().map(|job| job.build_args()).collect();
+    assert_eq!(
+        args,
+        vec![
+            vec![
+                "-i", "a.mkv", "-o", "a.mp4",
+                "--encoder", "x264",
+                "--subtitle", "scan",
+                "--subtitle-burned", "native",
+            ],
+            vec!["-i", "b.mkv", "-o", "b.mp4"],
+        ]
+    );
+}
+
+#[test]
+fn test_import_gui_queue_invalid_json() {
+    let result = import(r#"{ "Job": {} }"#);
+    assert!(matches!(result, Err(Error::InvalidGuiQueue { .. })));
+}
+
+#[test]
+fn test_gui_queue_entry_round_trip() {
+    let job = JobBuilder::new(
+        PathBuf::from("/usr/bin/HandBrakeCLI"),
+        "/videos/movie.mkv".into(),
+        "/encoded/movie.mp4".into(),
+    )
+    .format("av_mp4")
+    .video_codec("x264")
+    .quality(22.0)
+    .width(1280)
+    .height(720)
+    .audio_codec(2, "av_aac")
+    .subtitle(1)
+    .subtitle(3)
+    .subtitle_forced(3)
+    .subtitle_default(handbrake::job::SubtitleDefaultMode::Track(1));
+
+    let entry = job.to_gui_queue_entry();
+    assert_eq!(entry["Job"]["Source"]["Path"], "/videos/movie.mkv");
+    assert_eq!(entry["Job"]["Destination"]["Mux"], "av_mp4");
+    assert_eq!(entry["Job"]["Audio"]["AudioList"][0]["Track"], 1);
+    assert_eq!(entry["Job"]["Subtitle"]["SubtitleList"][1]["Forced"], true);
+
+    let queue = serde_json::to_string(&vec![entry]).unwrap();
+    let imported = import(&queue).expect("Exported queue should parse");
+    assert_eq!(imported.len(), 1);
+    assert_eq!(imported[0].build_args(), job.build_args());
+}
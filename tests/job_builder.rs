@@ -1,4 +1,4 @@
-use handbrake::{job::{SubtitleBurnMode, SubtitleDefaultMode}, InputSource, JobBuilder, OutputDestination};
+use handbrake::{job::{CombDetect, Filter, Mixdown, PreserveHdrMetadata, SubtitleBurnMode, SubtitleDefaultMode}, InputSource, JobBuilder, OutputDestination, RateControl};
 use std::path::PathBuf;
 
 #[test]
@@ -28,6 +28,18 @@ fn test_job_builder_basic_args_stdin_to_stdout() {
     assert_eq!(args, vec!["-i", "pipe:0", "-o", "pipe:1",]);
 }
 
+#[test]
+fn test_job_builder_basic_args_device_to_file() {
+    let handbrake_path = PathBuf::from("/usr/bin/HandBrakeCLI");
+    let input = InputSource::Device(PathBuf::from("/dev/sr0"));
+    let output = OutputDestination::File(PathBuf::from("movie.mkv"));
+
+    let builder = JobBuilder::new(handbrake_path, input, output);
+    let args = builder.build_args();
+
+    assert_eq!(args, vec!["-i", "/dev/sr0", "-o", "movie.mkv",]);
+}
+
 #[test]
 fn test_job_builder_with_preset() {
     let handbrake_path = PathBuf::from("/usr/bin/HandBrakeCLI");
@@ -122,6 +134,37 @@ fn test_job_builder_with_multiple_audio_codecs() {
     );
 }
 
+#[test]
+fn test_audio_mixdown_and_normalize_mix() {
+    let builder = JobBuilder::new("hb".into(), "in.mkv".into(), "out.mp4".into())
+        .audio_codec(1, "av_aac")
+        .audio_codec(2, "ac3")
+        .audio_codec(3, "opus")
+        .audio_mixdown(2, Mixdown::FivePointOne)
+        .normalize_mix(1, false)
+        .normalize_mix(1, true);
+    let args = builder.build_args();
+
+    // Lists stop at the last track with a value, and fill earlier gaps.
+    assert_eq!(
+        &args[10..],
+        ["--mixdown", ",5point1", "--normalize-mix", "1"]
+    );
+}
+
+#[test]
+fn test_audio_quality() {
+    let builder = JobBuilder::new("hb".into(), "in.mkv".into(), "out.mkv".into())
+        .audio_codec(1, "av_aac")
+        .audio_codec(2, "vorbis")
+        .audio_quality(2, 5.0)
+        .audio_quality(2, 6.5);
+    let args = builder.build_args();
+
+    // The first track keeps its bitrate.
+    assert_eq!(&args[8..], ["--aq", ",6.5"]);
+}
+
 #[test]
 fn test_job_builder_last_call_wins_preset() {
     let handbrake_path = PathBuf::from("/usr/bin/HandBrakeCLI");
@@ -385,6 +428,18 @@ fn test_subtitle_lang_list() {
     );
 }
 
+#[test]
+fn test_native_language_and_dub() {
+    let builder = JobBuilder::new("hb".into(), "in.mkv".into(), "out.mp4".into())
+        .subtitle_scan()
+        .native_language("eng")
+        .native_dub(true);
+    assert_eq!(
+        builder.build_args(),
+        vec!["-i", "in.mkv", "-o", "out.mp4", "--subtitle", "scan", "--native-language", "eng", "--native-dub"]
+    );
+}
+
 #[test]
 fn test_subtitle_burned() {
     let builder = JobBuilder::new(
@@ -543,3 +598,307 @@ fn test_all_subtitle_options() {
         ]
     );
 }
+
+#[test]
+fn test_sample() {
+    let builder = JobBuilder::new(
+        "hb".into(),
+        "in.mkv".into(),
+        "out.mp4".into(),
+    )
+    .sample(std::time::Duration::from_millis(12_500));
+    assert_eq!(
+        builder.build_args(),
+        vec![
+            "-i",
+            "in.mkv",
+            "-o",
+            "out.mp4",
+            "--start-at-preview",
+            "5",
+            "--stop-at",
+            "duration:12.5",
+        ]
+    );
+}
+
+#[test]
+fn test_disable_filters_and_two_pass() {
+    let builder = JobBuilder::new("hb".into(), "in.mkv".into(), "out.mkv".into())
+        .preset("HQ 1080p30 Surround")
+        .rate_control(RateControl::Bitrate {
+            kbps: 6000,
+            two_pass: true,
+            turbo: true,
+        })
+        .no_two_pass()
+        .no_deinterlace()
+        .no_comb_detect()
+        .disable_filter(Filter::Nlmeans);
+    assert_eq!(
+        builder.build_args(),
+        vec![
+            "-i", "in.mkv", "-o", "out.mkv", "--preset", "HQ 1080p30 Surround", "--no-comb-detect",
+            "--no-deinterlace", "--no-nlmeans", "--vb", "6000", "--no-two-pass",
+        ]
+    );
+}
+
+#[test]
+fn test_comb_detect() {
+    let builder = JobBuilder::new("hb".into(), "in.mkv".into(), "out.mkv".into()).comb_detect(CombDetect::Default);
+    assert_eq!(builder.build_args(), vec!["-i", "in.mkv", "-o", "out.mkv", "--comb-detect"]);
+
+    let builder = builder.comb_detect(CombDetect::Custom("mode=3:spatial-metric=2".to_string()));
+    assert_eq!(
+        builder.build_args(),
+        vec!["-i", "in.mkv", "-o", "out.mkv", "--comb-detect=mode=3:spatial-metric=2"]
+    );
+
+    let builder = builder.no_comb_detect();
+    assert_eq!(builder.build_args(), vec!["-i", "in.mkv", "-o", "out.mkv", "--no-comb-detect"]);
+
+    let builder = builder.comb_detect(CombDetect::Permissive);
+    assert_eq!(builder.build_args(), vec!["-i", "in.mkv", "-o", "out.mkv", "--comb-detect=permissive"]);
+}
+
+#[test]
+fn test_encopts() {
+    let builder = JobBuilder::new("hb".into(), "in.mkv".into(), "out.mkv".into())
+        .video_codec("x265")
+        .encopts("aq-mode=3:psy-rd=1.0")
+        .encopt("aq-mode", 2)
+        .encopt("tune", "grain");
+    assert_eq!(
+        builder.build_args(),
+        vec!["-i", "in.mkv", "-o", "out.mkv", "--encoder", "x265", "--encopts", "psy-rd=1.0:aq-mode=2:tune=grain"]
+    );
+
+    let builder = builder.encopts("scm=0");
+    assert_eq!(
+        builder.build_args(),
+        vec!["-i", "in.mkv", "-o", "out.mkv", "--encoder", "x265", "--encopts", "scm=0"]
+    );
+}
+
+#[test]
+fn test_video_bitrate_replaces_quality() {
+    let builder = JobBuilder::new("hb".into(), "in.mkv".into(), "out.mp4".into())
+        .quality(20.0)
+        .video_bitrate(2500);
+    assert_eq!(
+        builder.build_args(),
+        vec!["-i", "in.mkv", "-o", "out.mp4", "--vb", "2500"]
+    );
+
+    let builder = builder.quality(20.0);
+    assert_eq!(
+        builder.build_args(),
+        vec!["-i", "in.mkv", "-o", "out.mp4", "--quality", "20"]
+    );
+}
+
+#[test]
+fn test_rate_control_multi_pass() {
+    let builder = JobBuilder::new("hb".into(), "in.mkv".into(), "out.mp4".into())
+        .quality(20.0)
+        .rate_control(RateControl::Bitrate {
+            kbps: 6000,
+            two_pass: true,
+            turbo: true,
+        });
+    assert_eq!(
+        builder.build_args(),
+        vec!["-i", "in.mkv", "-o", "out.mp4", "--vb", "6000", "--two-pass", "--turbo"]
+    );
+
+    // Turbo only speeds up the first of two passes.
+    let builder = builder.rate_control(RateControl::Bitrate {
+        kbps: 6000,
+        two_pass: false,
+        turbo: true,
+    });
+    assert_eq!(
+        builder.build_args(),
+        vec!["-i", "in.mkv", "-o", "out.mp4", "--vb", "6000"]
+    );
+
+    let builder = builder.rate_control(RateControl::ConstantQuality(22.0));
+    assert_eq!(
+        builder.build_args(),
+        vec!["-i", "in.mkv", "-o", "out.mp4", "--quality", "22"]
+    );
+}
+
+#[test]
+fn test_chapters_and_time_range() {
+    let builder = JobBuilder::new("hb".into(), "in.mkv".into(), "out.mp4".into())
+        .chapters(3..=3)
+        .sample(std::time::Duration::from_secs(30))
+        .time_range(std::time::Duration::from_secs(60), std::time::Duration::from_millis(1500));
+    assert_eq!(
+        builder.build_args(),
+        vec![
+            "-i",
+            "in.mkv",
+            "-o",
+            "out.mp4",
+            "--chapters",
+            "3",
+            "--start-at",
+            "duration:60",
+            "--stop-at",
+            "duration:1.5",
+        ]
+    );
+}
+
+#[test]
+fn test_angle() {
+    let builder = JobBuilder::new("hb".into(), "/dev/sr0".into(), "out.mkv".into())
+        .angle(1)
+        .angle(2);
+    assert_eq!(
+        builder.build_args(),
+        vec!["-i", "/dev/sr0", "-o", "out.mkv", "--angle", "2"]
+    );
+}
+
+#[test]
+fn test_dvdnav() {
+    let builder = JobBuilder::new("hb".into(), "/dev/sr0".into(), "out.mkv".into()).dvdnav(false);
+    assert_eq!(
+        builder.build_args(),
+        vec!["-i", "/dev/sr0", "-o", "out.mkv", "--no-dvdnav"]
+    );
+
+    let builder = builder.dvdnav(true);
+    assert_eq!(builder.build_args(), vec!["-i", "/dev/sr0", "-o", "out.mkv"]);
+}
+
+#[test]
+fn test_verbose_and_json_output() {
+    let builder = JobBuilder::new("hb".into(), "in.mkv".into(), "out.mp4".into())
+        .verbose(7)
+        .json_output(true);
+    assert_eq!(
+        builder.build_args(),
+        vec!["--verbose=3", "--json", "-i", "in.mkv", "-o", "out.mp4"]
+    );
+}
+
+#[test]
+fn test_current_dir_keeps_relative_args() {
+    let builder = JobBuilder::new("hb".into(), "in.mkv".into(), "out.mp4".into()).current_dir("/videos");
+    assert_eq!(builder.build_args(), vec!["-i", "in.mkv", "-o", "out.mp4"]);
+    assert_eq!(builder.to_spec().current_dir, Some(PathBuf::from("/videos")));
+}
+
+#[test]
+fn test_clone_as_template() {
+    let template = JobBuilder::new("hb".into(), InputSource::Stdin, OutputDestination::Stdout)
+        .preset("Fast 1080p30")
+        .quality(22.0);
+    let first = template.clone().input("e01.mkv").output("e01.mp4");
+    let second = template.input(String::from("e02.mkv")).output(PathBuf::from("e02.mp4"));
+
+    assert_eq!(
+        first.build_args(),
+        vec!["-i", "e01.mkv", "-o", "e01.mp4", "--preset", "Fast 1080p30", "--quality", "22"]
+    );
+    assert_eq!(
+        second.build_args(),
+        vec!["-i", "e02.mkv", "-o", "e02.mp4", "--preset", "Fast 1080p30", "--quality", "22"]
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_display_quotes_command_line() {
+    let builder = JobBuilder::new(
+        "/usr/bin/HandBrakeCLI".into(),
+        "My Movie's Cut.mkv".into(),
+        "out.mp4".into(),
+    )
+    .preset("Fast 1080p30");
+    assert_eq!(
+        builder.to_string(),
+        r"/usr/bin/HandBrakeCLI -i 'My Movie'\''s Cut.mkv' -o out.mp4 --preset 'Fast 1080p30'"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn test_non_utf8_paths_are_kept() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let input = PathBuf::from(OsStr::from_bytes(b"caf\xe9.mkv"));
+    let builder = JobBuilder::new("hb".into(), input.clone().into(), "out.mp4".into());
+    let args = builder.build_args();
+    assert_eq!(args[1], input.as_os_str());
+
+    let parsed = JobBuilder::from_args("hb".into(), &args).unwrap();
+    assert_eq!(parsed.to_spec().input, InputSource::File(input));
+}
+
+#[test]
+fn test_max_dimensions_and_modulus() {
+    let builder = JobBuilder::new("hb".into(), "in.mkv".into(), "out.mp4".into())
+        .max_width(1920)
+        .max_height(1080)
+        .keep_display_aspect(false)
+        .modulus(16);
+    assert_eq!(
+        builder.build_args(),
+        vec![
+            "-i", "in.mkv", "-o", "out.mp4", "--maxWidth", "1920", "--maxHeight", "1080",
+            "--no-keep-display-aspect", "--modulus", "16",
+        ]
+    );
+
+    let builder = builder.keep_display_aspect(true);
+    assert!(builder.build_args().contains(&"--keep-display-aspect".into()));
+}
+
+#[test]
+fn test_anamorphic_overrides() {
+    let builder = JobBuilder::new("hb".into(), "in.mkv".into(), "out.mp4".into())
+        .display_width(1024)
+        .pixel_aspect(64, 45)
+        .itu_par(true);
+    assert_eq!(
+        builder.build_args(),
+        vec![
+            "-i", "in.mkv", "-o", "out.mp4", "--display-width", "1024", "--pixel-aspect", "64:45",
+            "--itu-par",
+        ]
+    );
+}
+
+#[test]
+fn test_color_overrides() {
+    use handbrake::job::{ColorMatrix, ColorPrimaries, ColorRange, ColorTransfer};
+
+    let builder = JobBuilder::new("hb".into(), "in.mkv".into(), "out.mp4".into())
+        .color_matrix(ColorMatrix::Bt709)
+        .color_range(ColorRange::Limited)
+        .color_transfer(ColorTransfer::Bt709);
+    assert_eq!(
+        builder.build_args(),
+        vec!["-i", "in.mkv", "-o", "out.mp4", "--color-matrix", "709", "--colorspace=range=limited:transfer=bt709"]
+    );
+
+    let builder = builder.color_primaries(ColorPrimaries::Bt470bg);
+    assert_eq!(
+        builder.build_args()[6],
+        "--colorspace=range=limited:primaries=bt470bg:transfer=bt709"
+    );
+}
+
+#[test]
+fn test_hdr_dynamic_metadata_disabled() {
+    let builder = JobBuilder::new("hb".into(), "in.mkv".into(), "out.mkv".into())
+        .preserve_hdr_metadata(Some(PreserveHdrMetadata::Disabled));
+    assert_eq!(builder.build_args(), vec!["-i", "in.mkv", "-o", "out.mkv", "--no-hdr-dynamic-metadata"]);
+}
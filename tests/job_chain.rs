@@ -0,0 +1,52 @@
+//! Tests for `JobChain` sequencing and failure propagation.
+//!
+//! These tests use `true` and `false` as stand-ins for `HandBrakeCLI`, so they only run on Unix.
+#![cfg(unix)]
+
+use handbrake::{FailureKind, InputSource, JobBuilder, OutputDestination};
+
+fn job(executable: &str, output: &str) -> JobBuilder {
+    JobBuilder::new(
+        executable.into(),
+        InputSource::File("in.mkv".into()),
+        OutputDestination::File(output.into()),
+    )
+}
+
+#[tokio::test]
+async fn test_chain_runs_all_jobs() {
+    let summaries = job("true", "a.mp4")
+        .then(job("true", "b.mp4"))
+        .run()
+        .await
+        .expect("Chain should succeed");
+
+    let outputs: Vec<_> = summaries.into_iter().map(|s| s.output.unwrap()).collect();
+    assert_eq!(outputs, vec!["a.mp4".into(), std::path::PathBuf::from("b.mp4")]);
+}
+
+#[tokio::test]
+async fn test_chain_stops_at_failed_job() {
+    let failure = job("true", "a.mp4")
+        .then(job("false", "b.mp4"))
+        .then(job("true", "c.mp4"))
+        .run()
+        .await
+        .expect_err("Chain should fail");
+
+    assert_eq!(failure.stage, 1);
+    assert_eq!(failure.failure.kind, FailureKind::Process);
+    assert_eq!(failure.completed.len(), 1);
+}
+
+#[tokio::test]
+async fn test_chain_reports_spawn_failure() {
+    let failure = job("/nonexistent/HandBrakeCLI", "a.mp4")
+        .then(job("true", "b.mp4"))
+        .run()
+        .await
+        .expect_err("Chain should fail");
+
+    assert_eq!(failure.stage, 0);
+    assert!(failure.completed.is_empty());
+}
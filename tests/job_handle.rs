@@ -0,0 +1,972 @@
+//! Tests for `JobHandle` I/O plumbing and process control.
+//!
+//! These tests use `/bin/sh` as a stand-in for `HandBrakeCLI`, so they only run on Unix.
+#![cfg(unix)]
+
+use futures::StreamExt;
+use handbrake::{
+    DiagnosticKind, Error, FailureKind, InputSource, JobBuilder, JobEvent, JobHandle, JobState,
+    OutputDestination, Phase,
+};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_util::sync::CancellationToken;
+
+fn start_script(output: OutputDestination, script: &str) -> JobHandle {
+    start_script_with_input(InputSource::File("in.mkv".into()), output, script)
+}
+
+fn start_script_with_input(
+    input: InputSource,
+    output: OutputDestination,
+    script: &str,
+) -> JobHandle {
+    JobBuilder::new("/bin/sh".into(), input, output)
+        .start_with_args(vec!["-c".to_string(), script.to_string()])
+        .expect("Failed to spawn /bin/sh")
+}
+
+#[tokio::test]
+async fn test_take_output_reader_receives_stdout_data() {
+    let mut handle = start_script(OutputDestination::Stdout, "sleep 0.1; printf 'video-data'");
+
+    let mut reader = handle.take_output_reader().expect("Output reader should be available");
+    assert!(handle.take_output_reader().is_none());
+
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data).await.unwrap();
+    assert_eq!(data, b"video-data");
+
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::Fragment(_) => panic!("Output data should bypass the event stream"),
+            JobEvent::Done(result) => {
+                assert!(result.unwrap().success());
+                break;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_output_reader_with_small_buffers() {
+    let mut handle = JobBuilder::new("/bin/sh".into(), "in.mkv".into(), OutputDestination::Stdout)
+        .stdout_buffer_capacity(3)
+        .stdout_chunk_size(4)
+        .start_with_args(vec![
+            "-c".to_string(),
+            "sleep 0.1; printf 'video\rEncoding: task 1 of 1, 50.00 %%\rdata'".to_string(),
+        ])
+        .expect("Failed to spawn /bin/sh");
+
+    let mut reader = handle.take_output_reader().expect("Output reader should be available");
+    let mut chunk = [0; 16];
+    let read = reader.read(&mut chunk).await.unwrap();
+    assert!(read <= 4);
+
+    let mut data = chunk[..read].to_vec();
+    reader.read_to_end(&mut data).await.unwrap();
+    assert_eq!(data, b"video\rdata");
+    assert!(handle.wait().await.is_ok());
+}
+
+#[tokio::test]
+async fn test_stdout_data_is_emitted_as_fragments_without_reader() {
+    let mut handle = start_script(OutputDestination::Stdout, "printf 'video-data'");
+
+    let mut data = Vec::new();
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::Fragment(fragment) => data.extend(fragment),
+            JobEvent::Done(_) => break,
+            _ => {}
+        }
+    }
+    assert_eq!(data, b"video-data");
+}
+
+#[tokio::test]
+async fn test_fragments_are_not_dropped_for_slow_readers() {
+    // Every line is a fragment, so there are many more than the event channel holds.
+    let mut handle = start_script(
+        OutputDestination::Stdout,
+        r"i=0; while [ $i -lt 500 ]; do printf 'chunk-%04d\n' $i; i=$((i + 1)); done",
+    );
+
+    let mut data = Vec::new();
+    let mut fragments = 0;
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::Fragment(fragment) => {
+                if fragments == 0 {
+                    // Let the job fill the channel before reading on.
+                    tokio::time::sleep(Duration::from_millis(300)).await;
+                }
+                fragments += 1;
+                data.extend(fragment);
+            }
+            JobEvent::Done(_) => break,
+            _ => {}
+        }
+    }
+    let expected: String = (0..500).map(|i| format!("chunk-{:04}\n", i)).collect();
+    assert!(fragments > 128);
+    assert_eq!(String::from_utf8(data).unwrap(), expected);
+    assert_eq!(handle.dropped_events(), 0);
+}
+
+#[tokio::test]
+async fn test_take_output_reader_unavailable_for_file_output() {
+    let mut handle = start_script(OutputDestination::File("out.mp4".into()), "true");
+    assert!(handle.take_output_reader().is_none());
+}
+
+#[tokio::test]
+async fn test_take_input_writer_feeds_stdin() {
+    let mut handle = start_script_with_input(InputSource::Stdin, OutputDestination::Stdout, "cat");
+
+    let mut writer = handle.take_input_writer().expect("Input writer should be available");
+    assert!(handle.take_input_writer().is_none());
+    let mut reader = handle.take_output_reader().expect("Output reader should be available");
+
+    writer.write_all(b"source-data").await.unwrap();
+    drop(writer);
+
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data).await.unwrap();
+    assert_eq!(data, b"source-data");
+}
+
+#[tokio::test]
+async fn test_take_input_writer_unavailable_for_file_input() {
+    let mut handle = start_script(OutputDestination::File("out.mp4".into()), "true");
+    assert!(handle.take_input_writer().is_none());
+}
+
+#[tokio::test]
+async fn test_take_events_can_be_moved_into_a_task() {
+    let mut handle = start_script(OutputDestination::File("out.mp4".into()), "echo 'log line' >&2");
+
+    let mut events = handle.take_events().expect("Events should be available");
+    assert!(handle.take_events().is_none());
+
+    let collected = tokio::spawn(async move {
+        let mut logs = Vec::new();
+        while let Some(event) = events.next().await {
+            if let JobEvent::Log(log) = event {
+                logs.push(log.message);
+            }
+        }
+        logs
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(collected, vec!["log line".to_string()]);
+    assert!(handle.events().next().await.is_none());
+}
+
+#[tokio::test]
+async fn test_split_controller_kills_process() {
+    let handle = start_script(OutputDestination::File("out.mp4".into()), "exec sleep 5");
+    let (controller, mut events) = handle.split();
+
+    tokio::spawn(async move {
+        controller.kill().await.expect("Failed to kill job");
+    });
+
+    let done = tokio::time::timeout(Duration::from_secs(2), async {
+        while let Some(event) = events.next().await {
+            if let JobEvent::Done(result) = event {
+                return result;
+            }
+        }
+        panic!("Event stream ended without a Done event");
+    })
+    .await
+    .expect("Killed job should finish promptly");
+    assert_eq!(done.unwrap_err().kind, FailureKind::Killed);
+}
+
+#[tokio::test]
+async fn test_kill_after_output_closed() {
+    // The process keeps running after closing its output, while the job waits for it to exit.
+    let handle = start_script(
+        OutputDestination::File("out.mp4".into()),
+        "exec >/dev/null 2>&1; sleep 5",
+    );
+    let controller = handle.controller();
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    tokio::time::timeout(Duration::from_secs(1), controller.kill())
+        .await
+        .expect("Kill should not wait for the process to exit")
+        .expect("Failed to kill job");
+    let done = tokio::time::timeout(Duration::from_secs(2), handle.wait())
+        .await
+        .expect("Killed job should finish promptly");
+    assert_eq!(done.unwrap_err().kind, FailureKind::Killed);
+    assert!(controller.kill().await.is_err());
+}
+
+#[tokio::test]
+async fn test_pause_and_resume() {
+    let mut handle = start_script(
+        OutputDestination::File("out.mp4".into()),
+        "sleep 0.2; echo 'finished' >&2",
+    );
+    let controller = handle.controller();
+
+    controller.pause().await.expect("Failed to pause job");
+    let paused = tokio::time::timeout(Duration::from_millis(500), handle.events().next()).await;
+    assert!(paused.is_err(), "Paused job should not produce events");
+
+    controller.resume().await.expect("Failed to resume job");
+    let mut logs = Vec::new();
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::Log(log) => logs.push(log.message),
+            JobEvent::Done(result) => {
+                assert!(result.unwrap().success());
+                break;
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(logs, vec!["finished".to_string()]);
+}
+
+#[tokio::test]
+async fn test_progress_watch_holds_latest_progress() {
+    let handle = start_script(
+        OutputDestination::File("out.mp4".into()),
+        r"printf 'Encoding: task 1 of 1, 10.00 %%\r'; printf 'Encoding: task 1 of 1, 42.50 %%\r'",
+    );
+    let progress = handle.progress_watch();
+    assert!(progress.borrow().is_none());
+
+    let (_, events) = handle.split();
+    // Drain the events so the job runs to completion.
+    events.collect::<Vec<_>>().await;
+
+    let latest = progress.borrow().clone().expect("Progress should have been reported");
+    assert_eq!(latest.percentage, 42.5);
+}
+
+#[tokio::test]
+async fn test_progress_watch_without_reading_events() {
+    let handle = start_script(
+        OutputDestination::File("out.mp4".into()),
+        r"i=1; while [ $i -le 300 ]; do printf 'Encoding: task 1 of 1, %d.%d0 %%\r' $((i / 10)) $((i % 10)); i=$((i + 1)); done",
+    );
+    let mut progress = handle.progress_watch();
+
+    // The events are never read, which must not stall the job.
+    let mut latest = None;
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while progress.changed().await.is_ok() {
+            latest = progress.borrow_and_update().as_ref().map(|p| p.percentage);
+        }
+    })
+    .await
+    .expect("The progress watch should close when the job ends");
+
+    assert_eq!(latest, Some(30.0));
+    assert!(matches!(handle.state(), JobState::Exited(_)));
+    assert!(handle.dropped_events() > 0);
+}
+
+#[tokio::test]
+async fn test_wait_returns_summary() {
+    let handle = start_script(
+        OutputDestination::File("out.mp4".into()),
+        r"printf 'Encoding: task 1 of 1, 50.00 %% (30.00 fps, avg 25.00 fps, ETA 00h00m01s)\r'",
+    );
+
+    let summary = handle.wait().await.expect("Job should succeed");
+    assert!(summary.status.success());
+    assert_eq!(summary.output, Some("out.mp4".into()));
+    assert_eq!(summary.avg_fps, Some(25.0));
+}
+
+#[tokio::test]
+async fn test_wait_reports_unsuccessful_exit() {
+    let handle = start_script(OutputDestination::File("out.mp4".into()), "exit 3");
+
+    let failure = handle.wait().await.expect_err("Job should fail");
+    assert_eq!(failure.exit_code, Some(3));
+}
+
+#[tokio::test]
+async fn test_user_data_is_echoed_in_events() {
+    let mut handle = JobBuilder::new("/bin/sh".into(), "in.mkv".into(), "out.mp4".into())
+        .user_data(serde_json::json!({ "id": 42, "tags": ["anime"] }))
+        .start_with_args(vec!["-c".to_string(), "echo 'Starting work' >&2".to_string()])
+        .expect("Failed to spawn /bin/sh");
+    assert_eq!(handle.user_data().unwrap()["id"], 42);
+
+    let events: Vec<_> = handle.timestamped_events().collect().await;
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().all(|e| e.user_data.as_ref().unwrap()["tags"][0] == "anime"));
+}
+
+#[tokio::test]
+async fn test_timeout_cancels_job() {
+    let handle = JobBuilder::new(
+        "/bin/sh".into(),
+        InputSource::File("in.mkv".into()),
+        OutputDestination::File("out.mp4".into()),
+    )
+    .timeout(Duration::from_millis(200))
+    .start_with_args(vec!["-c".to_string(), "exec sleep 5".to_string()])
+    .expect("Failed to spawn /bin/sh");
+
+    let failure = tokio::time::timeout(Duration::from_secs(2), handle.wait())
+        .await
+        .expect("Timed out job should finish promptly")
+        .expect_err("Timed out job should fail");
+    assert_eq!(failure.kind, FailureKind::Timeout);
+}
+
+#[tokio::test]
+async fn test_min_free_space_stops_job() {
+    let mut handle = JobBuilder::new(
+        "/bin/sh".into(),
+        InputSource::File("in.mkv".into()),
+        OutputDestination::File("out.mp4".into()),
+    )
+    .free_space_warnings([u64::MAX - 1, 1])
+    .min_free_space(u64::MAX)
+    .start_with_args(vec!["-c".to_string(), "exec sleep 5".to_string()])
+    .expect("Failed to spawn /bin/sh");
+
+    let mut warnings = 0;
+    let failure = tokio::time::timeout(Duration::from_secs(2), async {
+        while let Some(event) = handle.events().next().await {
+            match event {
+                JobEvent::LowDiskSpace { .. } => warnings += 1,
+                JobEvent::Done(result) => return result,
+                _ => {}
+            }
+        }
+        panic!("Event stream ended without a Done event");
+    })
+    .await
+    .expect("Job out of disk space should finish promptly")
+    .expect_err("Job out of disk space should fail");
+    assert_eq!(failure.kind, FailureKind::DiskSpace);
+    // The job is stopped before warnings are reported.
+    assert_eq!(warnings, 0);
+}
+
+#[tokio::test]
+async fn test_free_space_warnings() {
+    let mut handle = JobBuilder::new(
+        "/bin/sh".into(),
+        InputSource::File("in.mkv".into()),
+        OutputDestination::File("out.mp4".into()),
+    )
+    .free_space_warnings([u64::MAX - 1, u64::MAX, 0])
+    .start_with_args(vec!["-c".to_string(), "sleep 0.5".to_string()])
+    .expect("Failed to spawn /bin/sh");
+
+    let mut warnings = Vec::new();
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::LowDiskSpace { threshold, .. } => warnings.push(threshold),
+            JobEvent::Done(result) => {
+                assert!(result.unwrap().success());
+                break;
+            }
+            _ => {}
+        }
+    }
+    // Both crossed thresholds are reported as the lower one, and nothing is below zero.
+    assert_eq!(warnings, vec![u64::MAX - 1]);
+}
+
+#[tokio::test]
+async fn test_output_size_events() {
+    let path = std::env::temp_dir().join(format!("handbrake-rs-output-size-{}.mp4", std::process::id()));
+    let mut handle = JobBuilder::new(
+        "/bin/sh".into(),
+        InputSource::File("in.mkv".into()),
+        OutputDestination::File(path.clone()),
+    )
+    .output_size_interval(Duration::from_millis(100))
+    .start_with_args(vec![
+        "-c".to_string(),
+        "printf '0123456789' > \"$0\"; sleep 0.35".to_string(),
+        path.display().to_string(),
+    ])
+    .expect("Failed to spawn /bin/sh");
+
+    let mut sizes = Vec::new();
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::OutputSize { bytes, bytes_per_sec } => sizes.push((bytes, bytes_per_sec)),
+            JobEvent::Done(result) => {
+                assert!(result.unwrap().success());
+                break;
+            }
+            _ => {}
+        }
+    }
+    let _ = std::fs::remove_file(&path);
+
+    assert!(sizes.len() >= 2, "Expected several samples, got {:?}", sizes);
+    assert!(sizes.iter().all(|&(bytes, _)| bytes == 10));
+    // The output grew before the first sample only.
+    assert!(sizes[0].1 > 0.0);
+    assert_eq!(sizes[1].1, 0.0);
+}
+
+#[tokio::test]
+async fn test_cancel_token_cancels_job() {
+    let token = CancellationToken::new();
+    let handle = JobBuilder::new(
+        "/bin/sh".into(),
+        InputSource::File("in.mkv".into()),
+        OutputDestination::File("out.mp4".into()),
+    )
+    .cancel_token(token.child_token())
+    .start_with_args(vec!["-c".to_string(), "exec sleep 5".to_string()])
+    .expect("Failed to spawn /bin/sh");
+    assert_eq!(handle.state(), JobState::Running);
+
+    token.cancel();
+    let failure = tokio::time::timeout(Duration::from_secs(2), handle.wait())
+        .await
+        .expect("Cancelled job should finish promptly")
+        .expect_err("Cancelled job should fail");
+    assert_eq!(failure.kind, FailureKind::Cancelled);
+}
+
+#[tokio::test]
+async fn test_pid_and_state() {
+    let mut handle = start_script(OutputDestination::File("out.mp4".into()), "exec sleep 5");
+    assert!(handle.pid().is_some());
+    assert_eq!(handle.state(), JobState::Running);
+    assert!(!handle.state().is_finished());
+
+    handle.cancel().await.expect("Failed to cancel job");
+    assert_eq!(handle.state(), JobState::Cancelling);
+
+    while let Some(event) = handle.events().next().await {
+        if let JobEvent::Done(_) = event {
+            break;
+        }
+    }
+    assert!(matches!(handle.state(), JobState::Exited(status) if !status.success()));
+    assert!(handle.state().is_finished());
+    assert!(JobState::Failed.is_finished());
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn test_cpu_affinity_pins_process() {
+    let mut handle = JobBuilder::new(
+        "/bin/sh".into(),
+        InputSource::File("in.mkv".into()),
+        OutputDestination::File("out.mp4".into()),
+    )
+    .cpu_affinity([0])
+    .start_with_args(vec![
+        "-c".to_string(),
+        "grep Cpus_allowed_list /proc/self/status >&2".to_string(),
+    ])
+    .expect("Failed to spawn /bin/sh");
+
+    let mut logs = Vec::new();
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::Log(log) => logs.push(log.message),
+            JobEvent::Done(_) => break,
+            _ => {}
+        }
+    }
+    assert_eq!(logs, vec!["Cpus_allowed_list:\t0".to_string()]);
+}
+
+#[tokio::test]
+async fn test_wait_reports_output_size() {
+    let output = std::env::temp_dir().join(format!("handbrake-rs-size-{}.mp4", std::process::id()));
+    let script = format!("printf 'video' > '{}'", output.display());
+    let handle = start_script(OutputDestination::File(output.clone()), &script);
+
+    let summary = handle.wait().await.expect("Job should succeed");
+    std::fs::remove_file(&output).unwrap();
+
+    assert_eq!(summary.output, Some(output));
+    assert_eq!(summary.output_size, Some(5));
+}
+
+#[tokio::test]
+async fn test_invalid_utf8_log_lines_are_decoded_lossily() {
+    let mut handle = start_script(
+        OutputDestination::File("out.mp4".into()),
+        r"printf 'Title: caf\351\n' >&2; printf 'next line\n' >&2",
+    );
+
+    let mut logs = Vec::new();
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::Log(log) => logs.push(log.message),
+            JobEvent::Done(_) => break,
+            _ => {}
+        }
+    }
+    assert_eq!(logs, vec!["Title: caf\u{FFFD}".to_string(), "next line".to_string()]);
+}
+
+/// Collects the progress percentages and any other non-completion events of a job.
+async fn collect_progress(mut handle: JobHandle) -> (Vec<f32>, usize) {
+    let mut percentages = Vec::new();
+    let mut others = 0;
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::Progress(p) => percentages.push(p.percentage),
+            JobEvent::Done(_) => break,
+            _ => others += 1,
+        }
+    }
+    (percentages, others)
+}
+
+#[tokio::test]
+async fn test_progress_terminated_by_crlf() {
+    let handle = start_script(
+        OutputDestination::File("out.mp4".into()),
+        r"printf 'Encoding: task 1 of 1, 10.00 %%\r\n'; sleep 0.05; printf 'Encoding: task 1 of 1, 20.00 %%\r\n'",
+    );
+    assert_eq!(collect_progress(handle).await, (vec![10.0, 20.0], 0));
+}
+
+#[tokio::test]
+async fn test_progress_terminated_by_lf() {
+    let handle = start_script(
+        OutputDestination::File("out.mp4".into()),
+        r"printf 'Encoding: task 1 of 1, 10.00 %%\n'; sleep 0.05; printf 'Encoding: task 1 of 1, 20.00 %%\n'",
+    );
+    assert_eq!(collect_progress(handle).await, (vec![10.0, 20.0], 0));
+}
+
+#[tokio::test]
+async fn test_progress_on_stderr() {
+    let handle = start_script(
+        OutputDestination::File("out.mp4".into()),
+        r"printf 'Encoding: task 1 of 1, 10.00 %%\r' >&2; sleep 0.05; printf 'Encoding: task 1 of 1, 20.00 %%\r\n' >&2",
+    );
+    assert_eq!(collect_progress(handle).await, (vec![10.0, 20.0], 0));
+}
+
+#[tokio::test]
+async fn test_muxing_is_reported_as_progress() {
+    let mut handle = start_script(
+        OutputDestination::File("out.mp4".into()),
+        r"printf '\rEncoding: task 1 of 1, 99.50 %%'; printf '\rMuxing: this may take awhile...'; sleep 5",
+    );
+
+    let phases = tokio::time::timeout(Duration::from_secs(2), async {
+        let mut phases = Vec::new();
+        while let Some(event) = handle.events().next().await {
+            if let JobEvent::Progress(p) = event {
+                phases.push((p.phase, p.percentage));
+                if p.phase == Phase::Muxing {
+                    break;
+                }
+            }
+        }
+        phases
+    })
+    .await
+    .expect("Muxing should be reported before the process exits");
+    handle.kill().await.unwrap();
+
+    assert_eq!(phases, vec![(Phase::Encoding, 99.5), (Phase::Muxing, 100.0)]);
+}
+
+#[tokio::test]
+async fn test_subtitle_scan_pass_is_reported_as_phase() {
+    let mut handle = JobBuilder::new(
+        "/bin/sh".into(),
+        InputSource::File("in.mkv".into()),
+        OutputDestination::File("out.mp4".into()),
+    )
+    .subtitle_scan()
+    .start_with_args(vec![
+        "-c".to_string(),
+        r"printf 'Encoding: task 1 of 2, 80.00 %%\r'; printf 'Encoding: task 2 of 2, 10.00 %%\r'"
+            .to_string(),
+    ])
+    .expect("Failed to spawn /bin/sh");
+
+    let mut phases = Vec::new();
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::Progress(p) => phases.push((p.phase, p.percentage)),
+            JobEvent::Done(_) => break,
+            _ => {}
+        }
+    }
+    assert_eq!(
+        phases,
+        vec![(Phase::SubtitleScan, 80.0), (Phase::Encoding, 10.0)]
+    );
+}
+
+#[tokio::test]
+async fn test_reader_input_is_copied_to_stdin() {
+    let input = InputSource::reader(std::io::Cursor::new(b"first\nsecond\n".to_vec()));
+    let mut handle = start_script_with_input(
+        input.clone(),
+        OutputDestination::File("out.mp4".into()),
+        "cat >&2",
+    );
+
+    let mut logs = Vec::new();
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::Log(log) => logs.push(log.message),
+            JobEvent::Done(result) => {
+                assert!(result.unwrap().success());
+                break;
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(logs, vec!["first".to_string(), "second".to_string()]);
+
+    let reused = JobBuilder::new("/bin/sh".into(), input, OutputDestination::File("out.mp4".into()))
+        .start_with_args(vec!["-c".to_string(), "true".to_string()]);
+    assert!(reused.is_err(), "A reader can only feed one job");
+}
+
+#[tokio::test]
+async fn test_writer_output_receives_stdout_data() {
+    let (writer, mut reader) = tokio::io::duplex(16);
+    let mut handle = start_script(
+        OutputDestination::writer(writer),
+        "printf 'video-data-larger-than-the-pipe'",
+    );
+    assert!(handle.take_output_reader().is_none());
+
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data).await.unwrap();
+    assert_eq!(data, b"video-data-larger-than-the-pipe");
+
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::Fragment(_) => panic!("Output written to a writer should not be sent as fragments"),
+            JobEvent::Done(result) => {
+                assert!(result.unwrap().success());
+                break;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_json_output_rejects_stdout_output() {
+    for output in [OutputDestination::Stdout, OutputDestination::writer(tokio::io::sink())] {
+        let result = JobBuilder::new("/bin/sh".into(), "in.mkv".into(), output)
+            .json_output(true)
+            .start_with_args(vec!["-c".to_string(), "true".to_string()]);
+        assert!(matches!(result, Err(Error::IncompatibleOptions { .. })));
+    }
+}
+
+#[tokio::test]
+async fn test_json_output_progress() {
+    let script = r#"cat <<'EOF'
+Version: {
+    "Name": "HandBrake"
+}
+Progress: {
+    "Scanning": {
+        "Progress": 0.5
+    },
+    "State": "SCANNING"
+}
+Progress: {
+    "State": "WORKING",
+    "Working": {
+        "ETASeconds": 90,
+        "PassID": -1,
+        "Progress": 0.25,
+        "Rate": 0.0,
+        "RateAvg": 0.0
+    }
+}
+Progress: {
+    "State": "WORKING",
+    "Working": {
+        "ETASeconds": 30,
+        "PassID": 0,
+        "Progress": 0.5,
+        "Rate": 30.5,
+        "RateAvg": 29.5
+    }
+}
+Progress: {
+    "State": "WORKING",
+    "Working": {
+        "Hours": 0,
+        "Minutes": 1,
+        "PassID": 0,
+        "Progress": 0.75,
+        "Rate": 30.5,
+        "RateAvg": 29.5,
+        "Seconds": 5
+    }
+}
+Progress: {
+    "Muxing": {
+        "Progress": 0.0
+    },
+    "State": "MUXING"
+}
+EOF"#;
+    let mut handle = JobBuilder::new("/bin/sh".into(), "in.mkv".into(), "out.mp4".into())
+        .json_output(true)
+        .start_with_args(vec!["-c".to_string(), script.to_string()])
+        .expect("Failed to spawn /bin/sh");
+
+    let mut progress = Vec::new();
+    let mut logs = Vec::new();
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::Progress(p) => progress.push((p.percentage, p.avg_fps, p.eta, p.phase)),
+            JobEvent::Log(log) => logs.push(log.message),
+            JobEvent::Fragment(data) => panic!("Unexpected fragment: {:?}", data),
+            JobEvent::Done(_) => break,
+            _ => {}
+        }
+    }
+    assert_eq!(
+        progress,
+        vec![
+            (25.0, Some(0.0), Some(Duration::from_secs(90)), Phase::SubtitleScan),
+            (50.0, Some(29.5), Some(Duration::from_secs(30)), Phase::Encoding),
+            (75.0, Some(29.5), Some(Duration::from_secs(65)), Phase::Encoding),
+            (100.0, None, None, Phase::Muxing),
+        ]
+    );
+    assert_eq!(logs, vec!["Version: {\n    \"Name\": \"HandBrake\"\n}".to_string()]);
+}
+
+#[tokio::test]
+async fn test_known_problems_are_classified() {
+    let script = r#"echo "libdvdread: Can't seek to block 1024" >&2; echo 'No title found.' >&2; echo 'HandBrake has exited.' >&2"#;
+    let mut handle = start_script(OutputDestination::File("out.mp4".into()), script);
+
+    let mut events = Vec::new();
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::Warning(diagnostic) => events.push(("warning", Some(diagnostic.kind), diagnostic.message)),
+            JobEvent::Error(diagnostic) => events.push(("error", Some(diagnostic.kind), diagnostic.message)),
+            JobEvent::Log(log) => events.push(("log", None, log.message)),
+            JobEvent::Done(_) => break,
+            _ => {}
+        }
+    }
+    assert_eq!(
+        events,
+        vec![
+            ("warning", Some(DiagnosticKind::DvdRead), "libdvdread: Can't seek to block 1024".to_string()),
+            ("error", Some(DiagnosticKind::NoTitleFound), "No title found.".to_string()),
+            ("log", None, "HandBrake has exited.".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_scan_summary() {
+    let script = r#"cat >&2 <<'EOF'
++ title 1:
+  + duration: 00:01:30
+  + audio tracks:
+    + 1, English (AC3) (5.1 ch) (iso639-2: eng), 48000Hz, 448000bps
+Encode done!
+EOF"#;
+    let mut handle = start_script(OutputDestination::File("out.mp4".into()), script);
+
+    let mut events = Vec::new();
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::ScanSummary(summary) => events.push(format!("summary of {} title(s)", summary.titles.len())),
+            JobEvent::Log(log) => events.push(log.message),
+            JobEvent::Done(_) => break,
+            _ => {}
+        }
+    }
+    // The summary is sent once the listing ends, and its lines are still logged.
+    assert_eq!(events.len(), 6);
+    assert_eq!(events[4..], ["summary of 1 title(s)".to_string(), "Encode done!".to_string()]);
+}
+
+#[tokio::test]
+async fn test_progress_position() {
+    let script = r#"printf 'Encoding: task 1 of 1, 10.00 %%\r'; sleep 0.1
+printf '+ title 1:\n  + duration: 00:01:40\nscan done\n' >&2; sleep 0.1
+printf 'Encoding: task 1 of 1, 25.00 %%\r'"#;
+    let mut handle = start_script(OutputDestination::File("out.mp4".into()), script);
+
+    let mut positions = Vec::new();
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::Progress(progress) => positions.push(progress.position),
+            JobEvent::Done(_) => break,
+            _ => {}
+        }
+    }
+    // The position is unknown until the duration is.
+    assert_eq!(positions, vec![None, Some(Duration::from_secs(25))]);
+}
+
+#[tokio::test]
+async fn test_command_line() {
+    let handle = JobBuilder::new("/bin/sh".into(), "in.mkv".into(), "out.mp4".into())
+        .start_with_args(vec!["-c".to_string(), "exit 0".to_string()])
+        .expect("Failed to spawn /bin/sh");
+
+    assert_eq!(handle.command_line(), ["/bin/sh", "-c", "exit 0"]);
+    assert!(handle.wait().await.is_ok());
+}
+
+async fn collect_logs(mut handle: JobHandle) -> Vec<String> {
+    let mut logs = Vec::new();
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::Log(log) => logs.push(log.message),
+            JobEvent::Done(_) => break,
+            _ => {}
+        }
+    }
+    logs
+}
+
+#[tokio::test]
+async fn test_locale_defaults_to_c() {
+    let script = r#"echo "$LC_ALL $LANG" >&2"#;
+    let handle = start_script(OutputDestination::File("out.mp4".into()), script);
+    assert_eq!(collect_logs(handle).await, vec!["C C"]);
+
+    let handle = JobBuilder::new("/bin/sh".into(), "in.mkv".into(), "out.mp4".into())
+        .locale("de_DE.UTF-8")
+        .start_with_args(vec!["-c".to_string(), script.to_string()])
+        .unwrap();
+    assert_eq!(collect_logs(handle).await, vec!["de_DE.UTF-8 de_DE.UTF-8"]);
+}
+
+#[tokio::test]
+async fn test_env_is_forwarded() {
+    let script = r#"echo "${CUDA_VISIBLE_DEVICES:-unset} ${HOME:-unset} $LC_ALL" >&2"#;
+    let handle = JobBuilder::new("/bin/sh".into(), "in.mkv".into(), "out.mp4".into())
+        .env("CUDA_VISIBLE_DEVICES", "1")
+        .env("LC_ALL", "POSIX")
+        .start_with_args(vec!["-c".to_string(), script.to_string()])
+        .unwrap();
+    let logs = collect_logs(handle).await;
+    assert!(logs[0].starts_with("1 ") && logs[0].ends_with(" POSIX"), "{:?}", logs);
+
+    let handle = JobBuilder::new("/bin/sh".into(), "in.mkv".into(), "out.mp4".into())
+        .env("CUDA_VISIBLE_DEVICES", "1")
+        .env_clear()
+        .start_with_args(vec!["-c".to_string(), script.to_string()])
+        .unwrap();
+    assert_eq!(collect_logs(handle).await, vec!["unset unset C"]);
+}
+
+#[tokio::test]
+async fn test_current_dir_resolves_relative_output() {
+    let dir = std::env::temp_dir().join(format!("handbrake-rs-cwd-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let handle = JobBuilder::new("/bin/sh".into(), "in.mkv".into(), "out.mp4".into())
+        .current_dir(&dir)
+        .start_with_args(vec!["-c".to_string(), "printf 'video' > out.mp4".to_string()])
+        .unwrap();
+
+    let summary = handle.wait().await.expect("Job should succeed");
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(summary.output, Some(dir.join("out.mp4")));
+    assert_eq!(summary.output_size, Some(5));
+}
+
+#[tokio::test]
+async fn test_check_input_rejects_images() {
+    let dir = std::env::temp_dir().join(format!("handbrake-rs-check-input-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("cover.png"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+    std::fs::write(dir.join("movie.mkv"), b"\x1a\x45\xdf\xa3\x9f\x42\x86\x81\x01").unwrap();
+    let start = |input: &str| {
+        JobBuilder::new("/bin/sh".into(), input.into(), "out.mp4".into())
+            .current_dir(&dir)
+            .check_input(true)
+            .start_with_args(vec!["-c".to_string(), "true".to_string()])
+    };
+
+    let err = start("cover.png").unwrap_err();
+    assert!(matches!(err, Error::UnsupportedInput { ref reason, .. } if reason == "a PNG image"));
+    assert!(start("movie.mkv").unwrap().wait().await.is_ok());
+    // Missing inputs are left to `HandBrakeCLI`.
+    assert!(start("missing.mkv").unwrap().wait().await.is_ok());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+async fn test_stderr_file_skips_parsing_stderr() {
+    let dir = std::env::temp_dir().join(format!("handbrake-rs-stderr-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let stderr_path = dir.join("job.stderr");
+    let script = r"echo 'scan: 1 title' >&2; printf 'Encoding: task 1 of 1, 42.50 %%\r'";
+    let mut handle = JobBuilder::new("/bin/sh".into(), "in.mkv".into(), "out.mp4".into())
+        .stderr_file(&stderr_path)
+        .start_with_args(vec!["-c".to_string(), script.to_string()])
+        .unwrap();
+
+    let mut events = Vec::new();
+    while let Some(event) = handle.events().next().await {
+        match event {
+            JobEvent::Progress(p) => events.push(format!("progress {}", p.percentage)),
+            JobEvent::Log(log) => events.push(log.message),
+            JobEvent::Done(_) => break,
+            _ => {}
+        }
+    }
+    let stderr = std::fs::read_to_string(&stderr_path).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(events, vec!["progress 42.5"]);
+    assert_eq!(stderr, "scan: 1 title\n");
+}
+
+#[tokio::test]
+async fn test_stderr_file_is_relative_to_current_dir() {
+    let dir = std::env::temp_dir().join(format!("handbrake-rs-stderr-dir-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let job = |output| {
+        JobBuilder::new("/bin/sh".into(), "in.mkv".into(), output)
+            .current_dir(&dir)
+            .stderr_file("job.stderr")
+    };
+
+    // A job that fails its checks doesn't create the file.
+    let rejected = job(OutputDestination::Stdout).json_output(true).status().await;
+    assert!(matches!(rejected, Err(Error::IncompatibleOptions { .. })));
+    assert!(!dir.join("job.stderr").exists());
+
+    let status = job("out.mp4".into())
+        .start_with_args(vec!["-c".to_string(), "echo 'scan: 1 title' >&2".to_string()])
+        .unwrap()
+        .wait()
+        .await;
+    let stderr = std::fs::read_to_string(dir.join("job.stderr")).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert!(status.is_ok());
+    assert_eq!(stderr, "scan: 1 title\n");
+}
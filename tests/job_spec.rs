@@ -0,0 +1,81 @@
+use handbrake::job::{SubtitleBurnMode, SubtitleSelection};
+use handbrake::{InputSource, JobBuilder, JobSpec, OutputDestination};
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[test]
+fn test_job_spec_from_json() {
+    let spec: JobSpec = serde_json::from_str(
+        r#"{
+            "input": { "file": "/path/to/input.mkv" },
+            "output": "stdout",
+            "preset": "Fast 1080p30",
+            "audio_codecs": { "1": "aac" },
+            "subtitle_selection": "scan",
+            "subtitle_burned": "native"
+        }"#,
+    )
+    .expect("Spec should deserialize");
+
+    assert_eq!(spec.input, InputSource::File(PathBuf::from("/path/to/input.mkv")));
+    assert_eq!(spec.output, OutputDestination::Stdout);
+    assert_eq!(spec.subtitle_selection, Some(SubtitleSelection::Scan));
+    assert_eq!(spec.subtitle_burned, Some(SubtitleBurnMode::Native));
+
+    let builder = JobBuilder::from_spec(PathBuf::from("/usr/bin/HandBrakeCLI"), spec);
+    assert_eq!(
+        builder.build_args(),
+        vec![
+            "-i", "/path/to/input.mkv", "-o", "pipe:1",
+            "--preset", "Fast 1080p30",
+            "--audio", "1,aac",
+            "--subtitle", "scan",
+            "--subtitle-burned", "native",
+        ]
+    );
+}
+
+#[test]
+fn test_job_spec_requires_input_and_output() {
+    let result = serde_json::from_str::<JobSpec>(r#"{ "preset": "Fast 1080p30" }"#);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_job_spec_round_trip() {
+    let builder = JobBuilder::new(
+        PathBuf::from("/usr/bin/HandBrakeCLI"),
+        "input.mkv".into(),
+        "output.mp4".into(),
+    )
+    .preset("Fast 1080p30")
+    .quality(20.5)
+    .subtitle(1)
+    .subtitle_lang("eng")
+    .sample(Duration::from_secs(30))
+    .timeout(Duration::from_secs(3600))
+    .stdout_buffer_capacity(4 * 1024 * 1024)
+    .min_free_space(1 << 30)
+    .free_space_warnings([10 << 30])
+    .output_size_interval(Duration::from_secs(5))
+    .env("CUDA_VISIBLE_DEVICES", "0")
+    .cpu_affinity(0..2);
+
+    let spec = builder.to_spec();
+    let json = serde_json::to_string(&spec).expect("Spec should serialize");
+    let restored: JobSpec = serde_json::from_str(&json).expect("Spec should deserialize");
+    assert_eq!(restored, spec);
+
+    let rebuilt = JobBuilder::from_spec(PathBuf::from("/usr/bin/HandBrakeCLI"), restored);
+    assert_eq!(rebuilt.build_args(), builder.build_args());
+    assert_eq!(rebuilt.to_spec(), spec);
+}
+
+#[test]
+fn test_job_spec_locale_defaults_to_c() {
+    let spec: JobSpec = serde_json::from_str(
+        r#"{ "input": { "file": "in.mkv" }, "output": { "file": "out.mp4" } }"#,
+    )
+    .expect("Spec should deserialize");
+    assert_eq!(spec.locale.as_deref(), Some("C"));
+}
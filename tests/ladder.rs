@@ -0,0 +1,48 @@
+use handbrake::{Ladder, Rung};
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+fn ladder(input: &str) -> Ladder {
+    Ladder::new(PathBuf::from("/usr/bin/HandBrakeCLI"), input)
+}
+
+#[test]
+fn test_ladder_builds_one_job_per_rung() {
+    let jobs = ladder("/videos/movie.mkv")
+        .rung(Rung::bitrate(1920, 1080, 6000))
+        .rung(Rung::quality(1280, 720, 22.0))
+        .video_codec("x264")
+        .output_dir("/renditions")
+        .jobs();
+
+    let args: Vec<Vec<OsString>> = jobs.iter().map(|job| job.build_args()).collect();
+    assert_eq!(
+        args,
+        vec![
+            vec![
+                "-i", "/videos/movie.mkv", "-o", "/renditions/movie-1080p-6000k.mp4",
+                "--encoder", "x264", "--width", "1920", "--height", "1080", "--vb", "6000",
+            ],
+            vec![
+                "-i", "/videos/movie.mkv", "-o", "/renditions/movie-720p-q22.mp4",
+                "--encoder", "x264", "--width", "1280", "--height", "720", "--quality", "22",
+            ],
+        ]
+    );
+}
+
+#[test]
+fn test_ladder_rung_overrides_configuration() {
+    let jobs = ladder("movie.mkv")
+        .rungs([Rung::bitrate(640, 360, 800)])
+        .configure(|job| job.quality(18.0).width(3840))
+        .format("av_mkv")
+        .jobs();
+    assert_eq!(
+        jobs[0].build_args(),
+        vec![
+            "-i", "movie.mkv", "-o", "movie-360p-800k.mkv", "--width", "640", "--height", "360",
+            "--vb", "800", "--format", "av_mkv",
+        ]
+    );
+}
@@ -0,0 +1,75 @@
+//! Tests for job lifecycle notifications.
+//!
+//! These tests use `/bin/sh` as a stand-in for `HandBrakeCLI`, so they only run on Unix.
+#![cfg(unix)]
+
+use async_trait::async_trait;
+use handbrake::notify::{Notification, NotificationKind, Notifier};
+use handbrake::{InputSource, JobBuilder, OutputDestination};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+struct ChannelNotifier(mpsc::UnboundedSender<Notification>);
+
+#[async_trait]
+impl Notifier for ChannelNotifier {
+    async fn notify(&self, notification: &Notification) {
+        let _ = self.0.send(notification.clone());
+    }
+}
+
+#[tokio::test]
+async fn test_notifier_receives_lifecycle() {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let handle = JobBuilder::new(
+        "/bin/sh".into(),
+        InputSource::File("in.mkv".into()),
+        OutputDestination::File("out.mp4".into()),
+    )
+    .notifier(Arc::new(ChannelNotifier(tx)))
+    .start_with_args(vec![
+        "-c".to_string(),
+        r"printf 'Encoding: task 1 of 1, 5.00 %%\r'; printf 'Encoding: task 1 of 1, 12.00 %%\r'; printf 'Encoding: task 1 of 1, 57.50 %%\r'; printf 'Encoding: task 1 of 1, 58.00 %%\r'".to_string(),
+    ])
+    .expect("Failed to spawn /bin/sh");
+    handle.wait().await.expect("Job should succeed");
+
+    let mut kinds = Vec::new();
+    while let Ok(Some(notification)) = tokio::time::timeout(Duration::from_secs(1), rx.recv()).await {
+        assert_eq!(notification.input, Some(PathBuf::from("in.mkv")));
+        assert_eq!(notification.output, Some(PathBuf::from("out.mp4")));
+        let completed = matches!(notification.kind, NotificationKind::Completed { .. });
+        kinds.push(notification.kind);
+        if completed {
+            break;
+        }
+    }
+    assert_eq!(
+        kinds,
+        vec![
+            NotificationKind::Started,
+            NotificationKind::Progress { percentage: 10 },
+            NotificationKind::Progress { percentage: 50 },
+            NotificationKind::Completed {
+                success: true,
+                exit_code: Some(0),
+                message: None,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_notification_json() {
+    let notification = Notification {
+        input: Some(PathBuf::from("movie.mkv")),
+        output: None,
+        kind: NotificationKind::Progress { percentage: 50 },
+    };
+    assert_eq!(
+        serde_json::to_value(&notification).unwrap(),
+        serde_json::json!({ "input": "movie.mkv", "output": null, "event": "progress", "percentage": 50 })
+    );
+}
@@ -0,0 +1,73 @@
+use handbrake::{InputSource, JobBuilder, OutputDestination, Profile, RateControl};
+use std::path::PathBuf;
+
+fn job() -> JobBuilder {
+    JobBuilder::new(
+        PathBuf::from("/usr/bin/HandBrakeCLI"),
+        "input.mkv".into(),
+        "output.mkv".into(),
+    )
+}
+
+#[test]
+fn test_profile_applies_preset_and_settings() {
+    let profile = Profile::new("archive")
+        .preset("HQ 1080p30 Surround")
+        .args(["--encoder", "x265", "-q", "20", "--custom-flag"]);
+    let spec = job().profile(&profile).to_spec();
+
+    assert_eq!(spec.preset.as_deref(), Some("HQ 1080p30 Surround"));
+    assert_eq!(spec.video_codec.as_deref(), Some("x265"));
+    assert_eq!(spec.rate_control, Some(RateControl::ConstantQuality(20.0)));
+    assert_eq!(spec.extra_args, vec!["--custom-flag".to_string()]);
+    assert_eq!(spec.input, InputSource::File("input.mkv".into()));
+    assert_eq!(spec.output, OutputDestination::File("output.mkv".into()));
+}
+
+#[test]
+fn test_profile_override_order() {
+    let profile = Profile::new("archive").args(["--encoder", "x265", "--quality", "20"]);
+    let spec = job()
+        .preset("Fast 1080p30")
+        .video_codec("x264")
+        .width(1280)
+        .profile(&profile)
+        .quality(18.0)
+        .to_spec();
+
+    // The profile overrides the options set before it, and is overridden by the ones after it.
+    assert_eq!(spec.video_codec.as_deref(), Some("x265"));
+    assert_eq!(spec.rate_control, Some(RateControl::ConstantQuality(18.0)));
+    // Options and the preset the profile doesn't set are kept.
+    assert_eq!(spec.width, Some(1280));
+    assert_eq!(spec.preset.as_deref(), Some("Fast 1080p30"));
+}
+
+#[test]
+fn test_profile_from_job() {
+    let template = job().preset("Fast 1080p30").video_codec("x265").quality(22.0).width(1920);
+    let profile = Profile::from_job("web", &template);
+
+    assert_eq!(profile.name, "web");
+    assert_eq!(profile.preset.as_deref(), Some("Fast 1080p30"));
+    assert!(!profile.args.iter().any(|arg| arg == "-i" || arg == "-o"));
+
+    let other = JobBuilder::new(
+        PathBuf::from("/usr/bin/HandBrakeCLI"),
+        "other.mkv".into(),
+        "other.mp4".into(),
+    );
+    let rebuilt = other.profile(&profile);
+    assert_eq!(rebuilt.to_spec().input, InputSource::File("other.mkv".into()));
+    assert_eq!(rebuilt.build_args()[4..], template.build_args()[4..]);
+}
+
+#[test]
+fn test_profile_serde_round_trip() {
+    let profile = Profile::new("archive").preset("HQ 1080p30 Surround").args(["--encoder", "x265"]);
+    let json = serde_json::to_string(&profile).unwrap();
+    assert_eq!(serde_json::from_str::<Profile>(&json).unwrap(), profile);
+
+    let minimal: Profile = serde_json::from_str(r#"{ "name": "empty" }"#).unwrap();
+    assert_eq!(minimal, Profile::new("empty"));
+}
@@ -0,0 +1,33 @@
+//! Tests for the `indicatif` progress bar adapter.
+#![cfg(feature = "indicatif")]
+
+use handbrake::{Phase, Progress};
+use handbrake::progress_bar::drive;
+use indicatif::ProgressBar;
+use std::time::Duration;
+use tokio::sync::watch;
+
+#[tokio::test]
+async fn test_drive_updates_bar_until_job_exits() {
+    let (progress_tx, progress_rx) = watch::channel(None);
+    let bar = ProgressBar::hidden();
+    bar.set_length(10_000);
+    let task = tokio::spawn(drive(progress_rx, bar.clone()));
+
+    progress_tx.send_replace(Some(Progress {
+        percentage: 42.5,
+        fps: 30.0,
+        avg_fps: Some(28.5),
+        eta: Some(Duration::from_secs(3725)),
+        position: None,
+        phase: Phase::Encoding,
+    }));
+    drop(progress_tx);
+    tokio::time::timeout(Duration::from_secs(1), task)
+        .await
+        .expect("drive() should return once the job exits")
+        .unwrap();
+
+    assert_eq!(bar.position(), 4250);
+    assert_eq!(bar.message(), "30.00 fps (avg 28.50), ETA 01:02:05");
+}
@@ -0,0 +1,85 @@
+//! Tests for running jobs on a remote `Worker`, with the fake `HandBrakeCLI`.
+#![cfg(all(feature = "remote", feature = "test-util", unix))]
+
+mod common;
+
+use common::TempDir;
+use futures::StreamExt;
+use handbrake::remote::{RemoteJob, Worker};
+use handbrake::testing::FakeHandBrake;
+use handbrake::{Error, HandBrake, JobBuilder, JobEvent};
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+#[tokio::test]
+async fn test_remote_job_streams_events() {
+    let dir = TempDir::new("remote");
+    let path = FakeHandBrake::new()
+        .progress(40.0)
+        .log("Encode done!")
+        .install(&dir)
+        .unwrap();
+    let hb = HandBrake::new_with_path(&path).await.unwrap();
+    let spec = hb
+        .job("in.mkv".into(), "out.mp4".into())
+        .user_data(serde_json::json!({ "id": 7 }))
+        .to_spec();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { Worker::new(hb).serve(listener).await });
+
+    let job = RemoteJob::start(addr, &spec).await.unwrap();
+    let events: Vec<_> = tokio::time::timeout(Duration::from_secs(5), job.collect())
+        .await
+        .expect("Remote job should finish");
+
+    // The progress and the log are read from different pipes, so they may arrive in any order.
+    assert!(events.iter().any(|e| matches!(&e.event, JobEvent::Progress(p) if p.percentage == 40.0)));
+    assert!(events.iter().any(|e| matches!(&e.event, JobEvent::Log(log) if log.message == "Encode done!")));
+    match &events.last().unwrap().event {
+        JobEvent::Done(result) => assert!(result.as_ref().unwrap().success()),
+        event => panic!("Unexpected last event: {:?}", event),
+    }
+    assert!(events.iter().all(|e| e.user_data.as_ref().unwrap()["id"] == 7));
+}
+
+#[tokio::test]
+async fn test_worker_rejects_specs() {
+    let dir = TempDir::new("remote-rejected");
+    let path = FakeHandBrake::new().install(&dir).unwrap();
+    let hb = HandBrake::new_with_path(&path).await.unwrap();
+    let spec = hb.job("in.mkv".into(), "out.mp4".into()).extra_args(["--foo"]).to_spec();
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let worker = Worker::new(hb).check_spec(|spec| match spec.extra_args.is_empty() {
+        true => Ok(()),
+        false => Err("extra_args are not allowed".to_string()),
+    });
+    tokio::spawn(async move { worker.serve(listener).await });
+
+    let job = RemoteJob::start(addr, &spec).await.unwrap();
+    let events: Vec<_> = tokio::time::timeout(Duration::from_secs(5), job.collect())
+        .await
+        .expect("Rejected job should finish");
+
+    match &events[..] {
+        [event] => match &event.event {
+            JobEvent::Done(Err(failure)) => assert!(failure.message.contains("extra_args are not allowed")),
+            event => panic!("Unexpected event: {:?}", event),
+        },
+        events => panic!("Unexpected events: {:?}", events),
+    }
+}
+
+#[tokio::test]
+async fn test_remote_job_unreachable_worker() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+
+    let spec = JobBuilder::new("hb".into(), "in.mkv".into(), "out.mp4".into()).to_spec();
+    let result = RemoteJob::start(addr, &spec).await;
+    assert!(matches!(result, Err(Error::RemoteFailed { .. })));
+}
@@ -0,0 +1,133 @@
+#![cfg(feature = "segmented")]
+
+use handbrake::{InputSource, JobBuilder, OutputDestination, Segment};
+use std::ffi::OsString;
+use std::time::Duration;
+
+#[cfg(all(feature = "test-util", unix))]
+mod common;
+
+#[test]
+fn test_segmented_jobs_encode_each_segment() {
+    let jobs = JobBuilder::new("hb".into(), "in.mkv".into(), "/out/movie.mkv".into())
+        .video_codec("svt_av1")
+        .segmented()
+        .segment(Segment::Chapters(1..=4))
+        .segment(Segment::Time {
+            start: Duration::from_secs(600),
+            duration: Duration::from_secs(90),
+        })
+        .jobs()
+        .unwrap();
+
+    let args: Vec<Vec<OsString>> = jobs.iter().map(|job| job.build_args()).collect();
+    assert_eq!(
+        args,
+        vec![
+            vec![
+                "-i", "in.mkv", "-o", "/out/movie.mkv.segments/segment-001.mkv", "--encoder",
+                "svt_av1", "--chapters", "1-4",
+            ],
+            vec![
+                "-i", "in.mkv", "-o", "/out/movie.mkv.segments/segment-002.mkv", "--encoder",
+                "svt_av1", "--start-at", "duration:600", "--stop-at", "duration:90",
+            ],
+        ]
+    );
+}
+
+#[test]
+fn test_chapter_segments() {
+    let jobs = JobBuilder::new("hb".into(), "in.mkv".into(), "out.mkv".into())
+        .segmented()
+        .chapter_segments(1..=10, 3)
+        .jobs()
+        .unwrap();
+    let chapters: Vec<OsString> = jobs.iter().map(|job| job.build_args()[5].clone()).collect();
+    assert_eq!(chapters, vec!["1-3", "4-6", "7-9", "10"]);
+}
+
+#[test]
+fn test_segmented_requires_files() {
+    let piped = JobBuilder::new("hb".into(), InputSource::Stdin, "out.mkv".into())
+        .segmented()
+        .segment(Segment::Chapters(1..=1));
+    assert!(piped.jobs().is_err());
+
+    let to_stdout = JobBuilder::new("hb".into(), "in.mkv".into(), OutputDestination::Stdout)
+        .segmented()
+        .segment(Segment::Chapters(1..=1));
+    assert!(to_stdout.jobs().is_err());
+}
+
+#[cfg(all(feature = "test-util", unix))]
+mod fake {
+    use super::*;
+    use crate::common::TempDir;
+    use handbrake::testing::FakeHandBrake;
+    use std::path::PathBuf;
+
+    #[tokio::test]
+    async fn test_segmented_run_concatenates_segments() {
+        let dir = TempDir::new("segmented-run");
+        let path = FakeHandBrake::new().progress(100.0).install(&dir).unwrap();
+        let output = dir.join("movie.mkv");
+
+        let summaries = JobBuilder::new(path, "in.mkv".into(), output.clone().into())
+            .segmented()
+            .segments([Segment::Chapters(1..=2), Segment::Chapters(3..=4), Segment::Chapters(5..=5)])
+            .ffmpeg_path("true")
+            .run()
+            .await
+            .unwrap();
+        assert_eq!(summaries.len(), 3);
+        assert!(!dir.join("movie.mkv.segments").exists(), "Segments should be removed");
+    }
+
+    #[tokio::test]
+    async fn test_segmented_run_reports_failed_segment() {
+        let dir = TempDir::new("segmented-failure");
+        let path = FakeHandBrake::new().exit_code(2).install(&dir).unwrap();
+
+        let result = JobBuilder::new(path, "in.mkv".into(), dir.join("movie.mkv").into())
+            .segmented()
+            .segment(Segment::Chapters(1..=1))
+            .ffmpeg_path("true")
+            .run()
+            .await;
+        assert!(result.unwrap_err().to_string().contains("Segment 1 failed"));
+    }
+
+    #[tokio::test]
+    async fn test_segmented_resume_skips_completed_segments() {
+        let dir = TempDir::new("segmented-resume");
+        let output = dir.join("movie.mkv");
+        let segments = dir.join("movie.mkv.segments");
+        let _ = std::fs::remove_dir_all(&segments);
+        let succeeding = FakeHandBrake::new().install(&dir).unwrap();
+
+        let encode = |path: PathBuf, ffmpeg: &str| {
+            JobBuilder::new(path, "in.mkv".into(), output.clone().into())
+                .segmented()
+                .chapter_segments(1..=2, 1)
+                .ffmpeg_path(ffmpeg)
+                .resume(true)
+        };
+        // The concatenation fails, which keeps the completed segments.
+        assert!(encode(succeeding.clone(), "false").run().await.is_err());
+        // The fake doesn't write its output, so create the segment files it would have written.
+        std::fs::write(segments.join("segment-001.mkv"), "").unwrap();
+        std::fs::write(segments.join("segment-002.mkv"), "").unwrap();
+
+        let failing = FakeHandBrake::new().exit_code(2).install(&dir).unwrap();
+        assert_eq!(failing, succeeding);
+        let summaries = encode(failing.clone(), "true").run().await.unwrap();
+        assert!(summaries.is_empty(), "Completed segments should be skipped");
+
+        // Segments without a record are encoded again.
+        let encode = encode(failing, "true");
+        std::fs::create_dir_all(&segments).unwrap();
+        std::fs::write(segments.join("segment-001.mkv"), "").unwrap();
+        assert!(encode.run().await.is_err());
+    }
+}
@@ -0,0 +1,81 @@
+//! Tests for the HTTP `StatusServer`, with `/bin/sh` as a stand-in for `HandBrakeCLI`.
+#![cfg(all(feature = "status-server", unix))]
+
+use handbrake::{JobBuilder, StatusServer};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+async fn start_server() -> (StatusServer, SocketAddr) {
+    let server = StatusServer::new();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn({
+        let server = server.clone();
+        async move { server.serve(listener).await }
+    });
+    (server, addr)
+}
+
+async fn get(addr: SocketAddr, path: &str) -> TcpStream {
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", path);
+    stream.write_all(request.as_bytes()).await.unwrap();
+    stream
+}
+
+#[tokio::test]
+async fn test_jobs_status() {
+    let (server, addr) = start_server().await;
+    let handle = JobBuilder::new("/bin/sh".into(), "in.mkv".into(), "out.mp4".into())
+        .start_with_args(["-c", r"printf 'Encoding: task 1 of 1, 42.50 %%\r'"])
+        .unwrap();
+    let id = server.track("movie", &handle.controller());
+    handle.wait().await.unwrap();
+
+    let mut response = String::new();
+    get(addr, "/jobs").await.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.contains(r#""name":"movie","state":"exited","exit_code":0"#));
+    assert!(response.contains(r#""percentage":42.5"#));
+
+    server.untrack(id);
+    let mut response = String::new();
+    get(addr, "/jobs").await.read_to_string(&mut response).await.unwrap();
+    assert!(response.ends_with("[]"));
+
+    let mut response = String::new();
+    get(addr, "/unknown").await.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 404 Not Found"));
+}
+
+#[tokio::test]
+async fn test_events_stream() {
+    let (server, addr) = start_server().await;
+    let mut events = BufReader::new(get(addr, "/events").await);
+    let mut line = String::new();
+    // Wait for the headers, so that the client is subscribed before the job starts.
+    while line != "\r\n" {
+        line.clear();
+        events.read_line(&mut line).await.unwrap();
+    }
+
+    let handle = JobBuilder::new("/bin/sh".into(), "in.mkv".into(), "out.mp4".into())
+        .start_with_args(["-c", r"sleep 0.1; printf 'Encoding: task 1 of 1, 10.00 %%\r'"])
+        .unwrap();
+    server.track("movie", &handle.controller());
+
+    let mut received = String::new();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !received.contains(r#""state":"#) {
+            events.read_line(&mut received).await.unwrap();
+        }
+    })
+    .await
+    .expect("The done event should be streamed");
+    assert!(received.contains("event: progress"));
+    assert!(received.contains(r#""percentage":10.0"#));
+    assert!(received.contains("event: done"));
+    assert!(received.contains(r#""exit_code":0"#));
+}